@@ -0,0 +1,78 @@
+//! Compares the iterative `binary_search` against the recursive form it
+//! replaced, over cheap predicates where the search itself dominates.
+//!
+//! Run with `cargo bench --bench core`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use binary_search::{binary_search, Betweenable, Direction};
+
+fn recursive<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    mut f: F,
+  ) -> ((X, A), (X, B))
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<A, B> {
+  match X::between(low.0, high.0) {
+    None => (low, high),
+    Some(x) => {
+      match (f)(x) {
+        Direction::Low(low) => recursive((x, low), high, f),
+        Direction::High(high) => recursive(low, (x, high), f),
+      }
+    },
+  }
+}
+
+fn time<F>(name: &str, iterations: u32, mut f: F) -> Duration
+  where
+    F: FnMut(u32) {
+  let start = Instant::now();
+  for i in 0..iterations {
+    (f)(i);
+  }
+  let elapsed = start.elapsed();
+  println!("{:>24}: {:?} per search", name, elapsed / iterations);
+  elapsed
+}
+
+fn threshold<X>(t: X) -> impl Fn(X) -> Direction<(), ()>
+  where
+    X: PartialOrd {
+  move |x| {
+    if x < t {
+      Direction::Low(())
+    } else {
+      Direction::High(())
+    }
+  }
+}
+
+fn main() {
+  let iterations = 2_000_000;
+
+  println!("u32");
+  let key = |i: u32| i.wrapping_mul(2_654_435_761);
+  time("iterative", iterations, |i| {
+    let f = threshold(key(i));
+    black_box(binary_search((0, ()), (u32::MAX, ()), black_box(f)));
+  });
+  time("recursive", iterations, |i| {
+    let f = threshold(key(i));
+    black_box(recursive((0, ()), (u32::MAX, ()), black_box(f)));
+  });
+
+  println!("u128");
+  let key = |i: u32| u128::from(i).wrapping_mul(0x9e37_79b9_7f4a_7c15) << 64;
+  time("iterative", iterations, |i| {
+    let f = threshold(key(i));
+    black_box(binary_search((0, ()), (u128::MAX, ()), black_box(f)));
+  });
+  time("recursive", iterations, |i| {
+    let f = threshold(key(i));
+    black_box(recursive((0, ()), (u128::MAX, ()), black_box(f)));
+  });
+}
@@ -0,0 +1,52 @@
+//! Compares the specialized slice lookups against `slice::partition_point`.
+//!
+//! Run with `cargo bench --bench lookup`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use binary_search::eytzinger::Eytzinger;
+use binary_search::fast;
+use binary_search::sentinel::Padded;
+use binary_search::uniform;
+
+fn time<F>(name: &str, iterations: u32, mut f: F) -> Duration
+  where
+    F: FnMut(u32) {
+  let start = Instant::now();
+  for i in 0..iterations {
+    (f)(i);
+  }
+  let elapsed = start.elapsed();
+  println!("{:>24}: {:?} per lookup", name, elapsed / iterations);
+  elapsed
+}
+
+fn main() {
+  let iterations = 2_000_000;
+  for &len in [1_000_u32, 1_024, 100_000, 131_072, 1_000_000].iter() {
+    let values: Vec<u32> = (0..len).map(|i| i * 2).collect();
+    let padded = Padded::new(values.clone());
+    let eytzinger = Eytzinger::new(values.clone());
+    let mask = (2 * len).next_power_of_two() - 1;
+    let key = |i: u32| i.wrapping_mul(2_654_435_761) & mask;
+
+    println!("len = {}", len);
+    time("partition_point", iterations, |i| {
+      let key = key(i);
+      black_box(values.partition_point(|&x| x < key));
+    });
+    time("Padded::lower_bound", iterations, |i| {
+      black_box(padded.lower_bound(key(i)));
+    });
+    time("uniform::lower_bound", iterations, |i| {
+      black_box(uniform::lower_bound(&values, &key(i)));
+    });
+    time("fast::lower_bound", iterations, |i| {
+      black_box(fast::lower_bound(&values, key(i)));
+    });
+    time("Eytzinger::lower_bound", iterations, |i| {
+      black_box(eytzinger.lower_bound(&key(i)));
+    });
+  }
+}
@@ -0,0 +1,46 @@
+//! Splitting a fixed budget between two uses, such as memory between a
+//! cache and a buffer, where giving more to one leaves less for the other.
+
+use core::ops::Sub;
+
+use crate::{binary_search, Betweenable, Bracket, Direction};
+
+/// Find where feasibility flips as `x` of `budget` goes to the first use
+/// and `budget - x` to the second.
+///
+/// `f(x, budget - x)` must be monotone in `x`, as it is when the first use
+/// gets more of what it needs and the second less as `x` grows. `low` and
+/// `high` bound the split, as with `binary_search`, and are typically zero
+/// and `budget`, with witnesses for each side. Returns the largest `Low`
+/// split and the smallest `High` one.
+pub fn split_budget<X, A, B, F>(
+    budget: X,
+    low: (X, A),
+    high: (X, B),
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable + Sub<Output = X>,
+    F: FnMut(X, X) -> Direction<A, B> {
+  binary_search(low, high, |x| (f)(x, budget - x))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_the_split() {
+    // Reads need 300 of the 1000 workers; writes need 200.
+    let ((low, reads_starved), (high, writes_starved)) =
+      split_budget(1000_u32, (0, 0), (1000, 0), |reads, writes| {
+        if reads < 300 {
+          Direction::Low(300 - reads)
+        } else {
+          Direction::High(200_u32.saturating_sub(writes))
+        }
+      });
+    assert_eq!((low, reads_starved), (299, 1));
+    assert_eq!((high, writes_starved), (300, 0));
+  }
+}
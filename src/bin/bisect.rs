@@ -0,0 +1,115 @@
+//! Bisect an integer range by running a command for each probe.
+//!
+//! ```text
+//! bisect [--resume FILE] [--max-probes N] [--trace] LOW HIGH -- CMD ARGS..
+//! ```
+//!
+//! `LOW` is taken to be good and `HIGH` bad. The command is run with the
+//! probed value in `BISECT_VALUE`, and in place of any `{}` argument. As
+//! with `git bisect run`, exiting with 0 means good, 125 means the value
+//! can't be tested, and anything else up to 127 means bad; any other exit
+//! aborts the search.
+//!
+//! With `--resume`, every result is appended to `FILE`, and results already
+//! in it are reused rather than run again, so an interrupted search can be
+//! picked up where it left off.
+
+use std::process;
+
+use binary_search::runner::Runner;
+
+struct Options {
+  resume: Option<String>,
+  max_probes: Option<usize>,
+  trace: bool,
+  low: i64,
+  high: i64,
+  command: Vec<String>,
+}
+
+const USAGE: &str =
+  "usage: bisect [--resume FILE] [--max-probes N] [--trace] \
+   LOW HIGH -- CMD ARGS..";
+
+fn parse_args<I>(mut args: I) -> Result<Options, String>
+  where
+    I: Iterator<Item = String> {
+  let mut resume = None;
+  let mut max_probes = None;
+  let mut trace = false;
+  let mut bounds = Vec::new();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--resume" => {
+        resume = Some(args.next().ok_or("--resume needs a file")?);
+      },
+      "--max-probes" => {
+        let n = args.next().ok_or("--max-probes needs a count")?;
+        max_probes =
+          Some(n.parse().map_err(|_| format!("bad probe count {:?}", n))?);
+      },
+      "--trace" => trace = true,
+      "--" => break,
+      _ => {
+        let bound =
+          arg.parse().map_err(|_| format!("bad bound {:?}", arg))?;
+        bounds.push(bound);
+      },
+    }
+  }
+  let command: Vec<String> = args.collect();
+  match (bounds.as_slice(), command.is_empty()) {
+    (&[low, high], false) => {
+      Ok(Options { resume, max_probes, trace, low, high, command })
+    },
+    _ => Err(USAGE.to_string()),
+  }
+}
+
+fn bisect(options: &Options) -> Result<i32, String> {
+  let mut runner =
+    Runner::new(options.low, options.high, options.command.iter().cloned());
+  if let Some(path) = &options.resume {
+    runner = runner.resume(path);
+  }
+  if let Some(max) = options.max_probes {
+    runner = runner.max_probes(max);
+  }
+  let report =
+    runner
+      .run(|x, (low, high), verdict| {
+        if options.trace {
+          eprintln!("probe {} in ({}, {}): {}", x, low, high, verdict.name());
+        }
+      })
+      .map_err(|e| e.to_string())?;
+
+  if report.exhausted {
+    println!("probe budget exhausted");
+    println!("last good: {}", report.last_good);
+    println!("first bad: {}", report.first_bad);
+    return Ok(3);
+  }
+  println!("last good: {}", report.last_good);
+  println!("first bad: {}", report.first_bad);
+  if report.untestable.is_empty() {
+    Ok(0)
+  } else {
+    let untested: Vec<String> =
+      report.untestable.iter().map(|x| x.to_string()).collect();
+    println!("untestable in between: {}", untested.join(" "));
+    Ok(2)
+  }
+}
+
+fn main() {
+  let code =
+    parse_args(std::env::args().skip(1)).and_then(|options| bisect(&options));
+  match code {
+    Ok(code) => process::exit(code),
+    Err(message) => {
+      eprintln!("bisect: {}", message);
+      process::exit(1);
+    },
+  }
+}
@@ -0,0 +1,61 @@
+//! The searches of Python's `bisect` module, for code being ported from it
+//! or keeping a `Vec` sorted by hand.
+
+use crate::partition_point;
+
+/// Find where `x` would be inserted in sorted `slice` to go before any
+/// elements equal to it.
+pub fn bisect_left<T>(slice: &[T], x: &T) -> usize
+  where
+    T: Ord {
+  partition_point(slice.len(), |i| slice.get(i).is_some_and(|y| y < x))
+}
+
+/// Find where `x` would be inserted in sorted `slice` to go after any
+/// elements equal to it.
+pub fn bisect_right<T>(slice: &[T], x: &T) -> usize
+  where
+    T: Ord {
+  partition_point(slice.len(), |i| slice.get(i).is_some_and(|y| y <= x))
+}
+
+/// Insert `x` into sorted `vec` before any elements equal to it, and return
+/// where it went.
+pub fn insort_left<T>(vec: &mut Vec<T>, x: T) -> usize
+  where
+    T: Ord {
+  let index = bisect_left(vec, &x);
+  vec.insert(index, x);
+  index
+}
+
+/// Insert `x` into sorted `vec` after any elements equal to it, and return
+/// where it went.
+pub fn insort_right<T>(vec: &mut Vec<T>, x: T) -> usize
+  where
+    T: Ord {
+  let index = bisect_right(vec, &x);
+  vec.insert(index, x);
+  index
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_python() {
+    let values = [1, 2, 2, 2, 5];
+    assert_eq!(bisect_left(&values, &2), 1);
+    assert_eq!(bisect_right(&values, &2), 4);
+    assert_eq!(bisect_left(&values, &0), 0);
+    assert_eq!(bisect_right(&values, &9), 5);
+    assert_eq!(bisect_left(&[], &9), 0);
+
+    let mut pairs = vec![(1, 'a'), (3, 'b')];
+    assert_eq!(insort_left(&mut pairs, (2, 'c')), 1);
+    assert_eq!(insort_right(&mut pairs, (3, 'b')), 3);
+    assert_eq!(insort_left(&mut pairs, (0, 'z')), 0);
+    assert_eq!(pairs, vec![(0, 'z'), (1, 'a'), (2, 'c'), (3, 'b'), (3, 'b')]);
+  }
+}
@@ -0,0 +1,244 @@
+//! Tracing the boundary of a region that is monotone in several
+//! parameters, such as the combinations of thread count and batch size a
+//! service can sustain.
+
+use std::ops::Range;
+
+use crate::{partition_point, Direction};
+
+/// Find the maximal points of the region of the box `0..dims[0]`,
+/// `0..dims[1]`, ... for which `is_low` holds.
+///
+/// `is_low` must be monotone in every coordinate: if it holds at a point,
+/// it holds at every point below it. The maximal points are those where it
+/// holds but no longer does after a step up in any one coordinate, and
+/// together they describe the whole region. They're returned in
+/// lexicographic order.
+///
+/// The last two coordinates are traced as a staircase, taking a pair of
+/// bisections per step, so in two dimensions the number of probes is
+/// proportional to the number of maximal points times the log of the box
+/// size. Each further coordinate multiplies that by how far the region
+/// extends along it.
+pub fn maximal_points<F>(dims: &[usize], mut is_low: F) -> Vec<Vec<usize>>
+  where
+    F: FnMut(&[usize]) -> bool {
+  let mut points = Vec::new();
+  trace(dims, &mut Vec::new(), &mut is_low, &mut points);
+  points
+}
+
+/// Find the maximal points among those starting with `prefix`, over the
+/// remaining coordinates `dims`.
+fn trace<F>(
+    dims: &[usize],
+    prefix: &mut Vec<usize>,
+    is_low: &mut F,
+    points: &mut Vec<Vec<usize>>,
+  )
+  where
+    F: FnMut(&[usize]) -> bool {
+  let mut at = |prefix: &mut Vec<usize>, coordinates: &[usize]| {
+    let n = prefix.len();
+    prefix.extend_from_slice(coordinates);
+    let low = (is_low)(prefix);
+    prefix.truncate(n);
+    low
+  };
+  match *dims {
+    [] => {
+      if (at)(prefix, &[]) {
+        points.push(prefix.clone());
+      }
+    },
+    [n] => {
+      let count = partition_point(n, |x| (at)(prefix, &[x]));
+      if let Some(x) = count.checked_sub(1) {
+        points.push([prefix.as_slice(), &[x]].concat());
+      }
+    },
+    [n, m] => {
+      // Each step finds the highest `y` reached at `x`, and then the last
+      // `x` that still reaches it, which is a corner of the staircase.
+      let mut x = 0;
+      while x < n {
+        let height = partition_point(m, |y| (at)(prefix, &[x, y]));
+        let y =
+          match height.checked_sub(1) {
+            Some(y) => y,
+            None => break,
+          };
+        let run = partition_point(n - x, |i| (at)(prefix, &[x + i, y]));
+        x += run;
+        points.push([prefix.as_slice(), &[x - 1, y]].concat());
+      }
+    },
+    [n, ref rest @ ..] => {
+      let origin = vec![0; rest.len()];
+      let extent =
+        partition_point(n, |x| {
+          prefix.push(x);
+          let low = (at)(prefix, &origin);
+          prefix.pop();
+          low
+        });
+      for x in 0..extent {
+        prefix.push(x);
+        let mut slice = Vec::new();
+        trace(rest, prefix, is_low, &mut slice);
+        prefix.pop();
+        // A maximal point of this slice is only maximal overall if the
+        // next slice doesn't also contain it.
+        for mut point in slice {
+          let depth = prefix.len();
+          let maximal =
+            x + 1 == extent || {
+              if let Some(c) = point.get_mut(depth) {
+                *c += 1;
+              }
+              let low = (is_low)(&point);
+              if let Some(c) = point.get_mut(depth) {
+                *c -= 1;
+              }
+              !low
+            };
+          if maximal {
+            points.push(point);
+          }
+        }
+      }
+    },
+  }
+}
+
+/// A step in the frontier traced by `search_2d`. From column `x` until the
+/// next step, the low cells of each column are those below row `y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step<A, B> {
+  pub x: usize,
+  pub y: usize,
+  /// The outcome at `(x, y - 1)`, unless no cell in the column is low.
+  pub low: Option<A>,
+  /// The outcome at `(x, y)`, unless every cell in the column is low.
+  pub high: Option<B>,
+}
+
+/// Trace the frontier between the low and high cells of `xs` by `ys`,
+/// such as a sorted matrix, by saddleback search.
+///
+/// `f` must be monotone in both coordinates: if a cell is low, so is every
+/// cell below or to the left of it. The frontier is then a staircase
+/// descending from left to right, which is returned as the columns where
+/// it steps down, starting with the first column. The walk starts at the
+/// top of the first column and moves down or right with each probe, so it
+/// takes at most `xs.len() + ys.len()` of them, which suits frontiers with
+/// many steps better than the bisections of `maximal_points`.
+pub fn search_2d<A, B, F>(
+    xs: Range<usize>,
+    ys: Range<usize>,
+    mut f: F,
+  ) -> Vec<Step<A, B>>
+  where
+    F: FnMut(usize, usize) -> Direction<A, B> {
+  let mut steps = Vec::new();
+  let mut y = ys.end;
+  for x in xs.clone() {
+    let mut low = None;
+    let mut high = None;
+    while let Some(below) = y.checked_sub(1).filter(|&b| b >= ys.start) {
+      match f(x, below) {
+        Direction::Low(a) => {
+          low = Some(a);
+          break;
+        },
+        Direction::High(b) => {
+          high = Some(b);
+          y = below;
+        },
+      }
+    }
+    if x == xs.start || high.is_some() {
+      steps.push(Step { x, y, low, high });
+    }
+    if y <= ys.start {
+      // Every later column is entirely high.
+      break;
+    }
+  }
+  steps
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn brute_force<F>(dims: &[usize], is_low: F) -> Vec<Vec<usize>>
+    where
+      F: Fn(&[usize]) -> bool {
+    let mut points = vec![vec![]];
+    for &d in dims {
+      points =
+        points.into_iter()
+          .flat_map(|p| (0..d).map(move |x| [p.clone(), vec![x]].concat()))
+          .collect();
+    }
+    points.into_iter().filter(|p| {
+      is_low(p) && (0..p.len()).all(|i| {
+        let mut q = p.clone();
+        q[i] += 1;
+        q[i] >= dims[i] || !is_low(&q)
+      })
+    }).collect()
+  }
+
+  #[test]
+  fn traces_staircase_cheaply() {
+    let is_low = |p: &[usize]| p[0] * p[0] + p[1] * p[1] < 10_000;
+    let mut probes = 0;
+    let points =
+      maximal_points(&[1000, 1000], |p| {
+        probes += 1;
+        is_low(p)
+      });
+    assert_eq!(points, brute_force(&[1000, 1000], is_low));
+    assert_eq!(points.first(), Some(&vec![14, 99]));
+    assert!(probes < points.len() * 40, "{} probes", probes);
+  }
+
+  #[test]
+  fn matches_brute_force() {
+    type Case = (&'static [usize], fn(&[usize]) -> bool);
+    let cases: [Case; 4] = [
+      (&[4, 5, 6], |p| p[0] + p[1] + p[2] <= 5),
+      (&[3, 3, 3, 3], |p| p[0] * 2 + p[1] + p[2] * p[3] <= 4),
+      (&[7], |p| p[0] < 3),
+      (&[2, 2], |_| false),
+    ];
+    for (dims, is_low) in cases.iter() {
+      assert_eq!(maximal_points(dims, is_low), brute_force(dims, is_low));
+    }
+  }
+
+  #[test]
+  fn saddleback_traces_staircase() {
+    let is_low = |x: usize, y: usize| x * x + y * y < 2_000;
+    let mut probes = 0;
+    let steps =
+      search_2d(3..100, 5..60, |x, y| {
+        probes += 1;
+        if is_low(x, y) { Direction::Low((x, y)) } else { Direction::High(()) }
+      });
+    assert!(probes <= 97 + 55, "{} probes", probes);
+    for (i, step) in steps.iter().enumerate() {
+      let end = steps.get(i + 1).map_or(100, |next| next.x);
+      for x in step.x..end {
+        let y = (5..60).take_while(|&y| is_low(x, y)).count() + 5;
+        assert_eq!(step.y, y, "column {}", x);
+      }
+      assert_eq!(step.low, (step.y > 5).then(|| (step.x, step.y - 1)));
+      assert_eq!(step.high.is_some(), step.y < 60);
+    }
+    assert_eq!(steps.first().map(|s| (s.x, s.y)), Some((3, 45)));
+    assert_eq!(steps.last().map(|s| (s.x, s.y)), Some((45, 5)));
+  }
+}
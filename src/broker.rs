@@ -0,0 +1,340 @@
+//! Sharing one expensive predicate between concurrent searches, such as
+//! several thresholds being found against the same deployed system.
+//!
+//! A broker remembers the outcome of every probe. A search that probes a
+//! position another search is already probing waits for that probe to
+//! finish instead of making it again, and then both get its outcome. The
+//! outcome is kept separate from each search's direction, so searches for
+//! different thresholds can share the same measurements.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::{Betweenable, Bracket, Direction};
+
+#[cfg(feature = "async")]
+use std::{future::Future, task::{Poll, Waker}};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  // A panicking probe leaves the map consistent, as its slot is removed as
+  // the panic unwinds.
+  mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+enum Slot<R> {
+  Probing,
+  Done(R),
+}
+
+/// A broker for searches running on separate threads.
+pub struct Broker<X, R> {
+  slots: Mutex<HashMap<X, Slot<R>>>,
+  finished: Condvar,
+}
+
+/// Removes an unfinished probe's slot, so that a waiting search can make
+/// the probe itself if the one making it panics.
+struct Abandon<'a, X, R>
+  where
+    X: Eq + Hash + Copy {
+  broker: &'a Broker<X, R>,
+  x: Option<X>,
+}
+
+impl<'a, X, R> Drop for Abandon<'a, X, R>
+  where
+    X: Eq + Hash + Copy {
+  fn drop(&mut self) {
+    if let Some(x) = self.x {
+      lock(&self.broker.slots).remove(&x);
+      self.broker.finished.notify_all();
+    }
+  }
+}
+
+impl<X, R> Broker<X, R>
+  where
+    X: Eq + Hash + Copy,
+    R: Clone {
+  pub fn new() -> Self {
+    Broker { slots: Mutex::new(HashMap::new()), finished: Condvar::new() }
+  }
+
+  /// The outcome of probing `x`, calling `f` only if no other search has
+  /// probed it or is probing it.
+  pub fn probe<F>(&self, x: X, f: F) -> R
+    where
+      F: FnOnce(X) -> R {
+    let mut slots = lock(&self.slots);
+    loop {
+      match slots.get(&x) {
+        Some(Slot::Done(outcome)) => return outcome.clone(),
+        Some(Slot::Probing) => {
+          slots =
+            self.finished
+              .wait(slots)
+              .unwrap_or_else(PoisonError::into_inner);
+        },
+        None => break,
+      }
+    }
+    slots.insert(x, Slot::Probing);
+    drop(slots);
+
+    let mut abandon = Abandon { broker: self, x: Some(x) };
+    let outcome = f(x);
+    abandon.x = None;
+    lock(&self.slots).insert(x, Slot::Done(outcome.clone()));
+    self.finished.notify_all();
+    outcome
+  }
+
+  /// Like `binary_search`, but probes go through the broker. `measure`
+  /// makes a probe, and `decide` turns its outcome into this search's
+  /// direction.
+  pub fn binary_search<A, B, M, D>(
+      &self,
+      mut low: (X, A),
+      mut high: (X, B),
+      mut measure: M,
+      mut decide: D,
+    ) -> Bracket<X, A, B>
+    where
+      X: Betweenable,
+      M: FnMut(X) -> R,
+      D: FnMut(&R) -> Direction<A, B> {
+    while let Some(x) = X::between(low.0, high.0) {
+      match decide(&self.probe(x, &mut measure)) {
+        Direction::Low(a) => {
+          low = (x, a);
+        },
+        Direction::High(b) => {
+          high = (x, b);
+        },
+      }
+    }
+    (low, high)
+  }
+}
+
+impl<X, R> Default for Broker<X, R>
+  where
+    X: Eq + Hash + Copy,
+    R: Clone {
+  fn default() -> Self {
+    Broker::new()
+  }
+}
+
+#[cfg(feature = "async")]
+enum AsyncSlot<R> {
+  Probing(Vec<Waker>),
+  Done(R),
+}
+
+/// A broker for searches running as futures, which may be on one thread.
+#[cfg(feature = "async")]
+pub struct AsyncBroker<X, R> {
+  slots: Mutex<HashMap<X, AsyncSlot<R>>>,
+}
+
+/// Removes an unfinished probe's slot, so that a waiting search can make
+/// the probe itself if the one making it is dropped.
+#[cfg(feature = "async")]
+struct AbandonAsync<'a, X, R>
+  where
+    X: Eq + Hash + Copy {
+  broker: &'a AsyncBroker<X, R>,
+  x: Option<X>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, X, R> Drop for AbandonAsync<'a, X, R>
+  where
+    X: Eq + Hash + Copy {
+  fn drop(&mut self) {
+    if let Some(x) = self.x {
+      if let Some(AsyncSlot::Probing(waiters)) =
+        lock(&self.broker.slots).remove(&x) {
+        waiters.into_iter().for_each(Waker::wake);
+      }
+    }
+  }
+}
+
+#[cfg(feature = "async")]
+impl<X, R> AsyncBroker<X, R>
+  where
+    X: Eq + Hash + Copy,
+    R: Clone {
+  pub fn new() -> Self {
+    AsyncBroker { slots: Mutex::new(HashMap::new()) }
+  }
+
+  /// The outcome of probing `x`, awaiting `f` only if no other search has
+  /// probed it or is probing it.
+  pub async fn probe<F, Fut>(&self, x: X, f: F) -> R
+    where
+      F: FnOnce(X) -> Fut,
+      Fut: Future<Output = R> {
+    let settled =
+      std::future::poll_fn(|context| {
+        let mut slots = lock(&self.slots);
+        match slots.get_mut(&x) {
+          Some(AsyncSlot::Done(outcome)) => {
+            Poll::Ready(Some(outcome.clone()))
+          },
+          Some(AsyncSlot::Probing(waiters)) => {
+            waiters.push(context.waker().clone());
+            Poll::Pending
+          },
+          None => {
+            slots.insert(x, AsyncSlot::Probing(Vec::new()));
+            Poll::Ready(None)
+          },
+        }
+      }).await;
+    if let Some(outcome) = settled {
+      return outcome;
+    }
+
+    let mut abandon = AbandonAsync { broker: self, x: Some(x) };
+    let outcome = f(x).await;
+    abandon.x = None;
+    let previous =
+      lock(&self.slots).insert(x, AsyncSlot::Done(outcome.clone()));
+    if let Some(AsyncSlot::Probing(waiters)) = previous {
+      waiters.into_iter().for_each(Waker::wake);
+    }
+    outcome
+  }
+
+  /// Like `Broker::binary_search`, but `measure` is asynchronous.
+  pub async fn binary_search<A, B, M, Fut, D>(
+      &self,
+      mut low: (X, A),
+      mut high: (X, B),
+      mut measure: M,
+      mut decide: D,
+    ) -> Bracket<X, A, B>
+    where
+      X: Betweenable,
+      M: FnMut(X) -> Fut,
+      Fut: Future<Output = R>,
+      D: FnMut(&R) -> Direction<A, B> {
+    while let Some(x) = X::between(low.0, high.0) {
+      match decide(&self.probe(x, &mut measure).await) {
+        Direction::Low(a) => {
+          low = (x, a);
+        },
+        Direction::High(b) => {
+          high = (x, b);
+        },
+      }
+    }
+    (low, high)
+  }
+}
+
+#[cfg(feature = "async")]
+impl<X, R> Default for AsyncBroker<X, R>
+  where
+    X: Eq + Hash + Copy,
+    R: Clone {
+  fn default() -> Self {
+    AsyncBroker::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn shares_probes_between_threads() {
+    let broker = Broker::new();
+    let measured = Mutex::new(Vec::new());
+    let latency = |x: u32| {
+      measured.lock().unwrap().push(x);
+      std::thread::sleep(std::time::Duration::from_millis(1));
+      x * 3
+    };
+    let thresholds = [30, 90, 150, 151, 240];
+    let results =
+      std::thread::scope(|scope| {
+        let searches: Vec<_> =
+          thresholds.iter().map(|&threshold| {
+            let broker = &broker;
+            scope.spawn(move || {
+              broker.binary_search((0, ()), (100, ()), latency, |&l| {
+                if l < threshold {
+                  Direction::Low(())
+                } else {
+                  Direction::High(())
+                }
+              })
+            })
+          }).collect();
+        searches.into_iter().map(|s| s.join().unwrap()).collect::<Vec<_>>()
+      });
+    let expected: Vec<_> =
+      thresholds.iter().map(|&threshold| {
+        let x = threshold.div_ceil(3);
+        ((x - 1, ()), (x, ()))
+      }).collect();
+    assert_eq!(results, expected);
+
+    let mut measured = measured.into_inner().unwrap();
+    let calls = measured.len();
+    measured.sort();
+    measured.dedup();
+    assert_eq!(measured.len(), calls);
+    assert!(calls < thresholds.len() * 7);
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn fans_out_in_flight_async_probes() {
+    use std::cell::Cell;
+    use std::pin::pin;
+    use std::task::Context;
+
+    let broker = AsyncBroker::new();
+    let calls = Cell::new(0);
+    let slow = |x: u32| {
+      calls.set(calls.get() + 1);
+      let mut polled = false;
+      std::future::poll_fn(move |context| {
+        if polled {
+          Poll::Ready(x * 2)
+        } else {
+          polled = true;
+          context.waker().wake_by_ref();
+          Poll::Pending
+        }
+      })
+    };
+    let mut context = Context::from_waker(Waker::noop());
+    let mut first = pin!(broker.probe(7, slow));
+    let mut second = pin!(broker.probe(7, slow));
+    assert_eq!(first.as_mut().poll(&mut context), Poll::Pending);
+    assert_eq!(second.as_mut().poll(&mut context), Poll::Pending);
+    assert_eq!(first.as_mut().poll(&mut context), Poll::Ready(14));
+    assert_eq!(second.as_mut().poll(&mut context), Poll::Ready(14));
+    assert_eq!(calls.get(), 1);
+
+    let mut search =
+      pin!(broker.binary_search((0, ()), (100, ()), slow, |&y| {
+        if y < 14 { Direction::Low(()) } else { Direction::High(()) }
+      }));
+    let result =
+      loop {
+        if let Poll::Ready(result) = search.as_mut().poll(&mut context) {
+          break result;
+        }
+      };
+    assert_eq!(result, ((6, ()), (7, ())));
+    assert_eq!(calls.get(), 6);
+  }
+}
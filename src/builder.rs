@@ -0,0 +1,231 @@
+//! A builder for configuring a search, for when there are more options than
+//! fit comfortably in a function's arguments.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeInclusive;
+
+use crate::{binary_search_with_budget, Betweenable, Direction, SearchResult};
+
+/// Why `try_run` didn't run a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvalidBounds<X> {
+  /// The low end of the range isn't below the high end.
+  Empty { low: X, high: X },
+  /// The ends were checked, and the predicate was high at the low end.
+  LowIsHigh(X),
+  /// The ends were checked, and the predicate was low at the high end.
+  HighIsLow(X),
+}
+
+impl<X> fmt::Display for InvalidBounds<X>
+  where
+    X: fmt::Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      InvalidBounds::Empty { low, high } => {
+        write!(f, "search range {}..={} is empty", low, high)
+      },
+      InvalidBounds::LowIsHigh(x) => {
+        write!(f, "predicate is high at the low end, {}", x)
+      },
+      InvalidBounds::HighIsLow(x) => {
+        write!(f, "predicate is low at the high end, {}", x)
+      },
+    }
+  }
+}
+
+impl<X> core::error::Error for InvalidBounds<X>
+  where
+    X: fmt::Debug + fmt::Display {}
+
+/// A search over `low..=high`, configured a step at a time and then run,
+/// as in `BinarySearch::over(0..=100).max_probes(40).run(f)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BinarySearch<X, A = (), B = ()> {
+  low: (X, A),
+  high: (X, B),
+  max_probes: Option<usize>,
+}
+
+impl<X> BinarySearch<X>
+  where
+    X: Betweenable {
+  /// Search between the ends of `range`, which are taken to be low and
+  /// high respectively, as they are by `binary_search`.
+  pub fn over(range: RangeInclusive<X>) -> Self {
+    let (low, high) = range.into_inner();
+    BinarySearch {
+      low: (low, ()),
+      high: (high, ()),
+      max_probes: None,
+    }
+  }
+}
+
+impl<X, A, B> BinarySearch<X, A, B>
+  where
+    X: Betweenable {
+  /// Use `low` and `high` as the witnesses for the ends of the range.
+  pub fn with_witnesses<C, D>(self, low: C, high: D) -> BinarySearch<X, C, D> {
+    BinarySearch {
+      low: (self.low.0, low),
+      high: (self.high.0, high),
+      max_probes: self.max_probes,
+    }
+  }
+
+  /// Stop after `n` probes, even if the bracket hasn't narrowed to adjacent
+  /// values.
+  pub fn max_probes(mut self, n: usize) -> Self {
+    self.max_probes = Some(n);
+    self
+  }
+
+  /// Probe the ends of the range first, and fail unless the predicate is
+  /// low at the low end and high at the high end. Their witnesses then come
+  /// from those probes, which don't count towards `max_probes`.
+  ///
+  /// The check can fail, so the search it returns only has `try_run`.
+  pub fn check_ends(self) -> CheckedEnds<X, A, B> {
+    CheckedEnds(self)
+  }
+
+  /// Run the search, returning the narrowest bracket found.
+  ///
+  /// The ends of the range are taken to be low and high as they are, even
+  /// if they're the wrong way round, which gives them back unchanged. Use
+  /// `try_run` to have that caught.
+  pub fn run<F>(self, f: F) -> SearchResult<X, A, B>
+    where
+      F: FnMut(X) -> Direction<A, B> {
+    let BinarySearch { low, high, max_probes, .. } = self;
+    binary_search_with_budget(low, high, max_probes, || false, f)
+      .bracket()
+      .into()
+  }
+
+  /// Like `run`, but failing if the range is empty.
+  pub fn try_run<F>(
+      self,
+      f: F,
+    ) -> Result<SearchResult<X, A, B>, InvalidBounds<X>>
+    where
+      X: PartialOrd,
+      F: FnMut(X) -> Direction<A, B> {
+    let BinarySearch { low, high, max_probes } = self;
+    if low.0.partial_cmp(&high.0) != Some(Ordering::Less) {
+      return Err(InvalidBounds::Empty { low: low.0, high: high.0 });
+    }
+    let bracket =
+      binary_search_with_budget(low, high, max_probes, || false, f).bracket();
+    Ok(bracket.into())
+  }
+}
+
+/// A `BinarySearch` that checks the ends of its range before it starts, made
+/// by `BinarySearch::check_ends`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CheckedEnds<X, A = (), B = ()>(BinarySearch<X, A, B>);
+
+impl<X, A, B> CheckedEnds<X, A, B>
+  where
+    X: Betweenable {
+  /// Stop after `n` probes besides those of the ends, even if the bracket
+  /// hasn't narrowed to adjacent values.
+  pub fn max_probes(self, n: usize) -> Self {
+    CheckedEnds(self.0.max_probes(n))
+  }
+
+  /// Run the search, failing if the range is empty or the ends aren't low
+  /// and high.
+  pub fn try_run<F>(
+      self,
+      mut f: F,
+    ) -> Result<SearchResult<X, A, B>, InvalidBounds<X>>
+    where
+      X: PartialOrd,
+      F: FnMut(X) -> Direction<A, B> {
+    let BinarySearch { mut low, mut high, max_probes } = self.0;
+    if low.0.partial_cmp(&high.0) != Some(Ordering::Less) {
+      return Err(InvalidBounds::Empty { low: low.0, high: high.0 });
+    }
+    match (f)(low.0) {
+      Direction::Low(a) => low.1 = a,
+      Direction::High(_) => return Err(InvalidBounds::LowIsHigh(low.0)),
+    }
+    match (f)(high.0) {
+      Direction::Low(_) => return Err(InvalidBounds::HighIsLow(high.0)),
+      Direction::High(b) => high.1 = b,
+    }
+    BinarySearch { low, high, max_probes }.try_run(f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builds_and_runs_searches() {
+    let threshold = |x: u64| {
+      if x < 777 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let result = BinarySearch::over(0..=1000).run(threshold);
+    assert_eq!(result.into_tuple(), ((776, ()), (777, ())));
+
+    let mut probes = 0;
+    let result =
+      BinarySearch::over(0..=1000).max_probes(4).run(|x| {
+        probes += 1;
+        threshold(x)
+      });
+    assert_eq!(probes, 4);
+    assert_eq!((result.largest_low, result.smallest_high), (750, 812));
+
+    let result =
+      BinarySearch::over(0..=1000)
+        .with_witnesses(0, 1000)
+        .max_probes(40)
+        .run(|x| if x < 777 { Direction::Low(x) } else { Direction::High(x) });
+    assert_eq!(result.into_tuple(), ((776, 776), (777, 777)));
+
+    let witnessed = |x| {
+      if x < 777 { Direction::Low(x) } else { Direction::High(x) }
+    };
+    let (low, high) = (1000, 0);
+    assert_eq!(
+      BinarySearch::over(low..=high).try_run(threshold),
+      Err(InvalidBounds::Empty { low: 1000, high: 0 }),
+    );
+    assert_eq!(
+      BinarySearch::over(800..=1000).check_ends().try_run(threshold),
+      Err(InvalidBounds::LowIsHigh(800)),
+    );
+    assert_eq!(
+      BinarySearch::over(0..=500).check_ends().try_run(threshold),
+      Err(InvalidBounds::HighIsLow(500)),
+    );
+    let result =
+      BinarySearch::over(0..=1000)
+        .with_witnesses(0, 0)
+        .check_ends()
+        .try_run(witnessed)
+        .map(SearchResult::into_tuple);
+    assert_eq!(result, Ok(((776, 776), (777, 777))));
+    let mut probes = 0;
+    let result =
+      BinarySearch::over(0..=1000).check_ends().max_probes(4).try_run(|x| {
+        probes += 1;
+        threshold(x)
+      });
+    assert_eq!(probes, 6);
+    let bracket = result.map(|r| (r.largest_low, r.smallest_high));
+    assert_eq!(bracket, Ok((750, 812)));
+    assert_eq!(
+      InvalidBounds::LowIsHigh(800).to_string(),
+      "predicate is high at the low end, 800",
+    );
+  }
+}
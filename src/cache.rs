@@ -0,0 +1,144 @@
+//! Remembering the outcomes of probes between searches, such as a bisection
+//! that's run again over the same range each time new data arrives.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::{binary_search, Betweenable, Bracket, Direction};
+
+/// The outcomes of earlier probes, kept for successive searches to reuse.
+///
+/// With a capacity, the oldest outcome is forgotten to make room for a new
+/// one. The outcomes are only valid while the predicate is unchanged, so
+/// call `clear` when it changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedPredicate<X, A, B>
+  where
+    X: Eq + Hash {
+  outcomes: HashMap<X, Direction<A, B>>,
+  order: VecDeque<X>,
+  capacity: Option<usize>,
+  hits: usize,
+  misses: usize,
+}
+
+impl<X, A, B> CachedPredicate<X, A, B>
+  where
+    X: Eq + Hash + Copy,
+    A: Clone,
+    B: Clone {
+  pub fn new() -> Self {
+    CachedPredicate {
+      outcomes: HashMap::new(),
+      order: VecDeque::new(),
+      capacity: None,
+      hits: 0,
+      misses: 0,
+    }
+  }
+
+  /// A cache that keeps at most `capacity` outcomes.
+  pub fn with_capacity(capacity: usize) -> Self {
+    CachedPredicate { capacity: Some(capacity), ..CachedPredicate::new() }
+  }
+
+  /// The outcome of probing `x`, calling `f` only if it isn't cached.
+  pub fn probe<F>(&mut self, x: X, f: F) -> Direction<A, B>
+    where
+      F: FnOnce(X) -> Direction<A, B> {
+    if let Some(direction) = self.outcomes.get(&x) {
+      self.hits += 1;
+      return direction.clone();
+    }
+    self.misses += 1;
+    let direction = (f)(x);
+    if self.capacity == Some(0) {
+      return direction;
+    }
+    if self.capacity.is_some_and(|capacity| self.outcomes.len() >= capacity) {
+      if let Some(oldest) = self.order.pop_front() {
+        self.outcomes.remove(&oldest);
+      }
+    }
+    self.order.push_back(x);
+    self.outcomes.insert(x, direction.clone());
+    direction
+  }
+
+  /// Like `binary_search`, but probing through the cache.
+  pub fn binary_search<F>(
+      &mut self,
+      low: (X, A),
+      high: (X, B),
+      mut f: F,
+    ) -> Bracket<X, A, B>
+    where
+      X: Betweenable,
+      F: FnMut(X) -> Direction<A, B> {
+    binary_search(low, high, |x| self.probe(x, &mut f))
+  }
+
+  pub fn len(&self) -> usize {
+    self.outcomes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.outcomes.is_empty()
+  }
+
+  /// The number of probes answered from the cache.
+  pub fn hits(&self) -> usize {
+    self.hits
+  }
+
+  /// The number of probes that had to call the predicate.
+  pub fn misses(&self) -> usize {
+    self.misses
+  }
+
+  /// Forget every outcome, for when the predicate has changed.
+  pub fn clear(&mut self) {
+    self.outcomes.clear();
+    self.order.clear();
+  }
+}
+
+impl<X, A, B> Default for CachedPredicate<X, A, B>
+  where
+    X: Eq + Hash + Copy,
+    A: Clone,
+    B: Clone {
+  fn default() -> Self {
+    CachedPredicate::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reuses_probes_across_searches() {
+    let calls = std::cell::Cell::new(0);
+    let mut f = |x: u32| {
+      calls.set(calls.get() + 1);
+      if x < 600 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let mut cache = CachedPredicate::new();
+    let first = cache.binary_search((0, ()), (1000, ()), &mut f);
+    assert_eq!(first, ((599, ()), (600, ())));
+    assert_eq!((cache.hits(), cache.misses()), (0, 10));
+    // A search over a range that's grown probes the same points at first.
+    let second = cache.binary_search((0, ()), (2000, ()), &mut f);
+    assert_eq!(second, ((599, ()), (600, ())));
+    assert!(cache.hits() > 0);
+    assert_eq!(cache.misses(), calls.get());
+
+    let mut small = CachedPredicate::with_capacity(3);
+    small.binary_search((0, ()), (1000, ()), &mut f);
+    assert_eq!(small.len(), 3);
+    assert_eq!(small.probe(600, |_| unreachable!()), Direction::High(()));
+    small.clear();
+    assert!(small.is_empty());
+  }
+}
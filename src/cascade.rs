@@ -0,0 +1,164 @@
+//! Fractional cascading, for finding a key in each of several sorted lists
+//! with a single bisection.
+//!
+//! Searching `k` lists separately takes `O(k log n)` comparisons. A
+//! `Cascade` merges every other element of each list into the list before
+//! it, and records where each merged element falls in both its own list and
+//! the next merged one. Then only the first list is bisected: the position
+//! found there leads to within one place of the position in the next list,
+//! and so on down, for `O(log n + k)` in total. The merged lists take at
+//! most twice the space of the originals.
+
+use crate::partition_point;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Level<T> {
+  /// The elements of this level's list, merged with every other element of
+  /// the next level.
+  values: Vec<T>,
+  /// For each position in `values`, and one past the end, the lower bound
+  /// of the element there in this level's own list.
+  own: Vec<usize>,
+  /// The same, but in the next level's `values`.
+  next: Vec<usize>,
+}
+
+/// Several sorted lists, prepared so that a key can be found in all of
+/// them at once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cascade<T> {
+  levels: Vec<Level<T>>,
+}
+
+/// For each element of `values`, and one past the end, the number of
+/// elements of `list` that are less than it. Both must be sorted.
+fn lower_bounds_in<T>(values: &[T], list: &[T]) -> Vec<usize>
+  where
+    T: Ord {
+  let mut bound = 0;
+  let mut bounds = Vec::with_capacity(values.len() + 1);
+  for value in values {
+    while list.get(bound).is_some_and(|x| x < value) {
+      bound += 1;
+    }
+    bounds.push(bound);
+  }
+  bounds.push(list.len());
+  bounds
+}
+
+impl<T> Cascade<T>
+  where
+    T: Ord + Clone {
+  /// Prepare `lists`, which must each be sorted.
+  pub fn new<L>(lists: &[L]) -> Self
+    where
+      L: AsRef<[T]> {
+    let mut levels: Vec<Level<T>> = Vec::with_capacity(lists.len());
+    for list in lists.iter().rev() {
+      let list = list.as_ref();
+      let sampled: Vec<T> =
+        levels.last()
+          .map(|next| next.values.iter().skip(1).step_by(2).cloned().collect())
+          .unwrap_or_default();
+      let mut values = Vec::with_capacity(list.len() + sampled.len());
+      let mut sampled = sampled.into_iter().peekable();
+      for x in list {
+        while let Some(y) = sampled.next_if(|y| y < x) {
+          values.push(y);
+        }
+        values.push(x.clone());
+      }
+      values.extend(sampled);
+
+      let own = lower_bounds_in(&values, list);
+      let next =
+        levels.last()
+          .map(|next| lower_bounds_in(&values, &next.values))
+          .unwrap_or_default();
+      levels.push(Level { values, own, next });
+    }
+    levels.reverse();
+    Cascade { levels }
+  }
+
+  /// The number of lists.
+  pub fn len(&self) -> usize {
+    self.levels.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.levels.is_empty()
+  }
+
+  /// Find, in each list, the index of the first element that is not less
+  /// than `key`.
+  pub fn lower_bounds(&self, key: &T) -> Vec<usize> {
+    self.search(|x| x < key)
+  }
+
+  /// Find, in each list, the index of the first element that is greater
+  /// than `key`.
+  pub fn upper_bounds(&self, key: &T) -> Vec<usize> {
+    self.search(|x| x <= key)
+  }
+
+  fn search<P>(&self, mut is_low: P) -> Vec<usize>
+    where
+      P: FnMut(&T) -> bool {
+    let mut bounds = Vec::with_capacity(self.levels.len());
+    let mut levels = self.levels.iter().peekable();
+    let mut position =
+      match levels.peek() {
+        Some(first) => {
+          partition_point(first.values.len(), |i| {
+            first.values.get(i).is_some_and(&mut is_low)
+          })
+        },
+        None => return bounds,
+      };
+    while let Some(level) = levels.next() {
+      bounds.push(level.own.get(position).copied().unwrap_or_default());
+      if let Some(next) = levels.peek() {
+        // The element found here is no smaller than the bound in the next
+        // level, and at most one place past it, as every other element of
+        // the next level is also here.
+        position = level.next.get(position).copied().unwrap_or_default();
+        if let Some(previous) = position.checked_sub(1) {
+          if next.values.get(previous).is_some_and(|x| !is_low(x)) {
+            position = previous;
+          }
+        }
+      }
+    }
+    bounds
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_keys_in_every_list() {
+    let lists = vec![
+      vec![2, 4, 4, 9, 15, 20],
+      vec![],
+      vec![1, 3, 5, 7, 9, 11, 13, 15, 17, 19],
+      vec![4, 4, 4, 4],
+      vec![6, 8, 10, 30, 31, 32, 33],
+      vec![0],
+    ];
+    let cascade = Cascade::new(&lists);
+    assert_eq!(cascade.len(), lists.len());
+    for key in -1..=35 {
+      let lower: Vec<_> =
+        lists.iter().map(|l| l.partition_point(|&x| x < key)).collect();
+      let upper: Vec<_> =
+        lists.iter().map(|l| l.partition_point(|&x| x <= key)).collect();
+      assert_eq!(cascade.lower_bounds(&key), lower, "key {}", key);
+      assert_eq!(cascade.upper_bounds(&key), upper, "key {}", key);
+    }
+    assert!(Cascade::<u8>::new::<Vec<u8>>(&[]).lower_bounds(&0).is_empty());
+  }
+}
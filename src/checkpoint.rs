@@ -0,0 +1,191 @@
+//! A compact encoding of a search's bracket, for checkpointing a search in
+//! a few bytes of non-volatile memory and resuming it after a reset.
+//!
+//! Each bound is written as a LEB128 varint, with signed types zigzag
+//! encoded first, except that 8-bit bounds are written as single bytes,
+//! which is the same layout `postcard` uses for a tuple of two integers.
+//! Nothing here allocates, so it suits firmware that can't use the rest of
+//! the crate. A `u32` bracket takes at most 10 bytes, a `u64` one at most
+//! 20, and any bracket fits in `MAX_LEN` bytes.
+
+use core::convert::TryFrom;
+
+/// The most bytes an encoded bracket can take.
+pub const MAX_LEN: usize = 38;
+
+/// Integers that can be written as varints.
+pub trait Varint: Copy {
+  fn to_varint(self) -> u128;
+  fn from_varint(x: u128) -> Option<Self>;
+
+  /// Write the value to the start of `out` as `postcard` does, returning
+  /// the number of bytes written, or `None` if `out` is too short.
+  fn write(self, out: &mut [u8]) -> Option<usize> {
+    write_varint(self.to_varint(), out)
+  }
+
+  /// Read a value written by `write`, with the number of bytes it took.
+  fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+    let (x, n) = read_varint(bytes)?;
+    Some((Self::from_varint(x)?, n))
+  }
+}
+
+macro_rules! impl_varint_unsigned {
+  ($($t:ty),*) => {
+    $(
+      impl Varint for $t {
+        fn to_varint(self) -> u128 {
+          self as u128
+        }
+
+        fn from_varint(x: u128) -> Option<Self> {
+          <$t>::try_from(x).ok()
+        }
+      }
+    )*
+  };
+}
+
+macro_rules! impl_varint_signed {
+  ($($t:ty),*) => {
+    $(
+      impl Varint for $t {
+        fn to_varint(self) -> u128 {
+          let x = self as i128;
+          ((x << 1) ^ (x >> 127)) as u128
+        }
+
+        fn from_varint(x: u128) -> Option<Self> {
+          let x = ((x >> 1) as i128) ^ -((x & 1) as i128);
+          <$t>::try_from(x).ok()
+        }
+      }
+    )*
+  };
+}
+
+impl_varint_unsigned!(u16, u32, u64, u128, usize);
+impl_varint_signed!(i16, i32, i64, i128, isize);
+
+// `postcard` writes 8-bit integers as they are, since a varint would never
+// be shorter.
+macro_rules! impl_varint_byte {
+  ($($t:ty, $varint:ident;)*) => {
+    $(
+      impl Varint for $t {
+        fn to_varint(self) -> u128 {
+          $varint::to_varint(self.into())
+        }
+
+        fn from_varint(x: u128) -> Option<Self> {
+          <$t>::try_from($varint::from_varint(x)?).ok()
+        }
+
+        fn write(self, out: &mut [u8]) -> Option<usize> {
+          *out.first_mut()? = self as u8;
+          Some(1)
+        }
+
+        fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+          Some((*bytes.first()? as $t, 1))
+        }
+      }
+    )*
+  };
+}
+
+impl_varint_byte! {
+  u8, u16;
+  i8, i16;
+}
+
+/// Write the bracket `(low, high)` to the start of `out`, returning the
+/// number of bytes written, or `None` if `out` is too short.
+pub fn encode<X>(low: X, high: X, out: &mut [u8]) -> Option<usize>
+  where
+    X: Varint {
+  let n = low.write(out)?;
+  let m = high.write(out.get_mut(n..)?)?;
+  Some(n + m)
+}
+
+/// Read a bracket written by `encode`.
+pub fn decode<X>(bytes: &[u8]) -> Option<(X, X)>
+  where
+    X: Varint {
+  let (low, n) = X::read(bytes)?;
+  let (high, _) = X::read(bytes.get(n..)?)?;
+  Some((low, high))
+}
+
+pub(crate) fn write_varint(mut x: u128, out: &mut [u8]) -> Option<usize> {
+  let mut n = 0;
+  loop {
+    let byte = out.get_mut(n)?;
+    n += 1;
+    if x < 0x80 {
+      *byte = x as u8;
+      return Some(n);
+    }
+    *byte = (x as u8 & 0x7f) | 0x80;
+    x >>= 7;
+  }
+}
+
+pub(crate) fn read_varint(bytes: &[u8]) -> Option<(u128, usize)> {
+  let mut x: u128 = 0;
+  for (i, &byte) in bytes.iter().enumerate() {
+    let shift = 7 * i as u32;
+    let bits = u128::from(byte & 0x7f);
+    if shift >= 128 || (shift > 121 && bits >> (128 - shift) != 0) {
+      return None;
+    }
+    x |= bits << shift;
+    if byte & 0x80 == 0 {
+      return Some((x, i + 1));
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_compactly() {
+    let mut buffer = [0; MAX_LEN];
+    assert_eq!(encode(3_u32, 300, &mut buffer), Some(3));
+    assert_eq!(buffer[..3], [3, 0xac, 0x02]);
+    assert_eq!(decode::<u32>(&buffer), Some((3, 300)));
+
+    assert_eq!(encode(-1_i16, 1, &mut buffer), Some(2));
+    assert_eq!(buffer[..2], [1, 2]);
+    assert_eq!(decode::<i16>(&buffer), Some((-1, 1)));
+
+    assert_eq!(encode(i128::MIN, i128::MAX, &mut buffer), Some(38));
+    assert_eq!(decode::<i128>(&buffer), Some((i128::MIN, i128::MAX)));
+    assert_eq!(encode(u128::MIN, u128::MAX, &mut buffer), Some(20));
+    assert_eq!(decode::<u128>(&buffer), Some((0, u128::MAX)));
+
+    assert_eq!(encode(1_u64, u64::MAX, &mut buffer[..5]), None);
+    assert_eq!(decode::<u16>(&[0x80, 0x80, 0x04, 0]), None);
+    assert_eq!(decode::<u32>(&[0x80]), None);
+  }
+
+  #[test]
+  fn writes_bytes_as_postcard_does() {
+    let mut buffer = [0; MAX_LEN];
+    assert_eq!(encode(3_u8, 200, &mut buffer), Some(2));
+    assert_eq!(buffer[..2], [3, 200]);
+    assert_eq!(decode::<u8>(&buffer), Some((3, 200)));
+
+    assert_eq!(encode(-128_i8, -1, &mut buffer), Some(2));
+    assert_eq!(buffer[..2], [0x80, 0xff]);
+    assert_eq!(decode::<i8>(&buffer), Some((-128, -1)));
+
+    assert_eq!(encode(0_u8, 1, &mut buffer[..1]), None);
+    assert_eq!(decode::<u8>(&[7]), None);
+  }
+}
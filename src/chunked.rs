@@ -0,0 +1,140 @@
+//! Searches that hand the predicate several candidates at a time.
+//!
+//! This lets a predicate amortize a fixed per-call cost, such as spawning a
+//! process or making a network round trip, across several probes.
+
+use core::fmt;
+
+use crate::{Aborted, Betweenable, Direction, TryBracket};
+
+/// A reply from the predicate with the wrong number of directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WrongReplyLength {
+  /// The number of candidates passed to the predicate.
+  pub expected: usize,
+  /// The number of directions it returned.
+  pub got: usize,
+}
+
+impl fmt::Display for WrongReplyLength {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "expected {} directions from the predicate, got {}",
+      self.expected,
+      self.got,
+    )
+  }
+}
+
+impl core::error::Error for WrongReplyLength {}
+
+/// Search with a predicate that decides a batch of candidates per call.
+///
+/// Each call passes up to `batch` candidates, in increasing order and
+/// evenly spread over the current bracket, and expects one direction per
+/// candidate back. The bracket is then narrowed to the adjacent pair of
+/// candidates where the direction changes. Batches are rounded down to one
+/// less than a power of two so that the spread stays even.
+///
+/// A reply with more or fewer directions than candidates stops the search,
+/// with the bracket from before that call.
+pub fn binary_search<X, A, B, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    batch: usize,
+    mut f: F,
+  ) -> TryBracket<X, A, B, WrongReplyLength>
+  where
+    X: Betweenable,
+    F: FnMut(&[X]) -> Vec<Direction<A, B>> {
+  loop {
+    let candidates = spread(low.0, high.0, batch.max(1));
+    if candidates.is_empty() {
+      return Ok((low, high));
+    }
+    let directions = (f)(&candidates);
+    if directions.len() != candidates.len() {
+      let error =
+        WrongReplyLength {
+          expected: candidates.len(),
+          got: directions.len(),
+        };
+      return Err(Aborted { error, low, high });
+    }
+    for (&x, direction) in candidates.iter().zip(directions) {
+      match direction {
+        Direction::Low(a) => {
+          low = (x, a);
+        },
+        Direction::High(b) => {
+          high = (x, b);
+          break;
+        },
+      }
+    }
+  }
+}
+
+/// Find up to `batch` evenly spread points strictly between `low` and
+/// `high`, in increasing order.
+fn spread<X>(low: X, high: X, batch: usize) -> Vec<X>
+  where
+    X: Betweenable {
+  let mut bounds = vec![low, high];
+  loop {
+    let mut next = Vec::with_capacity(bounds.len() * 2);
+    for pair in bounds.windows(2) {
+      if let [a, b] = *pair {
+        next.push(a);
+        next.extend(X::between(a, b));
+      }
+    }
+    next.extend(bounds.last().copied());
+    if next.len() == bounds.len() || next.len() - 2 > batch {
+      break;
+    }
+    bounds = next;
+  }
+  let interior = bounds.len() - 2;
+  bounds.into_iter().skip(1).take(interior).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn chunked_search() {
+    for &threshold in [1, 2, 37, 99, 100].iter() {
+      let mut calls = 0;
+      let result =
+        binary_search((0_u32, ()), (100, ()), 7, |xs| {
+          calls += 1;
+          assert!(xs.len() <= 7);
+          assert!(xs.windows(2).all(|pair| pair[0] < pair[1]));
+          xs.iter()
+            .map(|&x| {
+              if x < threshold {
+                Direction::Low(())
+              } else {
+                Direction::High(())
+              }
+            })
+            .collect()
+        });
+      assert_eq!(result, Ok(((threshold - 1, ()), (threshold, ()))));
+      assert!(calls <= 3, "{} calls", calls);
+    }
+  }
+
+  #[test]
+  fn short_replies_are_errors() {
+    let result =
+      binary_search((0_u32, ()), (100, ()), 7, |xs| {
+        xs.iter().skip(1).map(|_| Direction::Low(())).collect::<Vec<_>>()
+      });
+    let error = WrongReplyLength { expected: 7, got: 6 };
+    assert_eq!(result, Err(Aborted { error, low: (0, ()), high: (100, ()) }));
+  }
+}
@@ -0,0 +1,140 @@
+//! Lookups in sorted lists of CIDR prefixes, such as allow and deny lists
+//! and routing tables.
+//!
+//! Each prefix is a `(network, length)` pair with the host bits of
+//! `network` clear, and the list is sorted, as `sort` leaves it. Prefixes
+//! may be nested, but a list of them can't otherwise overlap.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::partition_point;
+
+/// IP addresses, as the bits that prefixes are matched against.
+pub trait Address: Copy {
+  /// The number of bits in an address.
+  const BITS: u8;
+
+  /// The bits of the address, aligned to the top of a `u128` so that
+  /// prefixes of every family are masked the same way.
+  fn bits(self) -> u128;
+}
+
+impl Address for Ipv4Addr {
+  const BITS: u8 = 32;
+
+  fn bits(self) -> u128 {
+    u128::from(u32::from(self)) << 96
+  }
+}
+
+impl Address for Ipv6Addr {
+  const BITS: u8 = 128;
+
+  fn bits(self) -> u128 {
+    u128::from(self)
+  }
+}
+
+/// The first `len` bits of `bits`.
+fn mask(bits: u128, len: u8) -> u128 {
+  bits & !u128::MAX.checked_shr(u32::from(len)).unwrap_or(0)
+}
+
+fn key<A>(&(network, len): &(A, u8)) -> (u128, u8)
+  where
+    A: Address {
+  (network.bits(), len.min(A::BITS))
+}
+
+/// Find the longest prefix in `table` that contains `address`, and its
+/// index.
+pub fn longest_prefix_match<A>(table: &[(A, u8)], address: A) -> Option<usize>
+  where
+    A: Address {
+  let address = address.bits();
+  let mut bound = (address, A::BITS);
+  loop {
+    // The last prefix starting at or before the address is the longest
+    // that can contain it. If it doesn't, only a shorter prefix that
+    // contains both of them can, so the search is repeated for the bits
+    // they have in common, which get fewer each time.
+    let end =
+      partition_point(table.len(), |i| {
+        table.get(i).is_some_and(|entry| key(entry) <= bound)
+      });
+    let index = end.checked_sub(1)?;
+    let (network, len) = key(table.get(index)?);
+    if mask(address, len) == network {
+      return Some(index);
+    }
+    // Capping the bits in common keeps the search finite even if the
+    // table has host bits set.
+    let limit = len.min(bound.1).checked_sub(1)?;
+    let common = ((address ^ network).leading_zeros() as u8).min(limit);
+    bound = (mask(address, common), common);
+  }
+}
+
+/// Check whether any prefix in `table` contains `address`.
+pub fn contains<A>(table: &[(A, u8)], address: A) -> bool
+  where
+    A: Address {
+  longest_prefix_match(table, address).is_some()
+}
+
+/// Find the first prefix in `table` that starts after `address`, and its
+/// index.
+pub fn first_after<A>(table: &[(A, u8)], address: A) -> Option<usize>
+  where
+    A: Address {
+  let address = address.bits();
+  let index =
+    partition_point(table.len(), |i| {
+      table.get(i).is_some_and(|entry| key(entry).0 <= address)
+    });
+  (index < table.len()).then_some(index)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_longest_prefix() {
+    let v4 = Ipv4Addr::new;
+    let mut table = vec![
+      (v4(10, 0, 0, 0), 8),
+      (v4(10, 1, 0, 0), 16),
+      (v4(10, 1, 2, 0), 24),
+      (v4(10, 1, 2, 128), 25),
+      (v4(192, 168, 0, 0), 16),
+      (v4(10, 200, 0, 0), 16),
+    ];
+    table.sort();
+    let matched = |address| {
+      longest_prefix_match(&table, address).map(|i| table[i])
+    };
+    assert_eq!(matched(v4(10, 1, 2, 200)), Some((v4(10, 1, 2, 128), 25)));
+    assert_eq!(matched(v4(10, 1, 2, 3)), Some((v4(10, 1, 2, 0), 24)));
+    assert_eq!(matched(v4(10, 1, 3, 0)), Some((v4(10, 1, 0, 0), 16)));
+    assert_eq!(matched(v4(10, 100, 0, 0)), Some((v4(10, 0, 0, 0), 8)));
+    assert_eq!(matched(v4(10, 255, 0, 0)), Some((v4(10, 0, 0, 0), 8)));
+    assert_eq!(matched(v4(192, 168, 7, 7)), Some((v4(192, 168, 0, 0), 16)));
+    assert_eq!(matched(v4(9, 255, 255, 255)), None);
+    assert!(!contains(&table, v4(192, 169, 0, 0)));
+    assert!(contains(&[(v4(0, 0, 0, 0), 0)], v4(255, 255, 255, 255)));
+    assert!(contains(&[(v4(1, 2, 3, 4), 32)], v4(1, 2, 3, 4)));
+    assert!(!contains(&[(v4(1, 2, 3, 4), 32)], v4(1, 2, 3, 5)));
+
+    let after = |address| first_after(&table, address).map(|i| table[i]);
+    assert_eq!(after(v4(10, 1, 2, 3)), Some((v4(10, 1, 2, 128), 25)));
+    assert_eq!(after(v4(10, 1, 2, 128)), Some((v4(10, 200, 0, 0), 16)));
+    assert_eq!(after(v4(192, 168, 0, 0)), None);
+
+    let v6 = |s: &str| s.parse::<Ipv6Addr>().unwrap();
+    let table = [(v6("2001:db8::"), 32), (v6("2001:db8:1::"), 48)];
+    assert_eq!(longest_prefix_match(&table, v6("2001:db8:1::1")), Some(1));
+    assert_eq!(longest_prefix_match(&table, v6("2001:db8:2::1")), Some(0));
+    assert_eq!(longest_prefix_match(&table, v6("2001:db9::")), None);
+  }
+}
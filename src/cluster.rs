@@ -0,0 +1,87 @@
+//! Splitting sorted values into clusters separated by large gaps, such as
+//! grouping sorted timestamps into sessions.
+
+use std::ops::{Range, Sub};
+
+use crate::partition_point;
+
+/// Split sorted `values` into clusters, where neighbouring values within a
+/// cluster are at most `max_gap` apart, and clusters are further apart
+/// than that.
+///
+/// Any values within `max_gap` of each other must be in the same cluster,
+/// so rather than checking every gap, this gallops from each value to the
+/// last one within `max_gap` of it, and only checks the gap after that.
+/// When clusters are dense, that skips most of the values.
+pub fn clusters<T>(values: &[T], max_gap: T) -> Vec<Range<usize>>
+  where
+    T: Copy + PartialOrd + Sub<Output=T> {
+  let mut clusters = Vec::new();
+  let mut start = 0;
+  let mut anchor = 0;
+  while anchor < values.len() {
+    let last = last_within(values, anchor, max_gap).unwrap_or(anchor);
+    let next = last + 1;
+    let apart =
+      match (values.get(last), values.get(next)) {
+        (Some(&a), Some(&b)) => b - a > max_gap,
+        _ => true,
+      };
+    if apart {
+      clusters.push(start..next);
+      start = next;
+    }
+    anchor = next;
+  }
+  clusters
+}
+
+/// Find the last index from `anchor` onwards whose value is within
+/// `max_gap` of the value at `anchor`, or `None` if not even `anchor` is,
+/// as when `max_gap` is negative.
+fn last_within<T>(values: &[T], anchor: usize, max_gap: T) -> Option<usize>
+  where
+    T: Copy + PartialOrd + Sub<Output=T> {
+  let within = |i: usize| {
+    match (values.get(anchor), values.get(i)) {
+      (Some(&a), Some(&x)) => x - a <= max_gap,
+      _ => false,
+    }
+  };
+  let mut low = anchor;
+  let mut step = 1;
+  let high =
+    loop {
+      let x = anchor.saturating_add(step);
+      if x >= values.len() || !within(x) {
+        break x.min(values.len());
+      }
+      low = x;
+      step = step.saturating_mul(2);
+    };
+  partition_point(high - low, |i| within(low + i))
+    .checked_sub(1)
+    .map(|i| low + i)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn splits_at_large_gaps() {
+    let times = [0, 1, 2, 3, 10, 11, 30, 31, 32, 33, 34, 35, 36, 50];
+    assert_eq!(clusters(&times, 5), vec![0..4, 4..6, 6..13, 13..14]);
+    assert_eq!(clusters(&times, 100), vec![0..14]);
+    let singletons: Vec<Range<usize>> = (0..14).map(|i| i..i + 1).collect();
+    assert_eq!(clusters(&times, 0), singletons);
+    assert_eq!(clusters(&[] as &[u32], 5), vec![]);
+    assert_eq!(clusters(&[1, 2, 2], -1), vec![0..1, 1..2, 2..3]);
+
+    let times: Vec<u64> =
+      (0..10_000).map(|i| i * 3 + (i / 1000) * 100).collect();
+    let expected: Vec<Range<usize>> =
+      (0..10).map(|i| i * 1000..(i + 1) * 1000).collect();
+    assert_eq!(clusters(&times, 10), expected);
+  }
+}
@@ -0,0 +1,139 @@
+//! Two-phase searches that localize the transition on a coarse grid first.
+//!
+//! This suits predicates whose probes have a high fixed cost at fine
+//! granularity, where only grid points are cheap to set up (for example,
+//! release builds that are only deployed every `stride` commits).
+
+use core::ops::{Add, Div, Mul, Sub};
+
+use crate::{binary_search as fine_search, Betweenable, Direction};
+
+/// Positions whose differences may not fit in their type.
+pub trait CheckedSub: Sized {
+  /// `self - other`, or `None` if that overflows.
+  fn checked_sub(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_sub {
+  ($($t:ty),*) => {
+    $(
+      impl CheckedSub for $t {
+        fn checked_sub(self, other: Self) -> Option<Self> {
+          <$t>::checked_sub(self, other)
+        }
+      }
+    )*
+  };
+}
+
+impl_checked_sub!(u8, u16, u32, u64, u128, usize);
+impl_checked_sub!(i8, i16, i32, i64, i128, isize);
+
+#[cfg(feature = "rust_decimal")]
+impl CheckedSub for rust_decimal::Decimal {
+  fn checked_sub(self, other: Self) -> Option<Self> {
+    rust_decimal::Decimal::checked_sub(self, other)
+  }
+}
+
+/// Search by bisecting the grid `low + k * stride` first, and then
+/// bisecting exactly inside the grid cell that contains the transition.
+///
+/// Returns the same bracket as `binary_search`. A non-positive `stride`
+/// skips the coarse phase, as does a range too wide for its width to fit in
+/// `X`, such as one from a negative value to a positive one close to the
+/// bounds of the type.
+pub fn binary_search<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    stride: X,
+    mut f: F,
+  ) -> ((X, A), (X, B))
+  where
+    X: Betweenable,
+    X: CheckedSub,
+    X: From<u8>,
+    X: PartialOrd,
+    X: Add<X, Output=X>,
+    X: Sub<X, Output=X>,
+    X: Mul<X, Output=X>,
+    X: Div<X, Output=X>,
+    F: FnMut(X) -> Direction<A, B> {
+  let zero = X::from(0);
+  let origin = low.0;
+  let end = high.0;
+  let width =
+    match end.checked_sub(origin) {
+      Some(width) if stride > zero && width > zero => width,
+      _ => return fine_search(low, high, f),
+    };
+  let cells = width / stride;
+  let last =
+    if cells * stride == width {
+      cells
+    } else {
+      cells + X::from(1)
+    };
+  let point = |k: X| {
+    if k < last {
+      origin + k * stride
+    } else {
+      end
+    }
+  };
+
+  let ((coarse_low, a), (coarse_high, b)) =
+    fine_search((zero, low.1), (last, high.1), |k| (f)(point(k)));
+  fine_search((point(coarse_low), a), (point(coarse_high), b), f)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn coarse_then_fine() {
+    for &threshold in [1, 2, 9, 10, 11, 57, 99, 100].iter() {
+      let mut probes = Vec::new();
+      let result =
+        binary_search((0_u32, ()), (100, ()), 10, |x| {
+          probes.push(x);
+          if x < threshold {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        });
+      assert_eq!(result, ((threshold - 1, ()), (threshold, ())));
+      let coarse = probes.iter().take_while(|&&x| x % 10 == 0).count();
+      assert!(coarse >= 3);
+      assert!(probes.len() - coarse <= 4, "{:?}", probes);
+    }
+  }
+
+  #[test]
+  fn stride_that_does_not_divide_the_width() {
+    let result =
+      binary_search((3_i64, ()), (40, ()), 7, |x| {
+        if x < 38 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(result, ((37, ()), (38, ())));
+  }
+
+  #[test]
+  fn range_wider_than_the_type() {
+    let result =
+      binary_search((i64::MIN, ()), (i64::MAX, ()), 1 << 60, |x| {
+        if x < 12345 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(result, ((12344, ()), (12345, ())));
+  }
+}
@@ -0,0 +1,167 @@
+//! Combinators for search spaces built out of simpler ones.
+//!
+//! `union` searches two domains laid end to end, such as the ID spaces
+//! before and after a schema change, and `product` searches pairs in
+//! lexicographic order, where the range of the second coordinate may
+//! depend on the first.
+
+use crate::{binary_search, Betweenable, Bracket, Direction};
+
+/// A point in the union of two domains, where every point of the first
+/// comes before every point of the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Segment<X, Y> {
+  First(X),
+  Second(Y),
+}
+
+/// Search the union of the end of one domain and the start of another.
+///
+/// `low` is known to be low in the first domain and `high` is known to be
+/// high in the second. The candidates are the points of the first domain
+/// after `low` up to and including `first_end`, followed by the points of
+/// the second domain from `second_start` up to but excluding `high`.
+pub fn union<X, Y, A, B, F>(
+    low: (X, A),
+    first_end: X,
+    second_start: Y,
+    high: (Y, B),
+    mut f: F,
+  ) -> Bracket<Segment<X, Y>, A, B>
+  where
+    X: Betweenable + PartialOrd,
+    Y: Betweenable + PartialOrd,
+    F: FnMut(Segment<X, Y>) -> Direction<A, B> {
+  let mut low = low;
+  if low.0 < first_end {
+    match (f)(Segment::First(first_end)) {
+      Direction::High(b) => {
+        let ((x, a), (y, b)) =
+          binary_search(low, (first_end, b), |x| (f)(Segment::First(x)));
+        return ((Segment::First(x), a), (Segment::First(y), b));
+      },
+      Direction::Low(a) => {
+        low = (first_end, a);
+      },
+    }
+  }
+
+  let low = (Segment::First(low.0), low.1);
+  if second_start < high.0 {
+    match (f)(Segment::Second(second_start)) {
+      Direction::High(b) => {
+        (low, (Segment::Second(second_start), b))
+      },
+      Direction::Low(a) => {
+        let ((x, a), (y, b)) =
+          binary_search((second_start, a), high, |y| (f)(Segment::Second(y)));
+        ((Segment::Second(x), a), (Segment::Second(y), b))
+      },
+    }
+  } else {
+    (low, (Segment::Second(high.0), high.1))
+  }
+}
+
+/// Search pairs in lexicographic order.
+///
+/// `row(x)` gives the inclusive range of the second coordinate for first
+/// coordinate `x`. The first coordinate is bisected by probing the start of
+/// each row, and then the transition is found within the rows either side.
+pub fn product<X, Y, A, B, R, F>(
+    low: ((X, Y), A),
+    high: ((X, Y), B),
+    mut row: R,
+    mut f: F,
+  ) -> Bracket<(X, Y), A, B>
+  where
+    X: Betweenable + PartialEq,
+    Y: Betweenable + PartialOrd,
+    R: FnMut(X) -> (Y, Y),
+    F: FnMut((X, Y)) -> Direction<A, B> {
+  let (((x0, y0), a), ((x1, y1), b)) = (low, high);
+  let ((xl, (yl, a)), (xh, (yh, b))) =
+    binary_search((x0, (y0, a)), (x1, (y1, b)), |x| {
+      let (start, _) = (row)(x);
+      match (f)((x, start)) {
+        Direction::Low(a) => Direction::Low((start, a)),
+        Direction::High(b) => Direction::High((start, b)),
+      }
+    });
+
+  if xl == xh {
+    let ((y, a), (z, b)) = binary_search((yl, a), (yh, b), |y| (f)((xl, y)));
+    return (((xl, y), a), ((xl, z), b));
+  }
+
+  let (_, end) = (row)(xl);
+  let (start, _) = (row)(xh);
+  let point = |segment| {
+    match segment {
+      Segment::First(y) => (xl, y),
+      Segment::Second(y) => (xh, y),
+    }
+  };
+  let ((l, a), (h, b)) =
+    union((yl, a), end, start, (yh, b), |segment| (f)(point(segment)));
+  ((point(l), a), (point(h), b))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn union_test() {
+    // IDs 100..=199 before a migration and 1000..=1999 after it.
+    let thresholds = [
+      Segment::First(101),
+      Segment::First(150),
+      Segment::First(199),
+      Segment::Second(1000),
+      Segment::Second(1500),
+      Segment::Second(1999),
+    ];
+    for &threshold in thresholds.iter() {
+      let result =
+        union((100_u32, ()), 199, 1000_u64, (2000, ()), |segment| {
+          if segment < threshold {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        });
+      let expected_low =
+        match threshold {
+          Segment::First(x) => Segment::First(x - 1),
+          Segment::Second(1000) => Segment::First(199),
+          Segment::Second(y) => Segment::Second(y - 1),
+        };
+      assert_eq!(result, ((expected_low, ()), (threshold, ())));
+    }
+  }
+
+  #[test]
+  fn product_test() {
+    // Days 0 to 9, each with a number of sequence numbers that varies.
+    let row = |day: u32| (0_u32, day * 3 + 1);
+    let mut points = Vec::new();
+    for day in 0..10 {
+      for seq in 0..=row(day).1 {
+        points.push((day, seq));
+      }
+    }
+    for pair in points.windows(2) {
+      let threshold = pair[1];
+      let result =
+        product(((0, 0), ()), ((9, 28), ()), row, |point| {
+          if point < threshold {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        });
+      assert_eq!(result, ((pair[0], ()), (threshold, ())));
+    }
+  }
+}
@@ -0,0 +1,155 @@
+//! Bound queries over rows sorted by a composite key, the way a database
+//! index over several columns is searched.
+
+use std::cmp::Ordering;
+
+use crate::partition_point;
+
+/// The direction a column is sorted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Order {
+  Ascending,
+  Descending,
+}
+
+/// A composite key over rows of type `T`, queried with keys of type `Q`.
+///
+/// Each column compares a row against the query, as it would if the column
+/// were sorted ascending; descending columns have their comparison
+/// reversed. The rows must be sorted by the columns in the order they were
+/// added.
+pub struct CompositeKey<'a, T, Q> {
+  columns: Vec<(Compare<'a, T, Q>, Order)>,
+}
+
+type Compare<'a, T, Q> = Box<dyn Fn(&T, &Q) -> Ordering + 'a>;
+
+impl<'a, T, Q> Default for CompositeKey<'a, T, Q> {
+  fn default() -> Self {
+    CompositeKey { columns: Vec::new() }
+  }
+}
+
+impl<'a, T, Q> CompositeKey<'a, T, Q> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a column, comparing rows to queries with `compare`.
+  pub fn column<F>(mut self, order: Order, compare: F) -> Self
+    where
+      F: Fn(&T, &Q) -> Ordering + 'a {
+    self.columns.push((Box::new(compare), order));
+    self
+  }
+
+  pub fn len(&self) -> usize {
+    self.columns.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.columns.is_empty()
+  }
+
+  /// Compare `row` to `query` on the first `prefix` columns.
+  pub fn compare_prefix(&self, row: &T, query: &Q, prefix: usize) -> Ordering {
+    for (compare, order) in self.columns.iter().take(prefix) {
+      let ordering =
+        match order {
+          Order::Ascending => (compare)(row, query),
+          Order::Descending => (compare)(row, query).reverse(),
+        };
+      if ordering != Ordering::Equal {
+        return ordering;
+      }
+    }
+    Ordering::Equal
+  }
+
+  /// Find the first row that is lexicographically not less than `query`,
+  /// comparing the columns in order.
+  pub fn lower_bound(&self, rows: &[T], query: &Q) -> usize {
+    self.lower_bound_prefix(rows, query, self.columns.len())
+  }
+
+  /// Find the first row that is lexicographically greater than `query`,
+  /// comparing the columns in order.
+  pub fn upper_bound(&self, rows: &[T], query: &Q) -> usize {
+    self.upper_bound_prefix(rows, query, self.columns.len())
+  }
+
+  /// Find the first row that is lexicographically not less than `query` on
+  /// the first `prefix` columns.
+  pub fn lower_bound_prefix(
+      &self,
+      rows: &[T],
+      query: &Q,
+      prefix: usize,
+    ) -> usize {
+    partition_point(rows.len(), |i| {
+      rows.get(i).is_some_and(|row| {
+        self.compare_prefix(row, query, prefix) == Ordering::Less
+      })
+    })
+  }
+
+  /// Find the first row that is lexicographically greater than `query` on
+  /// the first `prefix` columns.
+  pub fn upper_bound_prefix(
+      &self,
+      rows: &[T],
+      query: &Q,
+      prefix: usize,
+    ) -> usize {
+    partition_point(rows.len(), |i| {
+      rows.get(i).is_some_and(|row| {
+        self.compare_prefix(row, query, prefix) != Ordering::Greater
+      })
+    })
+  }
+
+  /// Find the range of rows that match `query` on the first `prefix`
+  /// columns.
+  pub fn equal_range_prefix(
+      &self,
+      rows: &[T],
+      query: &Q,
+      prefix: usize,
+    ) -> std::ops::Range<usize> {
+    let start = self.lower_bound_prefix(rows, query, prefix);
+    let end = self.upper_bound_prefix(rows, query, prefix);
+    start..end.max(start)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn composite_key_test() {
+    // Sorted by country ascending, then population descending, then name.
+    let rows = [
+      ("fr", 2_100_000, "paris"),
+      ("fr", 500_000, "lyon"),
+      ("uk", 8_900_000, "london"),
+      ("uk", 1_100_000, "birmingham"),
+      ("uk", 1_100_000, "glasgow"),
+      ("uk", 500_000, "bristol"),
+    ];
+    type Row = (&'static str, u32, &'static str);
+    let key =
+      CompositeKey::new()
+        .column(Order::Ascending, |r: &Row, q: &Row| r.0.cmp(q.0))
+        .column(Order::Descending, |r: &Row, q: &Row| r.1.cmp(&q.1))
+        .column(Order::Ascending, |r: &Row, q: &Row| r.2.cmp(q.2));
+
+    assert_eq!(key.lower_bound(&rows, &("uk", 1_100_000, "c")), 4);
+    assert_eq!(key.upper_bound(&rows, &("uk", 1_100_000, "glasgow")), 5);
+    assert_eq!(key.equal_range_prefix(&rows, &("uk", 0, ""), 1), 2..6);
+    let uk_1_100_000 = ("uk", 1_100_000, "");
+    assert_eq!(key.equal_range_prefix(&rows, &uk_1_100_000, 2), 3..5);
+    assert_eq!(key.equal_range_prefix(&rows, &("de", 0, ""), 1), 0..0);
+    assert_eq!(key.lower_bound_prefix(&rows, &("fr", 1_000_000, ""), 2), 1);
+  }
+}
@@ -0,0 +1,143 @@
+//! Searches that can run at compile time, for lookup tables and thresholds
+//! computed in `const` items instead of at startup.
+//!
+//! Closures can't be called in a `const` context, so a search with a
+//! predicate is a macro, `const_binary_search!`, which expands to a loop
+//! around the predicate in place. Searches of sorted arrays are `const fn`s,
+//! one for each integer type.
+
+/// Like `binary_search`, but usable in a `const` context, over integers and
+/// without witnesses.
+///
+/// `const_binary_search!(low, high, |x| is_low)` evaluates `is_low` with
+/// `x` bound to each probe, and gives `(largest_low, smallest_high)`, with
+/// the same probes and result as `binary_search`. As with it, `low` is
+/// taken to be low and `high` to be high without being probed.
+///
+/// For example, `const_binary_search!(0, 1 << 21, |x| x * x <= 1 << 40).0`
+/// is the integer square root of `1 << 40`.
+#[macro_export]
+macro_rules! const_binary_search {
+  ($low:expr, $high:expr, |$x:ident| $is_low:expr) => {{
+    let mut low = $low;
+    let mut high = $high;
+    loop {
+      // The floor of the mean, without overflow, as `Betweenable` has it.
+      let $x = (low >> 1) + (high >> 1) + (low & high & 1);
+      if !(low < $x && $x < high) {
+        break (low, high);
+      }
+      if $is_low {
+        low = $x;
+      } else {
+        high = $x;
+      }
+    }
+  }};
+}
+
+macro_rules! impl_bounds {
+  ($($lower:ident, $upper:ident, $t:ty;)*) => {
+    $(
+      /// Find the index of the first element of sorted `slice` that is not
+      /// less than `key`.
+      pub const fn $lower(slice: &[$t], key: $t) -> usize {
+        let mut base = 0;
+        let mut rest = slice;
+        // Take the middle element of what's left and keep the half that
+        // the answer is in, which leaves nothing once it's found.
+        while let Some((left, right)) =
+          rest.split_at_checked(rest.len() / 2) {
+          match right {
+            [middle, tail @ ..] => {
+              if *middle < key {
+                base += left.len() + 1;
+                rest = tail;
+              } else {
+                rest = left;
+              }
+            },
+            [] => break,
+          }
+        }
+        base
+      }
+
+      /// Find the index of the first element of sorted `slice` that is
+      /// greater than `key`.
+      pub const fn $upper(slice: &[$t], key: $t) -> usize {
+        let mut base = 0;
+        let mut rest = slice;
+        while let Some((left, right)) =
+          rest.split_at_checked(rest.len() / 2) {
+          match right {
+            [middle, tail @ ..] => {
+              if *middle <= key {
+                base += left.len() + 1;
+                rest = tail;
+              } else {
+                rest = left;
+              }
+            },
+            [] => break,
+          }
+        }
+        base
+      }
+    )*
+  };
+}
+
+impl_bounds!(
+  lower_bound_u8, upper_bound_u8, u8;
+  lower_bound_u16, upper_bound_u16, u16;
+  lower_bound_u32, upper_bound_u32, u32;
+  lower_bound_u64, upper_bound_u64, u64;
+  lower_bound_u128, upper_bound_u128, u128;
+  lower_bound_usize, upper_bound_usize, usize;
+  lower_bound_i8, upper_bound_i8, i8;
+  lower_bound_i16, upper_bound_i16, i16;
+  lower_bound_i32, upper_bound_i32, i32;
+  lower_bound_i64, upper_bound_i64, i64;
+  lower_bound_i128, upper_bound_i128, i128;
+  lower_bound_isize, upper_bound_isize, isize;
+);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{binary_search, Direction};
+
+  const LIMITS: [u32; 6] = [10, 20, 20, 20, 40, 80];
+  const AT_20: (usize, usize) =
+    (lower_bound_u32(&LIMITS, 20), upper_bound_u32(&LIMITS, 20));
+  const NEGATIVE: (i8, i8) = const_binary_search!(i8::MIN, 0, |x| x < -100);
+
+  #[test]
+  fn evaluates_at_compile_time() {
+    assert_eq!(AT_20, (1, 4));
+    assert_eq!(NEGATIVE, (-101, -100));
+    for key in 0..100 {
+      assert_eq!(
+        lower_bound_u32(&LIMITS, key),
+        LIMITS.partition_point(|&x| x < key),
+      );
+      assert_eq!(
+        upper_bound_u32(&LIMITS, key),
+        LIMITS.partition_point(|&x| x <= key),
+      );
+    }
+    assert_eq!(lower_bound_i64(&[], 3), 0);
+
+    for threshold in [0_u64, 1, 2, 999, 1000].iter().copied() {
+      let ((low, ()), (high, ())) =
+        binary_search((0_u64, ()), (1000, ()), |x| {
+          if x < threshold { Direction::Low(()) } else { Direction::High(()) }
+        });
+      assert_eq!(
+        const_binary_search!(0_u64, 1000, |x| x < threshold),
+        (low, high),
+      );
+    }
+  }
+}
@@ -0,0 +1,76 @@
+//! Searches whose timing doesn't depend on where the transition is.
+//!
+//! These are intended for cryptographic code, where the position of a key
+//! must not leak through the number of probes or the memory touched. The
+//! predicate you supply must itself be constant-time for this to hold.
+
+use crate::Direction;
+
+/// Search the open interval `(low, high)` in a fixed number of rounds.
+///
+/// Exactly `ceil(log2(high - low))` probes are made, regardless of the
+/// answers, and the bracket is narrowed arithmetically rather than by
+/// branching on the direction. Returns the largest low and the smallest
+/// high, as `binary_search` does.
+pub fn binary_search<F>(low: usize, high: usize, mut f: F) -> (usize, usize)
+  where
+    F: FnMut(usize) -> Direction<(), ()> {
+  if high <= low {
+    return (low, high);
+  }
+  let mut base = low;
+  let mut len = high - low;
+  while len > 1 {
+    let half = len / 2;
+    let mid = base + half;
+    let is_low = matches!((f)(mid), Direction::Low(())) as usize;
+    base += is_low * half;
+    len -= half;
+  }
+  (base, base + 1)
+}
+
+/// Find the number of leading elements of `slice` for which `pred` holds,
+/// which is the partition point when `slice` is partitioned by `pred`.
+///
+/// Every element is visited, in order, so the memory access pattern is
+/// independent of both the key and the contents of the slice.
+pub fn partition_point<T, P>(slice: &[T], mut pred: P) -> usize
+  where
+    P: FnMut(&T) -> bool {
+  slice.iter().fold(0, |count, x| count + (pred)(x) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fixed_number_of_rounds() {
+    for &threshold in [1, 2, 50, 99].iter() {
+      let mut probes = 0;
+      let result =
+        binary_search(0, 100, |x| {
+          probes += 1;
+          if x < threshold {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        });
+      assert_eq!(result, (threshold - 1, threshold));
+      assert_eq!(probes, 7);
+    }
+  }
+
+  #[test]
+  fn partition_point_matches_std() {
+    let values = [1, 3, 3, 5, 8, 13];
+    for key in 0..15 {
+      assert_eq!(
+        partition_point(&values, |&x| x < key),
+        values.partition_point(|&x| x < key),
+      );
+    }
+  }
+}
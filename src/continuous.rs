@@ -0,0 +1,141 @@
+//! Minimizing unimodal functions of a real variable, such as a smooth cost
+//! over a continuous parameter.
+
+/// The point that divides a bracket in the golden ratio, measured from the
+/// other end.
+const INVERSE_PHI: f64 = 0.618_033_988_749_894_9;
+
+/// The outcome of `golden_section_search`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimum {
+  /// The lower end of the final bracket, with its value.
+  pub low: (f64, f64),
+  /// The point inside the bracket with the smallest value seen there.
+  pub middle: (f64, f64),
+  /// The upper end of the final bracket, with its value.
+  pub high: (f64, f64),
+  /// The point with the smallest value seen anywhere, which is one of the
+  /// ends if the minimum is at a bound of the search.
+  pub best: (f64, f64),
+  pub probes: usize,
+  /// Whether the bracket narrowed to `eps`, rather than the search running
+  /// out of probes.
+  pub converged: bool,
+}
+
+/// Find the minimum of `f` on `low..=high` by golden-section search,
+/// stopping once the bracket is no wider than `eps`, or after `max_probes`
+/// probes if given.
+///
+/// `f` must be unimodal on the range: decreasing up to its minimum, and
+/// increasing after it. Both ends are probed, then two points inside, and
+/// then each probe shrinks the bracket by a factor of about 0.618. To find
+/// a maximum, negate `f`. The search also stops once the bracket can't be
+/// divided any more at the precision of `f64`. Values near a smooth minimum
+/// differ only by the square of the distance from it, so an `eps` much
+/// below the square root of `f64::EPSILON`, relative to the scale of the
+/// problem, narrows the bracket by rounding error instead.
+///
+/// With `max_probes` below four, the points the budget doesn't cover
+/// aren't probed, and have NaN values in the result.
+pub fn golden_section_search<F>(
+    low: f64,
+    high: f64,
+    eps: f64,
+    max_probes: Option<usize>,
+    mut f: F,
+  ) -> Minimum
+  where
+    F: FnMut(f64) -> f64 {
+  let budget = max_probes.unwrap_or(usize::MAX);
+  let mut probes = 0;
+  let mut probe = |x: f64, probes: &mut usize| {
+    if *probes < budget {
+      *probes += 1;
+      (x, (f)(x))
+    } else {
+      (x, f64::NAN)
+    }
+  };
+  let mut a = probe(low, &mut probes);
+  let mut c = probe(high, &mut probes);
+  let mut x1 = probe(high - INVERSE_PHI * (high - low), &mut probes);
+  let mut x2 = probe(low + INVERSE_PHI * (high - low), &mut probes);
+  let mut converged = true;
+  while c.0 - a.0 > eps {
+    if probes >= budget {
+      converged = false;
+      break;
+    }
+    if x1.1 < x2.1 {
+      let x = x2.0 - INVERSE_PHI * (x2.0 - a.0);
+      if !(a.0 < x && x < x1.0) {
+        break;
+      }
+      c = x2;
+      x2 = x1;
+      x1 = probe(x, &mut probes);
+    } else {
+      let x = x1.0 + INVERSE_PHI * (c.0 - x1.0);
+      if !(x2.0 < x && x < c.0) {
+        break;
+      }
+      a = x1;
+      x1 = x2;
+      x2 = probe(x, &mut probes);
+    }
+  }
+  let (low, middle, high) =
+    if x1.1 < x2.1 {
+      (a, x1, x2)
+    } else {
+      (x1, x2, c)
+    };
+  let best =
+    [a, x1, x2, c].iter()
+      .copied()
+      .fold(middle, |best, x| {
+        if x.1 < best.1 || best.1.is_nan() { x } else { best }
+      });
+  Minimum { low, middle, high, best, probes, converged }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn narrows_to_tolerance_or_budget() {
+    let f = |x: f64| (x - 2.0_f64.sqrt()).powi(2);
+    let minimum = golden_section_search(-10.0, 10.0, 1e-9, None, f);
+    assert!(minimum.converged);
+    assert!(minimum.high.0 - minimum.low.0 <= 1e-9);
+    let root = 2.0_f64.sqrt();
+    assert!(minimum.low.0 <= root && root <= minimum.high.0);
+    assert!((minimum.best.0 - root).abs() < 1e-9);
+    assert!(minimum.middle.1 <= minimum.low.1);
+    assert!(minimum.middle.1 <= minimum.high.1);
+    assert!(minimum.probes < 60, "{} probes", minimum.probes);
+
+    let capped = golden_section_search(-10.0, 10.0, 1e-9, Some(10), f);
+    assert!(!capped.converged);
+    assert_eq!(capped.probes, 10);
+
+    let mut calls = 0;
+    let ends =
+      golden_section_search(-10.0, 10.0, 1e-9, Some(2), |x| {
+        calls += 1;
+        f(x)
+      });
+    assert_eq!((ends.probes, calls), (2, 2));
+    assert!(!ends.converged);
+    assert_eq!(ends.best.0, 10.0);
+    let none = golden_section_search(-10.0, 10.0, 1e-9, Some(0), f);
+    assert_eq!(none.probes, 0);
+    assert!(none.best.1.is_nan());
+
+    let at_end = golden_section_search(0.0, 1.0, 0.0, None, |x| x);
+    assert!(at_end.converged);
+    assert_eq!(at_end.best, (0.0, 0.0));
+  }
+}
@@ -0,0 +1,88 @@
+//! Searches whose probes have a cost, such as time, money or samples, and
+//! that are budgeted in it instead of in probes.
+
+use core::ops::Add;
+
+use crate::{Betweenable, Budgeted, Direction};
+
+/// The total cost of a search's probes, split by the side they came out on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Costs<C> {
+  pub low: C,
+  pub high: C,
+  pub probes: usize,
+}
+
+impl<C> Costs<C>
+  where
+    C: Copy + Add<Output = C> {
+  pub fn total(&self) -> C {
+    self.low + self.high
+  }
+}
+
+/// Like `binary_search_with_budget`, but each probe also returns its cost,
+/// and the search stops once the total reaches `budget`, if given.
+///
+/// The cost of a probe is only known once it's made, so the total can
+/// overshoot the budget by the cost of the last probe.
+pub fn binary_search<X, A, B, C, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    budget: Option<C>,
+    mut f: F,
+  ) -> (Budgeted<X, A, B>, Costs<C>)
+  where
+    X: Betweenable,
+    C: Copy + Default + PartialOrd + Add<Output = C>,
+    F: FnMut(X) -> (Direction<A, B>, C) {
+  let mut costs = Costs { low: C::default(), high: C::default(), probes: 0 };
+  while let Some(x) = X::between(low.0, high.0) {
+    if budget.is_some_and(|budget| costs.total() >= budget) {
+      return (Budgeted::Exhausted((low, high)), costs);
+    }
+    let (direction, cost) = (f)(x);
+    costs.probes += 1;
+    match direction {
+      Direction::Low(a) => {
+        low = (x, a);
+        costs.low = costs.low + cost;
+      },
+      Direction::High(b) => {
+        high = (x, b);
+        costs.high = costs.high + cost;
+      },
+    }
+  }
+  (Budgeted::Converged((low, high)), costs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stops_when_budget_is_spent() {
+    // Probes of larger inputs cost more, in cents.
+    let probe = |x: u32| {
+      let cost = 100 + x;
+      if x < 300 {
+        (Direction::Low(()), cost)
+      } else {
+        (Direction::High(()), cost)
+      }
+    };
+    let (result, costs) = binary_search((0, ()), (1000, ()), None, probe);
+    assert_eq!(result, Budgeted::Converged(((299, ()), (300, ()))));
+    assert_eq!(costs.probes, 10);
+    assert!(costs.low > 0 && costs.high > 0);
+
+    let (result, capped) =
+      binary_search((0, ()), (1000, ()), Some(2000), probe);
+    assert!(result.converged().is_none());
+    assert!(capped.total() >= 2000 && capped.total() < 2000 + 1100);
+    assert!(capped.probes < costs.probes);
+    let ((low, ()), (high, ())) = result.bracket();
+    assert!(low < 300 && 300 <= high);
+  }
+}
@@ -0,0 +1,80 @@
+//! Finger searches, for streams of queries that each land near the last.
+
+use crate::gallop::nearest_transition;
+use crate::Direction;
+
+/// A position in a sorted slice that later queries search outwards from.
+///
+/// Each query gallops from the result of the previous one, so it takes
+/// `O(log d)` comparisons, where `d` is how far the answer has moved. For
+/// queries in sorted order, such as one side of a merge join, that's
+/// `O(n + m)` overall rather than `O(m log n)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a, T> {
+  slice: &'a [T],
+  finger: usize,
+}
+
+impl<'a, T> Cursor<'a, T>
+  where
+    T: Ord {
+  pub fn new(slice: &'a [T]) -> Self {
+    Cursor { slice, finger: 0 }
+  }
+
+  /// The index returned by the last query.
+  pub fn position(&self) -> usize {
+    self.finger
+  }
+
+  /// Find the index of the first element that is not less than `key`.
+  pub fn lower_bound(&mut self, key: &T) -> usize {
+    self.seek(|x| x < key)
+  }
+
+  /// Find the index of the first element that is greater than `key`.
+  pub fn upper_bound(&mut self, key: &T) -> usize {
+    self.seek(|x| x <= key)
+  }
+
+  /// Find the number of leading elements for which `is_low` holds,
+  /// starting from the finger, and move the finger there.
+  fn seek<P>(&mut self, mut is_low: P) -> usize
+    where
+      P: FnMut(&T) -> bool {
+    let slice = self.slice;
+    let result =
+      nearest_transition(self.finger, |i| {
+        if slice.get(i).is_some_and(&mut is_low) {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    // The gallop only runs off the end of `usize` downwards, when every
+    // element is high.
+    self.finger = result.map_or(0, |(_, (high, ()))| high);
+    self.finger
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn follows_nearby_queries() {
+    let values: Vec<u32> = (0..1000).map(|x| x * 2).collect();
+    let mut cursor = Cursor::new(&values);
+    for key in [0, 1, 2, 7, 500, 501, 1998, 1999, 5000, 3, 0] {
+      let expected = values.partition_point(|&x| x < key);
+      assert_eq!(cursor.lower_bound(&key), expected, "key {}", key);
+      assert_eq!(cursor.position(), expected);
+      let expected = values.partition_point(|&x| x <= key);
+      assert_eq!(cursor.upper_bound(&key), expected, "key {}", key);
+    }
+
+    let empty: [u32; 0] = [];
+    assert_eq!(Cursor::new(&empty).lower_bound(&3), 0);
+  }
+}
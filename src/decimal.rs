@@ -0,0 +1,112 @@
+//! Searching exact decimal values, such as prices, without going through
+//! floats or scaling integers by hand.
+//!
+//! `Fixed` is a fixed-point number stored as an integer count of
+//! `10^-DECIMALS`ths. With the `rust_decimal` feature, `Decimal` is also
+//! `Betweenable`, at the precision of the ends of the search.
+
+use core::fmt;
+
+use crate::Betweenable;
+
+/// A fixed-point number with `DECIMALS` digits after the point, stored as
+/// the integer `self.0`, so `Fixed::<i64, 2>(1999)` is `19.99`.
+///
+/// It's bisected as its integer, so every value a search probes has
+/// exactly `DECIMALS` digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<T, const DECIMALS: u32>(pub T);
+
+impl<T, const DECIMALS: u32> Betweenable for Fixed<T, DECIMALS>
+  where
+    T: Betweenable {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    T::between(low.0, high.0).map(Fixed)
+  }
+}
+
+impl<T, const DECIMALS: u32> fmt::Display for Fixed<T, DECIMALS>
+  where
+    T: Copy + Into<i128> {
+  /// Formats with all `DECIMALS` digits, as in `-0.50`.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let raw: i128 = self.0.into();
+    let sign = if raw < 0 { "-" } else { "" };
+    let raw = raw.unsigned_abs();
+    match 10_u128.checked_pow(DECIMALS) {
+      Some(1) => write!(f, "{}{}", sign, raw),
+      Some(unit) => {
+        let width = DECIMALS as usize;
+        write!(f, "{}{}.{:0width$}", sign, raw / unit, raw % unit)
+      },
+      // No integer type has that many digits, so the whole part is zero.
+      None => {
+        let width = DECIMALS as usize;
+        write!(f, "{}0.{:0>width$}", sign, raw)
+      },
+    }
+  }
+}
+
+/// Decimals are bisected at the larger scale of the two ends, so searching
+/// from `0.00` to `100.00` probes only whole cents, while searching from
+/// `0` to `100` probes only integers. There's nothing between two values
+/// that can't be brought to a common scale.
+#[cfg(feature = "rust_decimal")]
+impl Betweenable for rust_decimal::Decimal {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    let scale = low.scale().max(high.scale());
+    let at_scale = |x: Self| {
+      10_i128.checked_pow(scale - x.scale())?.checked_mul(x.mantissa())
+    };
+    let mid = i128::between(at_scale(low)?, at_scale(high)?)?;
+    rust_decimal::Decimal::try_from_i128_with_scale(mid, scale).ok()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{binary_search, Direction};
+
+  #[test]
+  fn searches_fixed_point() {
+    type Price = Fixed<i64, 2>;
+    // The highest price at which an order for 7 units still fills, with
+    // demand falling by a unit every 1.37.
+    let fills = |p: Price| 100 - p.0 / 137 >= 7;
+    let ((highest, ()), _) =
+      binary_search((Fixed(0), ()), (Fixed(100_000), ()), |p: Price| {
+        if fills(p) { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(highest, Fixed(12_877));
+    assert_eq!(highest.to_string(), "128.77");
+    assert_eq!(Fixed::<i32, 3>(-50).to_string(), "-0.050");
+    assert_eq!(Fixed::<u8, 0>(7).to_string(), "7");
+    assert_eq!(Fixed::<i8, 40>(5).to_string(), format!("0.{:0>40}", 5));
+  }
+
+  #[cfg(feature = "rust_decimal")]
+  #[test]
+  fn searches_decimals() {
+    use rust_decimal::Decimal;
+
+    let cents = |x: i64| Decimal::new(x, 2);
+    assert_eq!(Decimal::between(cents(100), cents(103)), Some(cents(101)));
+    assert_eq!(Decimal::between(cents(100), cents(101)), None);
+    // `1` and `1.5` are bisected in tenths.
+    assert_eq!(
+      Decimal::between(Decimal::ONE, Decimal::new(15, 1)),
+      Some(Decimal::new(12, 1)),
+    );
+    let ((low, ()), (high, ())) =
+      binary_search((cents(0), ()), (cents(10_000), ()), |p| {
+        if p * Decimal::from(3) < Decimal::from(100) {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!((low, high), (cents(3333), cents(3334)));
+  }
+}
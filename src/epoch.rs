@@ -0,0 +1,84 @@
+//! Searching ID spaces that are split into epochs, such as sequence numbers
+//! that restart every day, where IDs are ordered by epoch and then by
+//! offset within the epoch.
+
+use crate::compose::product;
+use crate::{Betweenable, Bracket, Direction};
+
+/// Where the transition of a search over the whole ID space lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Located<X, A, B> {
+  /// The very first ID is already high.
+  BeforeFirst((X, B)),
+  /// The transition is between these IDs.
+  Between(Bracket<X, A, B>),
+  /// The very last ID is still low.
+  AfterLast((X, A)),
+}
+
+/// Search the IDs of epochs `first` through `last`, where `bounds(epoch)`
+/// gives the inclusive range of offsets in `epoch`.
+///
+/// Every epoch must have at least one ID. `bounds` is only called for the
+/// epochs the search visits, about `log` of the number of epochs of them.
+pub fn search_epochs<E, O, A, B, R, F>(
+    first: E,
+    last: E,
+    mut bounds: R,
+    mut f: F,
+  ) -> Located<(E, O), A, B>
+  where
+    E: Betweenable + PartialEq,
+    O: Betweenable + PartialOrd,
+    R: FnMut(E) -> (O, O),
+    F: FnMut((E, O)) -> Direction<A, B> {
+  let start = (first, (bounds)(first).0);
+  let low =
+    match (f)(start) {
+      Direction::Low(a) => (start, a),
+      Direction::High(b) => return Located::BeforeFirst((start, b)),
+    };
+  let end = (last, (bounds)(last).1);
+  let high =
+    match (f)(end) {
+      Direction::High(b) => (end, b),
+      Direction::Low(a) => return Located::AfterLast((end, a)),
+    };
+  Located::Between(product(low, high, bounds, f))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn search_epochs_test() {
+    // Orders numbered from 1 each day, with more orders on later days.
+    let bounds = |day: u32| (1_u32, 10 + day * day);
+    let threshold = |t: (u32, u32)| {
+      move |id: (u32, u32)| {
+        if id < t {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      }
+    };
+    assert_eq!(
+      search_epochs(0, 30, bounds, threshold((17, 250))),
+      Located::Between((((17, 249), ()), ((17, 250), ()))),
+    );
+    assert_eq!(
+      search_epochs(0, 30, bounds, threshold((17, 1))),
+      Located::Between((((16, 266), ()), ((17, 1), ()))),
+    );
+    assert_eq!(
+      search_epochs(0, 30, bounds, threshold((0, 1))),
+      Located::BeforeFirst(((0, 1), ())),
+    );
+    assert_eq!(
+      search_epochs(0, 30, bounds, threshold((31, 1))),
+      Located::AfterLast(((30, 910), ())),
+    );
+  }
+}
@@ -0,0 +1,92 @@
+//! Searches that stop as soon as the predicate recognises its target.
+//!
+//! `Direction` only has the two sides of the transition, so these take a
+//! three-way answer instead, for predicates that can tell when they've hit
+//! exactly what they're looking for, such as a key comparison.
+
+use crate::{Betweenable, Direction};
+
+/// A three-way answer from a predicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison<A, B, C> {
+  Low(A),
+  Exact(C),
+  High(B),
+}
+
+impl<A, B, C> From<Direction<A, B>> for Comparison<A, B, C> {
+  fn from(direction: Direction<A, B>) -> Self {
+    match direction {
+      Direction::Low(a) => Comparison::Low(a),
+      Direction::High(b) => Comparison::High(b),
+    }
+  }
+}
+
+/// The outcome of `binary_search_exact`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Found<X, A, B, C> {
+  /// The predicate answered `Exact` at this point.
+  Exact(X, C),
+  /// The predicate never answered `Exact`, and transitions between these
+  /// points, as with `binary_search`.
+  Between((X, A), (X, B)),
+}
+
+/// Like `binary_search`, but stopping at the first probe that `f` answers
+/// with `Exact`.
+pub fn binary_search_exact<X, A, B, C, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    mut f: F,
+  ) -> Found<X, A, B, C>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Comparison<A, B, C> {
+  while let Some(x) = X::between(low.0, high.0) {
+    match (f)(x) {
+      Comparison::Low(a) => {
+        low = (x, a);
+      },
+      Comparison::High(b) => {
+        high = (x, b);
+      },
+      Comparison::Exact(c) => {
+        return Found::Exact(x, c);
+      },
+    }
+  }
+  Found::Between(low, high)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cmp::Ordering;
+
+  #[test]
+  fn stops_on_exact_match() {
+    let values = [1, 3, 5, 7, 9, 11, 13];
+    let mut probes = 0;
+    let compare = |key: i32| {
+      move |i: usize| {
+        match values[i].cmp(&key) {
+          Ordering::Less => Comparison::Low(()),
+          Ordering::Equal => Comparison::Exact(values[i]),
+          Ordering::Greater => Comparison::High(()),
+        }
+      }
+    };
+    let found =
+      binary_search_exact((0, ()), (6, ()), |i| {
+        probes += 1;
+        (compare(7))(i)
+      });
+    assert_eq!(found, Found::Exact(3, 7));
+    assert_eq!(probes, 1);
+    assert_eq!(
+      binary_search_exact((0, ()), (6, ()), compare(8)),
+      Found::Between((3, ()), (4, ())),
+    );
+  }
+}
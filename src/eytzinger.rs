@@ -0,0 +1,139 @@
+//! Sorted tables stored in Eytzinger (breadth-first) order.
+//!
+//! The Eytzinger layout stores a sorted table as an implicit binary search
+//! tree, with the root first, then its two children, then their four, and
+//! so on. A search walks down the tree, so the first few levels are shared
+//! by every search and stay in cache, and the children of a node are next
+//! to each other, so the nodes a few levels down can be prefetched while
+//! the current one is compared. This pays off on tables much bigger than
+//! the cache, where plain bisection misses on almost every probe; on small
+//! tables `slice::partition_point` is as fast or faster. `cargo bench
+//! --bench lookup` compares them.
+
+/// A sorted table in Eytzinger order.
+///
+/// Node `k` (one-based) is stored at offset `k - 1`, and its children are
+/// nodes `2k` and `2k + 1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Eytzinger<T> {
+  values: Vec<T>,
+}
+
+impl<T> Eytzinger<T>
+  where
+    T: Ord {
+  /// Lay out `values`, which must be sorted.
+  pub fn new(values: Vec<T>) -> Self {
+    let len = values.len();
+    let mut sorted: Vec<Option<T>> = values.into_iter().map(Some).collect();
+    let values =
+      (1..=len)
+        .filter_map(|k| sorted.get_mut(rank(k, len)).and_then(Option::take))
+        .collect();
+    Eytzinger { values }
+  }
+
+  pub fn len(&self) -> usize {
+    self.values.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+
+  /// Find the index, in sorted order, of the first element that is not
+  /// less than `key`.
+  pub fn lower_bound(&self, key: &T) -> usize {
+    self.search(|x| x < key)
+  }
+
+  /// Find the index, in sorted order, of the first element that is greater
+  /// than `key`.
+  pub fn upper_bound(&self, key: &T) -> usize {
+    self.search(|x| x <= key)
+  }
+
+  #[inline(always)]
+  fn search<P>(&self, is_low: P) -> usize
+    where
+      P: Fn(&T) -> bool {
+    let len = self.values.len();
+    let mut k = 1;
+    while k <= len {
+      prefetch(self.values.as_ptr().wrapping_add(k.wrapping_mul(16)));
+      // SAFETY: `1 <= k <= len`.
+      let x = unsafe { self.values.get_unchecked(k - 1) };
+      k = std::hint::select_unpredictable((is_low)(x), 2 * k + 1, 2 * k);
+    }
+    // The walk went right each time it passed a low node, and then left
+    // once at the first high one. Undoing those steps leaves that node, or
+    // zero if every node was low.
+    match k.checked_shr(k.trailing_ones() + 1).unwrap_or(0) {
+      0 => len,
+      k => rank(k, len),
+    }
+  }
+}
+
+/// The index in sorted order of node `k` of a tree of `len` nodes, where
+/// `1 <= k <= len`.
+///
+/// In a perfect tree, the nodes at each depth are evenly spaced in sorted
+/// order, so a node's rank follows from its depth and its position along
+/// its level. The tree is only missing nodes from the right of its last
+/// level, and those that would have come before `k` are then taken off.
+fn rank(k: usize, len: usize) -> usize {
+  let levels = usize::BITS - len.leading_zeros();
+  let depth = usize::BITS - 1 - k.leading_zeros();
+  let along = k - (1 << depth);
+  let perfect = ((2 * along + 1) << (levels - 1 - depth)) - 1;
+  // The last level's nodes alternate with the rest in sorted order.
+  let last_level: usize = 1 << (levels - 1);
+  let missing =
+    last_level
+      .saturating_add(perfect.div_ceil(2))
+      .saturating_sub(len)
+      .saturating_sub(1);
+  perfect - missing
+}
+
+/// Hint that the cache line at `p` will be read soon. `p` needn't point
+/// into an allocation.
+#[inline(always)]
+fn prefetch<T>(p: *const T) {
+  #[cfg(target_arch = "x86_64")]
+  {
+    use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+    // SAFETY: SSE is part of the x86-64 baseline, and prefetching doesn't
+    // access memory, so any address will do.
+    unsafe { _mm_prefetch::<_MM_HINT_T0>(p.cast()) };
+  }
+  #[cfg(not(target_arch = "x86_64"))]
+  let _ = p;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_partition_point() {
+    for len in 0..70 {
+      let values: Vec<u32> = (0..len).map(|i| i / 3 * 2).collect();
+      let eytzinger = Eytzinger::new(values.clone());
+      assert_eq!(eytzinger.len(), values.len());
+      for key in 0..=len {
+        assert_eq!(
+          eytzinger.lower_bound(&key),
+          values.partition_point(|&x| x < key),
+        );
+        assert_eq!(
+          eytzinger.upper_bound(&key),
+          values.partition_point(|&x| x <= key),
+        );
+      }
+    }
+    let names = Eytzinger::new(vec!["ann".to_string(), "bob".to_string()]);
+    assert_eq!(names.lower_bound(&"b".to_string()), 1);
+  }
+}
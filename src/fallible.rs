@@ -0,0 +1,185 @@
+//! Searches over data sources where reading a record can fail.
+//!
+//! Rather than panicking on a corrupt or unreachable record, these stop at
+//! the first failure and hand back the error along with how far the search
+//! had got.
+
+use std::fmt;
+
+use crate::partition_point;
+
+/// A search that was stopped by an error reading the record at `position`.
+///
+/// Every index below `low` was known to be low, and every index from
+/// `high` onwards was known to be high, so the answer lies in
+/// `low..=high`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Interrupted<E> {
+  pub error: E,
+  pub position: usize,
+  pub low: usize,
+  pub high: usize,
+}
+
+impl<E> fmt::Display for Interrupted<E>
+  where
+    E: fmt::Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "search interrupted reading record {} (answer in {}..={}): {}",
+      self.position,
+      self.low,
+      self.high,
+      self.error,
+    )
+  }
+}
+
+impl<E> std::error::Error for Interrupted<E>
+  where
+    E: std::error::Error {}
+
+/// Find the number of leading records for which `is_low` holds, where
+/// `get(i)` reads the `i`th of `len` records.
+pub fn try_partition_point<T, E, G, P>(
+    len: usize,
+    mut get: G,
+    mut is_low: P,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    G: FnMut(usize) -> Result<T, E>,
+    P: FnMut(&T) -> bool {
+  let mut low = 0;
+  let mut high = len;
+  let mut interrupted = None;
+  let index =
+    partition_point(len, |i| {
+      if interrupted.is_some() {
+        return false;
+      }
+      match (get)(i) {
+        Ok(record) => {
+          let below = (is_low)(&record);
+          if below {
+            low = low.max(i + 1);
+          } else {
+            high = high.min(i);
+          }
+          below
+        },
+        Err(error) => {
+          interrupted = Some(Interrupted { error, position: i, low, high });
+          false
+        },
+      }
+    });
+  match interrupted {
+    Some(interrupted) => Err(interrupted),
+    None => Ok(index),
+  }
+}
+
+/// Find the index of the first record that is not less than `key`.
+pub fn try_lower_bound<T, E, G>(
+    len: usize,
+    get: G,
+    key: &T,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    T: Ord,
+    G: FnMut(usize) -> Result<T, E> {
+  try_partition_point(len, get, |record| record < key)
+}
+
+/// Find the index of the first record that is greater than `key`.
+pub fn try_upper_bound<T, E, G>(
+    len: usize,
+    get: G,
+    key: &T,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    T: Ord,
+    G: FnMut(usize) -> Result<T, E> {
+  try_partition_point(len, get, |record| record <= key)
+}
+
+/// Like `try_lower_bound`, but over the items of `iter`, such as a stream
+/// of records being decoded.
+///
+/// Each probe clones `iter` and skips ahead with `nth`, so this is for
+/// iterators that can skip cheaply, without decoding the items in between.
+pub fn try_lower_bound_iter<I, T, E>(
+    iter: I,
+    key: &T,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    I: ExactSizeIterator<Item = Result<T, E>> + Clone,
+    T: Ord {
+  try_partition_point_iter(iter, |record| record < key)
+}
+
+/// Like `try_upper_bound`, but over the items of `iter`, as with
+/// `try_lower_bound_iter`.
+pub fn try_upper_bound_iter<I, T, E>(
+    iter: I,
+    key: &T,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    I: ExactSizeIterator<Item = Result<T, E>> + Clone,
+    T: Ord {
+  try_partition_point_iter(iter, |record| record <= key)
+}
+
+fn try_partition_point_iter<I, T, E, P>(
+    iter: I,
+    mut is_low: P,
+  ) -> Result<usize, Interrupted<E>>
+  where
+    I: ExactSizeIterator<Item = Result<T, E>> + Clone,
+    P: FnMut(&T) -> bool {
+  // An iterator that ends early is treated as if it were high from there.
+  try_partition_point(
+    iter.len(),
+    |i| iter.clone().nth(i).transpose(),
+    |record| record.as_ref().is_some_and(&mut is_low),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode(record: &[u8]) -> Result<u16, String> {
+    match *record {
+      [a, b] => Ok(u16::from_be_bytes([a, b])),
+      _ => Err(format!("bad record {:?}", record)),
+    }
+  }
+
+  #[test]
+  fn propagates_errors_with_partial_bracket() {
+    let records: Vec<&[u8]> =
+      vec![&[0, 1], &[0, 5], &[0, 9], &[1, 0], &[7], &[2, 0], &[3, 0]];
+    let get = |i: usize| decode(records[i]);
+    assert_eq!(try_lower_bound(records.len(), get, &5), Ok(1));
+    assert_eq!(try_upper_bound(records.len(), get, &5), Ok(2));
+    assert_eq!(
+      try_lower_bound(records.len(), get, &600),
+      Err(Interrupted {
+        error: "bad record [7]".to_string(),
+        position: 4,
+        low: 4,
+        high: 6,
+      }),
+    );
+
+    let decoded = records.iter().map(|record| decode(record));
+    assert_eq!(try_lower_bound_iter(decoded.clone(), &5), Ok(1));
+    assert_eq!(try_upper_bound_iter(decoded.clone(), &100), Ok(3));
+    assert_eq!(
+      try_upper_bound_iter(decoded, &700).map_err(|e| e.position),
+      Err(4),
+    );
+  }
+}
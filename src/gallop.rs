@@ -0,0 +1,351 @@
+//! Galloping (exponential) searches, for when the transition is expected
+//! to be near a known point, or the bounds aren't known at all.
+
+use core::convert::TryFrom;
+use core::ops::Bound;
+
+use crate::{binary_search, Betweenable, Bracket, Direction};
+
+/// Types that can be stepped a distance forwards or backwards.
+pub trait Steppable: Betweenable + Ord {
+  /// `self + distance`, or `None` if that isn't representable.
+  fn forward(self, distance: u128) -> Option<Self>;
+
+  /// `self - distance`, or `None` if that isn't representable.
+  fn backward(self, distance: u128) -> Option<Self>;
+
+  const MIN: Self;
+  const MAX: Self;
+}
+
+macro_rules! impl_steppable {
+  ($($t:ty),*) => {
+    $(
+      impl Steppable for $t {
+        fn forward(self, distance: u128) -> Option<Self> {
+          let distance = i128::try_from(distance).ok()?;
+          i128::try_from(self).ok()?.checked_add(distance)
+            .and_then(|x| <$t>::try_from(x).ok())
+        }
+
+        fn backward(self, distance: u128) -> Option<Self> {
+          let distance = i128::try_from(distance).ok()?;
+          i128::try_from(self).ok()?.checked_sub(distance)
+            .and_then(|x| <$t>::try_from(x).ok())
+        }
+
+        const MIN: Self = <$t>::MIN;
+        const MAX: Self = <$t>::MAX;
+      }
+    )*
+  };
+}
+
+impl_steppable!(u8, u16, u32, u64, usize, i8, i16, i32, i64, i128, isize);
+
+impl Steppable for u128 {
+  fn forward(self, distance: u128) -> Option<Self> {
+    self.checked_add(distance)
+  }
+
+  fn backward(self, distance: u128) -> Option<Self> {
+    self.checked_sub(distance)
+  }
+
+  const MIN: Self = u128::MIN;
+  const MAX: Self = u128::MAX;
+}
+
+/// The result of a gallop that stops at the limits of the type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Saturated<X, A, B> {
+  /// The transition was found.
+  Found(Bracket<X, A, B>),
+  /// Every value up to and including `X::MAX` was low, so the transition,
+  /// if there is one, lies above what the type can represent.
+  AboveMax((X, A)),
+  /// Every value down to and including `X::MIN` was high.
+  BelowMin((X, B)),
+}
+
+impl<X, A, B> Saturated<X, A, B> {
+  /// The bracket, if the transition was found.
+  pub fn found(self) -> Option<Bracket<X, A, B>> {
+    match self {
+      Saturated::Found(bracket) => Some(bracket),
+      Saturated::AboveMax(_) | Saturated::BelowMin(_) => None,
+    }
+  }
+}
+
+/// Gallop upwards from `low`, probing at doubling distances from it, until
+/// a probe is high.
+///
+/// The probes stop at `limit`. An included limit is probed when the next
+/// distance would overshoot it, an excluded one never is, and without a
+/// limit the probes go up to `X::MAX`. Returns the bracket around the first
+/// high probe, ready to bisect, or the highest low probed if the probes
+/// reached the limit without finding a high one.
+pub(crate) fn gallop_up<X, A, B, F>(
+    low: (X, A),
+    limit: Bound<X>,
+    f: &mut F,
+  ) -> Result<Bracket<X, A, B>, (X, A)>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  let start = low.0;
+  let mut low = low;
+  let mut distance = 1;
+  loop {
+    let next = start.forward(distance);
+    let x =
+      match limit {
+        Bound::Excluded(limit) => {
+          match next {
+            Some(x) if x < limit => x,
+            _ => return Err(low),
+          }
+        },
+        Bound::Included(limit) if low.0 < limit => {
+          next.filter(|&x| x <= limit).unwrap_or(limit)
+        },
+        Bound::Unbounded if low.0 < X::MAX => next.unwrap_or(X::MAX),
+        Bound::Included(_) | Bound::Unbounded => return Err(low),
+      };
+    match (f)(x) {
+      Direction::Low(a) => low = (x, a),
+      Direction::High(b) => return Ok((low, (x, b))),
+    }
+    distance = distance.saturating_mul(2);
+  }
+}
+
+/// Like `gallop_up`, but downwards from `high`, until a probe is low,
+/// without going below `limit`, or `X::MIN` without one.
+pub(crate) fn gallop_down<X, A, B, F>(
+    high: (X, B),
+    limit: Bound<X>,
+    f: &mut F,
+  ) -> Result<Bracket<X, A, B>, (X, B)>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  let start = high.0;
+  let mut high = high;
+  let mut distance = 1;
+  loop {
+    let next = start.backward(distance);
+    let x =
+      match limit {
+        Bound::Excluded(limit) => {
+          match next {
+            Some(x) if x > limit => x,
+            _ => return Err(high),
+          }
+        },
+        Bound::Included(limit) if high.0 > limit => {
+          next.filter(|&x| x >= limit).unwrap_or(limit)
+        },
+        Bound::Unbounded if high.0 > X::MIN => next.unwrap_or(X::MIN),
+        Bound::Included(_) | Bound::Unbounded => return Err(high),
+      };
+    match (f)(x) {
+      Direction::High(b) => high = (x, b),
+      Direction::Low(a) => return Ok(((x, a), high)),
+    }
+    distance = distance.saturating_mul(2);
+  }
+}
+
+/// Find the transition nearest to `pivot`, galloping away from it in
+/// whichever direction the transition lies and then bisecting.
+///
+/// This takes `O(log d)` probes, where `d` is the distance from `pivot` to
+/// the transition. Returns `None` if the gallop reaches the end of the type
+/// without finding the transition.
+pub fn nearest_transition<X, A, B, F>(
+    pivot: X,
+    f: F,
+  ) -> Option<Bracket<X, A, B>>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  nearest_transition_saturating(pivot, f).found()
+}
+
+/// Like `nearest_transition`, but when the gallop reaches the end of the
+/// type, it reports how far the search got, so that the caller can tell
+/// a transition beyond the type's range from a missing one, and retry with
+/// a wider type if need be.
+pub fn nearest_transition_saturating<X, A, B, F>(
+    pivot: X,
+    mut f: F,
+  ) -> Saturated<X, A, B>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  match (f)(pivot) {
+    Direction::Low(a) => {
+      match gallop_up((pivot, a), Bound::Unbounded, &mut f) {
+        Ok((low, high)) => Saturated::Found(binary_search(low, high, f)),
+        Err(low) => Saturated::AboveMax(low),
+      }
+    },
+    Direction::High(b) => {
+      match gallop_down((pivot, b), Bound::Unbounded, &mut f) {
+        Ok((low, high)) => Saturated::Found(binary_search(low, high, f)),
+        Err(high) => Saturated::BelowMin(high),
+      }
+    },
+  }
+}
+
+/// Find the transition nearest to `hint`, as `nearest_transition` does, but
+/// within `bounds` if given, whose ends are taken to be low and high
+/// without being probed, as they are by `binary_search`.
+///
+/// The gallop from `hint` stops at the bounds, and the search then bisects
+/// what's left, so it takes `O(log d)` probes when the transition is `d`
+/// from `hint`, and never many more than `binary_search` would when it's
+/// far. A `hint` outside the bounds is ignored. Returns `None` only without
+/// bounds, when `nearest_transition` would.
+pub fn binary_search_near<X, A, B, F>(
+    hint: X,
+    bounds: Option<Bracket<X, A, B>>,
+    mut f: F,
+  ) -> Option<Bracket<X, A, B>>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  let (low, high) =
+    match bounds {
+      Some(bounds) => bounds,
+      None => return nearest_transition(hint, f),
+    };
+  if !(low.0 < hint && hint < high.0) {
+    return Some(binary_search(low, high, f));
+  }
+  // Gallop away from `hint` until a probe crosses the transition or the
+  // next would leave the bracket.
+  let (low, high) =
+    match (f)(hint) {
+      Direction::Low(a) => {
+        gallop_up((hint, a), Bound::Excluded(high.0), &mut f)
+          .unwrap_or_else(|low| (low, high))
+      },
+      Direction::High(b) => {
+        gallop_down((hint, b), Bound::Excluded(low.0), &mut f)
+          .unwrap_or_else(|high| (low, high))
+      },
+    };
+  Some(binary_search(low, high, f))
+}
+
+/// Search upwards from `low`, which is known to be low, when there's no
+/// known upper bound.
+///
+/// This gallops, doubling the distance from `low` until a probe is high,
+/// and then bisects, taking `O(log d)` probes where `d` is the distance to
+/// the transition. If every value up to and including `X::MAX` is low, it
+/// returns the highest of them as the error.
+pub fn binary_search_unbounded<X, A, B, F>(
+    low: (X, A),
+    mut f: F,
+  ) -> Result<Bracket<X, A, B>, (X, A)>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  gallop_up(low, Bound::Unbounded, &mut f)
+    .map(|(low, high)| binary_search(low, high, f))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn threshold(t: i64) -> impl FnMut(i64) -> Direction<(), ()> {
+    move |x| {
+      if x < t {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    }
+  }
+
+  #[test]
+  fn finds_nearby_transitions_cheaply() {
+    for &(pivot, t) in [(0, 5), (100, 3), (-7, -7), (1000, 1001)].iter() {
+      let mut probes = 0;
+      let mut f = threshold(t);
+      let result =
+        nearest_transition(pivot, |x| {
+          probes += 1;
+          (f)(x)
+        });
+      assert_eq!(result, Some(((t - 1, ()), (t, ()))));
+      assert!(probes <= 16, "{} probes", probes);
+    }
+  }
+
+  #[test]
+  fn reports_missing_transitions() {
+    assert_eq!(nearest_transition(0, threshold(i64::MIN)), None);
+    assert_eq!(
+      nearest_transition(0, threshold(i64::MAX)),
+      Some(((i64::MAX - 1, ()), (i64::MAX, ()))),
+    );
+    let all_low = |_: u16| Direction::Low::<(), ()>(());
+    assert_eq!(nearest_transition(3, all_low), None);
+    assert_eq!(
+      nearest_transition_saturating(3, all_low),
+      Saturated::AboveMax((u16::MAX, ())),
+    );
+    assert_eq!(
+      nearest_transition_saturating(0, threshold(i64::MIN)),
+      Saturated::BelowMin((i64::MIN, ())),
+    );
+  }
+
+  #[test]
+  fn unbounded_search_saturates() {
+    for &t in [1, 2, 1000, i64::MAX].iter() {
+      let result = binary_search_unbounded((0, ()), threshold(t));
+      assert_eq!(result, Ok(((t - 1, ()), (t, ()))));
+    }
+    let all_low = |_: u8| Direction::Low::<(), ()>(());
+    assert_eq!(binary_search_unbounded((7, ()), all_low), Err((u8::MAX, ())));
+    assert_eq!(binary_search_unbounded((u8::MAX, ()), all_low), Err((255, ())));
+  }
+
+  #[test]
+  fn near_search_stays_in_bounds() {
+    let bounds = Some(((0, ()), (1_000_000, ())));
+    let cases = [(500, 503), (500, 490), (500, 500), (2, 999_999)];
+    for &(hint, t) in cases.iter() {
+      let mut probes = 0;
+      let mut f = threshold(t);
+      let result =
+        binary_search_near(hint, bounds, |x| {
+          assert!(0 < x && x < 1_000_000, "probed {}", x);
+          probes += 1;
+          (f)(x)
+        });
+      assert_eq!(result, Some(((t - 1, ()), (t, ()))));
+      if (t - hint).abs() < 10 {
+        assert!(probes <= 8, "{} probes", probes);
+      } else {
+        assert!(probes <= 40, "{} probes", probes);
+      }
+    }
+    assert_eq!(
+      binary_search_near(-5, bounds, threshold(1)),
+      Some(((0, ()), (1, ()))),
+    );
+    assert_eq!(
+      binary_search_near(0, None, threshold(-3)),
+      Some(((-4, ()), (-3, ()))),
+    );
+  }
+}
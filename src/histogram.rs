@@ -0,0 +1,68 @@
+//! Finding the histogram bucket a value falls in, as metrics libraries do
+//! when recording observations.
+//!
+//! A histogram with `n` boundaries, sorted in increasing order, has `n + 1`
+//! buckets. The first is everything below the first boundary and the last
+//! is everything above the last, so every value lands in some bucket.
+
+use crate::partition_point;
+
+/// Which bucket a value equal to a boundary goes in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OnBoundary {
+  /// The bucket above the boundary, so buckets are `[low, high)`.
+  Above,
+  /// The bucket below the boundary, so buckets are `(low, high]`, as with
+  /// Prometheus' `le` buckets.
+  Below,
+}
+
+/// Find the index of the bucket `value` falls in.
+///
+/// Values that aren't comparable with the boundaries, such as NaN, go in
+/// the first bucket.
+pub fn bucket<T>(boundaries: &[T], value: &T, on_boundary: OnBoundary) -> usize
+  where
+    T: PartialOrd {
+  partition_point(boundaries.len(), |i| {
+    boundaries.get(i).is_some_and(|boundary| {
+      match on_boundary {
+        OnBoundary::Above => boundary <= value,
+        OnBoundary::Below => boundary < value,
+      }
+    })
+  })
+}
+
+/// Find the index of the bucket each of `values` falls in.
+pub fn buckets<T>(
+    boundaries: &[T],
+    values: &[T],
+    on_boundary: OnBoundary,
+  ) -> Vec<usize>
+  where
+    T: PartialOrd {
+  values.iter().map(|value| bucket(boundaries, value, on_boundary)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn locates_buckets() {
+    let boundaries = [0.005, 0.01, 0.1, 1.0, 10.0];
+    let values = [0.0, 0.005, 0.05, 1.0, 9.99, 10.0, 1e9, -1.0, f64::NAN];
+    assert_eq!(
+      buckets(&boundaries, &values, OnBoundary::Above),
+      vec![0, 1, 2, 4, 4, 5, 5, 0, 0],
+    );
+    assert_eq!(
+      buckets(&boundaries, &values, OnBoundary::Below),
+      vec![0, 0, 2, 3, 4, 4, 5, 0, 0],
+    );
+    assert_eq!(bucket(&[], &3, OnBoundary::Above), 0);
+    assert_eq!(bucket(&[1, 2, 2, 3], &2, OnBoundary::Above), 3);
+    assert_eq!(bucket(&[1, 2, 2, 3], &2, OnBoundary::Below), 1);
+  }
+}
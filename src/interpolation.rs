@@ -0,0 +1,291 @@
+//! Interpolation search over sorted numeric keys.
+//!
+//! Interpolation guesses where a key lies from the values at the ends of
+//! the current range, which takes `O(log log n)` probes on roughly uniform
+//! data. On skewed data it can take `O(n)`, so the searches here watch how
+//! well the guesses are doing and fall back to plain bisection.
+
+use crate::float::Float;
+use crate::{Betweenable, Bracket, Strategy};
+
+/// Keys that can be interpolated between.
+pub trait Numeric: Copy + PartialOrd {
+  fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_numeric {
+  ($($t:ty),*) => {
+    $(
+      impl Numeric for $t {
+        fn to_f64(self) -> f64 {
+          self as f64
+        }
+      }
+    )*
+  };
+}
+
+impl_numeric!(
+  u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// Positions that a search can interpolate between.
+pub trait Interpolate: Copy {
+  /// The point about `t` of the way from `low` to `high`, moved
+  /// strictly between them if it rounds to either, or `None` if nothing is
+  /// between them.
+  fn interpolate(low: Self, high: Self, fraction: f64) -> Option<Self>;
+
+  /// The point to bisect at once interpolation has been given up on.
+  fn bisect(low: Self, high: Self) -> Option<Self>;
+}
+
+macro_rules! impl_interpolate_int {
+  ($($t:ty),*) => {
+    $(
+      impl Interpolate for $t {
+        fn interpolate(low: Self, high: Self, t: f64) -> Option<Self> {
+          let (first, last) = (low.checked_add(1)?, high.checked_sub(1)?);
+          if first > last {
+            return None;
+          }
+          let (a, b) = (low as f64, high as f64);
+          // Casts from `f64` saturate, and the clamp keeps the point inside
+          // after rounding.
+          let x = (a + t * (b - a)) as $t;
+          Some(x.clamp(first, last))
+        }
+
+        fn bisect(low: Self, high: Self) -> Option<Self> {
+          Betweenable::between(low, high)
+        }
+      }
+    )*
+  };
+}
+
+impl_interpolate_int!(u8, u16, u32, u64, u128, usize);
+impl_interpolate_int!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! impl_interpolate_float {
+  ($($t:ty),*) => {
+    $(
+      impl Interpolate for $t {
+        fn interpolate(low: Self, high: Self, t: f64) -> Option<Self> {
+          let x = low + (t as $t) * (high - low);
+          if low < x && x < high {
+            Some(x)
+          } else {
+            Self::bisect(low, high)
+          }
+        }
+
+        /// Bisects the bit patterns, as `float::binary_search` does, as
+        /// the arithmetic midpoint can stall.
+        fn bisect(low: Self, high: Self) -> Option<Self> {
+          let (low, high) = (low.to_ordered(), high.to_ordered());
+          Betweenable::between(low, high).map(<$t>::from_ordered)
+        }
+      }
+    )*
+  };
+}
+
+impl_interpolate_float!(f32, f64);
+
+/// A `Strategy` that interpolates between the witnesses at the ends of the
+/// bracket, which are the values of an increasing function, towards `key`,
+/// bisecting when they can't be interpolated between.
+///
+/// Unlike `binary_search`, it never gives up on interpolating, so it suits
+/// values known to be close to uniform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interpolating<V> {
+  pub key: V,
+}
+
+impl<X, V> Strategy<X, V, V> for Interpolating<V>
+  where
+    X: Interpolate,
+    V: Numeric {
+  fn choose(&mut self, low: &(X, V), high: &(X, V)) -> Option<X> {
+    let (a, b) = (low.1.to_f64(), high.1.to_f64());
+    let fraction = (self.key.to_f64() - a) / (b - a);
+    if fraction.is_finite() {
+      X::interpolate(low.0, high.0, fraction.clamp(0.0, 1.0))
+    } else {
+      X::bisect(low.0, high.0)
+    }
+  }
+}
+
+/// Like `binary_search`, for finding where the increasing function `f`
+/// reaches `key`, choosing each probe by interpolating between the values
+/// at the ends of the bracket.
+///
+/// `low` and `high` are given with the values of `f` there, and each probe
+/// is low if its value is less than `key`, so the bracket found is the
+/// last point below `key` and the first at or above it, with their values.
+/// As with `lower_bound`, guesses are made until `max_bad_guesses` of them
+/// have failed to at least halve the bracket, and then the search bisects.
+pub fn binary_search<X, V, F>(
+    mut low: (X, V),
+    mut high: (X, V),
+    key: V,
+    max_bad_guesses: usize,
+    mut f: F,
+  ) -> Bracket<X, V, V>
+  where
+    X: Interpolate,
+    V: Numeric,
+    F: FnMut(X) -> V {
+  let mut bad_guesses = 0;
+  loop {
+    let (a, b) = (low.1.to_f64(), high.1.to_f64());
+    let fraction = ((key.to_f64() - a) / (b - a)).clamp(0.0, 1.0);
+    let guessing = bad_guesses < max_bad_guesses && fraction.is_finite();
+    let x =
+      if guessing {
+        X::interpolate(low.0, high.0, fraction)
+      } else {
+        X::bisect(low.0, high.0)
+      };
+    let x =
+      match x {
+        Some(x) => x,
+        None => return (low, high),
+      };
+    let value = (f)(x);
+    // The part of the bracket kept, as a fraction of it, by where the guess
+    // was aimed rather than where it landed, which needs no subtraction.
+    let kept =
+      if value < key {
+        low = (x, value);
+        1.0 - fraction
+      } else {
+        high = (x, value);
+        fraction
+      };
+    if guessing && kept > 0.5 {
+      bad_guesses += 1;
+    }
+  }
+}
+
+/// Find the index of the first element of `slice` that is not less than
+/// `key`, as `slice::partition_point(|x| x < key)` would.
+///
+/// Each probe is an interpolation guess until `max_bad_guesses` of them
+/// have failed to at least halve the range, and a bisection from then on,
+/// so the worst case is `O(log n + max_bad_guesses)` probes.
+pub fn lower_bound<T>(slice: &[T], key: T, max_bad_guesses: usize) -> usize
+  where
+    T: Numeric {
+  let mut low = 0;
+  let mut high = slice.len();
+  let mut bad_guesses = 0;
+  while low < high {
+    let width = high - low;
+    let probe =
+      if bad_guesses < max_bad_guesses {
+        guess(slice, low, high, key)
+      } else {
+        low + width / 2
+      };
+    match slice.get(probe) {
+      Some(&x) if x < key => low = probe + 1,
+      _ => high = probe,
+    }
+    if high - low > width / 2 {
+      bad_guesses += 1;
+    }
+  }
+  low
+}
+
+/// Interpolate the position of `key` in `slice[low..high]`, which must be
+/// non-empty.
+fn guess<T>(slice: &[T], low: usize, high: usize, key: T) -> usize
+  where
+    T: Numeric {
+  let last = high - 1;
+  match (slice.get(low), slice.get(last)) {
+    (Some(&a), _) if key <= a => low,
+    (_, Some(&b)) if key > b => last,
+    (Some(&a), Some(&b)) => {
+      let (a, b, key) = (a.to_f64(), b.to_f64(), key.to_f64());
+      let fraction = (key - a) / (b - a);
+      let offset = (fraction * (last - low) as f64) as usize;
+      low + offset.min(last - low)
+    },
+    _ => low,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_partition_point() {
+    let uniform: Vec<u32> = (0..1000).map(|i| i * 3).collect();
+    let skewed: Vec<u64> = (0..64).map(|i| 1 << i).collect();
+    for key in 0..3003 {
+      let expected = uniform.partition_point(|&x| x < key);
+      assert_eq!(lower_bound(&uniform, key, 4), expected);
+      assert_eq!(lower_bound(&uniform, key, 0), expected);
+    }
+    for i in 0..64 {
+      for &key in [(1 << i) - 1, 1 << i].iter() {
+        let expected = skewed.partition_point(|&x| x < key);
+        assert_eq!(lower_bound(&skewed, key, 4), expected);
+      }
+    }
+    assert_eq!(lower_bound(&[] as &[f64], 1.0, 4), 0);
+  }
+
+  #[test]
+  fn interpolates_between_witnesses() {
+    let mut probes = 0;
+    let result =
+      binary_search((0_u64, 0), (1 << 40, 3 << 40), 3_000_001, 4, |x| {
+        probes += 1;
+        x * 3
+      });
+    assert_eq!(result, ((1_000_000, 3_000_000), (1_000_001, 3_000_003)));
+    assert!(probes <= 4, "{} probes", probes);
+
+    // Skewed values use up the guesses and fall back to bisection.
+    let mut probes = 0;
+    let result =
+      binary_search((0_u32, 1.0), (1000, 1e300), 1e10, 4, |x| {
+        probes += 1;
+        10f64.powf(0.3 * f64::from(x))
+      });
+    assert_eq!((result.0).0, 33);
+    assert!(probes <= 4 + 10 + 1, "{} probes", probes);
+
+    let ((low, _), (high, _)) =
+      binary_search((0.0_f64, 0.0), (10.0, 1000.0), 10.0, 8, |x| x * x * x);
+    assert!(low < 10f64.cbrt() && 10f64.cbrt() <= high);
+    assert!(high - low <= f64::EPSILON * 4.0);
+
+    let mut probes = 0;
+    let result =
+      crate::binary_search_with_strategy(
+        (0_u64, 0),
+        (1 << 40, 3 << 40),
+        &mut Interpolating { key: 3_000_001 },
+        |x| {
+          probes += 1;
+          if x * 3 < 3_000_001 {
+            crate::Direction::Low(x * 3)
+          } else {
+            crate::Direction::High(x * 3)
+          }
+        },
+      );
+    assert_eq!(result, ((1_000_000, 3_000_000), (1_000_001, 3_000_003)));
+    assert!(probes <= 4, "{} probes", probes);
+  }
+}
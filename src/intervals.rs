@@ -0,0 +1,86 @@
+//! Lookups in sorted lists of non-overlapping intervals, such as routing
+//! tables, address maps and timelines.
+//!
+//! Each interval is a `(start, end, value)` triple covering `start..end`,
+//! and the list is sorted by `start`.
+
+use crate::partition_point;
+
+/// Find the interval containing `point`, and its index.
+pub fn interval_at<'a, K, V>(
+    intervals: &'a [(K, K, V)],
+    point: &K,
+  ) -> Option<(usize, &'a (K, K, V))>
+  where
+    K: Ord {
+  let index =
+    partition_point(intervals.len(), |i| {
+      intervals.get(i).is_some_and(|(start, _, _)| start <= point)
+    });
+  let index = index.checked_sub(1)?;
+  let interval = intervals.get(index)?;
+  (*point < interval.1).then_some((index, interval))
+}
+
+/// Find the intervals that overlap `start..end`.
+pub fn overlapping<'a, K, V>(
+    intervals: &'a [(K, K, V)],
+    start: &K,
+    end: &K,
+  ) -> &'a [(K, K, V)]
+  where
+    K: Ord {
+  // The intervals don't overlap, so their ends are sorted too.
+  let first =
+    partition_point(intervals.len(), |i| {
+      intervals.get(i).is_some_and(|(_, e, _)| e <= start)
+    });
+  let last =
+    partition_point(intervals.len(), |i| {
+      intervals.get(i).is_some_and(|(s, _, _)| s < end)
+    });
+  intervals.get(first..last.max(first)).unwrap_or_default()
+}
+
+/// Find the first interval that starts after `point`, and its index.
+pub fn first_starting_after<'a, K, V>(
+    intervals: &'a [(K, K, V)],
+    point: &K,
+  ) -> Option<(usize, &'a (K, K, V))>
+  where
+    K: Ord {
+  let index =
+    partition_point(intervals.len(), |i| {
+      intervals.get(i).is_some_and(|(start, _, _)| start <= point)
+    });
+  intervals.get(index).map(|interval| (index, interval))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn interval_lookups() {
+    let map = [(0, 10, "a"), (10, 20, "b"), (30, 40, "c"), (50, 51, "d")];
+    let value = |point| interval_at(&map, &point).map(|(_, iv)| iv.2);
+    assert_eq!(value(0), Some("a"));
+    assert_eq!(value(10), Some("b"));
+    assert_eq!(value(25), None);
+    assert_eq!(value(39), Some("c"));
+    assert_eq!(value(51), None);
+    assert_eq!(value(-1), None);
+
+    let values = |start, end| {
+      overlapping(&map, &start, &end).iter().map(|iv| iv.2).collect::<Vec<_>>()
+    };
+    assert_eq!(values(5, 35), vec!["a", "b", "c"]);
+    assert_eq!(values(20, 30), Vec::<&str>::new());
+    assert_eq!(values(19, 31), vec!["b", "c"]);
+    assert_eq!(values(60, 70), Vec::<&str>::new());
+
+    assert_eq!(first_starting_after(&map, &10), Some((2, &(30, 40, "c"))));
+    assert_eq!(first_starting_after(&map, &-5), Some((0, &(0, 10, "a"))));
+    assert_eq!(first_starting_after(&map, &50), None);
+  }
+}
@@ -0,0 +1,439 @@
+//! Searches over sorted files, without loading them into memory.
+//!
+//! Positions in the file are bisected by byte offset. A probe at offset `p`
+//! looks at the first line that starts at or after `p`, so the search
+//! works on arbitrary line lengths and always reports line boundaries.
+//!
+//! Lines end at `\n`, and a `\r` before it is dropped, so files with CRLF
+//! line endings search the same as those without. The last line needn't
+//! have a terminator.
+//!
+//! Files of fixed-length records are searched by record instead, with
+//! `search_records`, or with the `zerocopy` feature, `search_records_as`,
+//! which decodes each probed record as a type.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(feature = "zerocopy")]
+use std::mem::size_of;
+
+#[cfg(feature = "zerocopy")]
+use zerocopy::{FromBytes, Immutable, KnownLayout};
+
+use crate::{binary_search, partition_point, Direction};
+
+const CHUNK: usize = 4096;
+
+/// Find the byte offset of the first line for which `f` returns `High`.
+///
+/// The lines of the file must be sorted with respect to `f`: every `Low`
+/// line comes before every `High` line. Returns the length of the file if
+/// every line is `Low`. Lines are passed to `f` without their terminator.
+pub fn search_lines<R, F>(reader: &mut R, mut f: F) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Direction<(), ()> {
+  search_lines_by(reader, |line| Some((f)(line)))
+}
+
+/// Find the byte offset of the first line for which `compare` doesn't
+/// return `Less`, the way `slice::binary_search_by` compares elements with
+/// the target.
+pub fn lower_bound_lines<R, F>(
+    reader: &mut R,
+    mut compare: F,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Ordering {
+  search_lines(reader, |line| {
+    match (compare)(line) {
+      Ordering::Less => Direction::Low(()),
+      _ => Direction::High(()),
+    }
+  })
+}
+
+/// Like `lower_bound_lines`, but finding the first line for which `compare`
+/// returns `Greater`.
+pub fn upper_bound_lines<R, F>(
+    reader: &mut R,
+    mut compare: F,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Ordering {
+  search_lines(reader, |line| {
+    match (compare)(line) {
+      Ordering::Greater => Direction::High(()),
+      _ => Direction::Low(()),
+    }
+  })
+}
+
+/// Find the index of the first record for which `f` returns `High`, in a
+/// file of records that are each `record_len` bytes long.
+///
+/// Only the probed records are read. Bytes after the last whole record,
+/// such as a record still being appended, are ignored, and the number of
+/// whole records is returned if every one is `Low`. Fails with
+/// `InvalidInput` if `record_len` is zero.
+pub fn search_records<R, F>(
+    reader: &mut R,
+    record_len: usize,
+    mut f: F,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Direction<(), ()> {
+  if record_len == 0 {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "record length is zero",
+    ));
+  }
+  let step = record_len as u64;
+  let count = reader.seek(SeekFrom::End(0))? / step;
+  let count = usize::try_from(count).unwrap_or(usize::MAX);
+  let mut record = vec![0; record_len];
+  let mut error = None;
+  let index =
+    partition_point(count, |i| {
+      if error.is_some() {
+        return false;
+      }
+      let read =
+        reader.seek(SeekFrom::Start(i as u64 * step))
+          .and_then(|_| reader.read_exact(&mut record));
+      match read {
+        Ok(()) => matches!((f)(&record), Direction::Low(())),
+        Err(e) => {
+          error = Some(e);
+          false
+        },
+      }
+    });
+  match error {
+    Some(e) => Err(e),
+    None => Ok(index as u64),
+  }
+}
+
+/// Like `search_records`, but each probed record is decoded as a `T`, in
+/// native byte order, straight from the bytes read, with no allocation
+/// per record.
+///
+/// Fails with `InvalidInput` if `T` is zero-sized.
+#[cfg(feature = "zerocopy")]
+pub fn search_records_as<T, R, F>(reader: &mut R, mut f: F) -> io::Result<u64>
+  where
+    T: FromBytes + Immutable + KnownLayout,
+    R: Read + Seek,
+    F: FnMut(&T) -> Direction<(), ()> {
+  search_records(reader, size_of::<T>(), |bytes| {
+    // `bytes` is always one record long, so this always decodes.
+    match T::read_from_bytes(bytes) {
+      Ok(record) => (f)(&record),
+      Err(_) => Direction::High(()),
+    }
+  })
+}
+
+/// Find the byte offset of the first line whose timestamp is at or after
+/// `target`.
+///
+/// `parse` extracts the timestamp from a line. Lines it can't parse, such as
+/// the continuation lines of a multi-line message, are considered part of
+/// the entry before them, so the offset returned is always that of a line
+/// that parsed.
+pub fn seek_to_time<R, T, P>(
+    reader: &mut R,
+    target: &T,
+    mut parse: P,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    T: Ord,
+    P: FnMut(&[u8]) -> Option<T> {
+  search_lines_by(reader, |line| {
+    (parse)(line).map(|time| {
+      if time < *target {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    })
+  })
+}
+
+/// Like `search_lines`, but `f` may return `None` to defer to the next line.
+fn search_lines_by<R, F>(reader: &mut R, mut f: F) -> io::Result<u64>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Option<Direction<(), ()>> {
+  let len = reader.seek(SeekFrom::End(0))?;
+  let first = decisive_line_at_or_after(reader, 0, len, &mut f)?;
+  if let Direction::High(offset) = first {
+    return Ok(offset);
+  }
+
+  let mut error = None;
+  let (_, (_, offset)) =
+    binary_search((0, ()), (len, len), |pos| {
+      if error.is_some() {
+        return Direction::High(len);
+      }
+      decisive_line_at_or_after(reader, pos, len, &mut f)
+        .unwrap_or_else(|e| {
+          error = Some(e);
+          Direction::High(len)
+        })
+    });
+  match error {
+    Some(e) => Err(e),
+    None => Ok(offset),
+  }
+}
+
+/// Starting from the first line at or after `pos`, find the first line that
+/// `f` decides. If it is `High`, or there is no such line, the offset of the
+/// line (or `len`) is returned as the witness.
+fn decisive_line_at_or_after<R, F>(
+    reader: &mut R,
+    pos: u64,
+    len: u64,
+    f: &mut F,
+  ) -> io::Result<Direction<(), u64>>
+  where
+    R: Read + Seek,
+    F: FnMut(&[u8]) -> Option<Direction<(), ()>> {
+  let mut start = line_start_at_or_after(reader, pos, len)?;
+  let mut line = Vec::new();
+  while start < len {
+    let next = read_line(reader, start, &mut line)?;
+    match (f)(&line) {
+      Some(Direction::Low(())) => return Ok(Direction::Low(())),
+      Some(Direction::High(())) => return Ok(Direction::High(start)),
+      None => start = next,
+    }
+  }
+  Ok(Direction::High(len))
+}
+
+/// Find the offset of the first line that starts at or after `pos`.
+fn line_start_at_or_after<R>(
+    reader: &mut R,
+    pos: u64,
+    len: u64,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek {
+  if pos == 0 {
+    return Ok(0);
+  }
+  let mut offset = pos - 1;
+  reader.seek(SeekFrom::Start(offset))?;
+  let mut buffer = [0; CHUNK];
+  loop {
+    let n = reader.read(&mut buffer)?;
+    if n == 0 {
+      return Ok(len);
+    }
+    if let Some(i) = find_newline(buffer.get(..n).unwrap_or_default()) {
+      return Ok(offset + i as u64 + 1);
+    }
+    offset += n as u64;
+  }
+}
+
+/// Read the line starting at `start` into `line`, without its terminator
+/// or a trailing `\r`, and return the offset of the line after it.
+fn read_line<R>(
+    reader: &mut R,
+    start: u64,
+    line: &mut Vec<u8>,
+  ) -> io::Result<u64>
+  where
+    R: Read + Seek {
+  line.clear();
+  reader.seek(SeekFrom::Start(start))?;
+  let mut buffer = [0; CHUNK];
+  loop {
+    let n = reader.read(&mut buffer)?;
+    if n == 0 {
+      let end = start + line.len() as u64;
+      strip_cr(line);
+      return Ok(end);
+    }
+    let chunk = buffer.get(..n).unwrap_or_default();
+    if let Some(i) = find_newline(chunk) {
+      line.extend_from_slice(chunk.get(..i).unwrap_or_default());
+      let end = start + line.len() as u64 + 1;
+      strip_cr(line);
+      return Ok(end);
+    }
+    line.extend_from_slice(chunk);
+  }
+}
+
+fn strip_cr(line: &mut Vec<u8>) {
+  if line.last() == Some(&b'\r') {
+    line.pop();
+  }
+}
+
+/// Find the first `\n` in `bytes`, testing a word of bytes at a time.
+fn find_newline(bytes: &[u8]) -> Option<usize> {
+  const LSB: u64 = u64::from_ne_bytes([0x01; 8]);
+  const MSB: u64 = u64::from_ne_bytes([0x80; 8]);
+  const NEWLINES: u64 = u64::from_ne_bytes([b'\n'; 8]);
+  let mut words = bytes.chunks_exact(8);
+  let mut offset = 0;
+  for word in &mut words {
+    let mut array = [0; 8];
+    array.copy_from_slice(word);
+    let x = u64::from_ne_bytes(array) ^ NEWLINES;
+    if x.wrapping_sub(LSB) & !x & MSB != 0 {
+      break;
+    }
+    offset += 8;
+  }
+  let rest = bytes.get(offset..).unwrap_or_default();
+  rest.iter().position(|&b| b == b'\n').map(|i| offset + i)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  #[test]
+  fn search_lines_test() {
+    let text = b"apple\nbanana\ncherry\ndate\nelderberry\n";
+    let cases = [("a", 0), ("b", 6), ("c", 13), ("cz", 20), ("z", 36)];
+    for (key, expected) in cases.iter() {
+      let offset =
+        search_lines(&mut Cursor::new(&text[..]), |line| {
+          if line < key.as_bytes() {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        }).unwrap();
+      assert_eq!(offset, *expected, "searching for {}", key);
+    }
+  }
+
+  #[test]
+  fn seek_to_time_skips_unparseable_lines() {
+    let text =
+      b"10 start\n20 failure\n  at frame 1\n  at frame 2\n30 retry\n40 done";
+    let parse = |line: &[u8]| {
+      std::str::from_utf8(line).ok()?.split(' ').next()?.parse::<u32>().ok()
+    };
+    let mut cursor = Cursor::new(&text[..]);
+    assert_eq!(seek_to_time(&mut cursor, &5, parse).unwrap(), 0);
+    assert_eq!(seek_to_time(&mut cursor, &20, parse).unwrap(), 9);
+    assert_eq!(seek_to_time(&mut cursor, &25, parse).unwrap(), 46);
+    assert_eq!(seek_to_time(&mut cursor, &40, parse).unwrap(), 55);
+    assert_eq!(seek_to_time(&mut cursor, &41, parse).unwrap(), 62);
+  }
+
+  #[test]
+  fn handles_crlf_and_unterminated_last_line() {
+    let text = b"apple\r\nbanana\r\ncherry\r\ndate";
+    let search = |key: &[u8]| {
+      search_lines(&mut Cursor::new(&text[..]), |line| {
+        assert!(!line.ends_with(b"\r"));
+        if line < key {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      }).unwrap()
+    };
+    assert_eq!(search(b"banana"), 7);
+    assert_eq!(search(b"cherry"), 15);
+    assert_eq!(search(b"date"), 23);
+    assert_eq!(search(b"z"), 27);
+
+    let long = [vec![b'x'; 20], vec![b'\n'], vec![b'y'; 5]].concat();
+    for end in 0..long.len() {
+      let expected = long[..end].iter().position(|&b| b == b'\n');
+      assert_eq!(find_newline(&long[..end]), expected);
+    }
+  }
+
+  #[test]
+  fn searches_by_comparator_and_record() {
+    let text = b"apple\nbanana\nbanana\ncherry\n";
+    let bounds = |key: &[u8]| {
+      let mut cursor = Cursor::new(&text[..]);
+      (
+        lower_bound_lines(&mut cursor, |line| line.cmp(key)).unwrap(),
+        upper_bound_lines(&mut cursor, |line| line.cmp(key)).unwrap(),
+      )
+    };
+    assert_eq!(bounds(b"banana"), (6, 20));
+    assert_eq!(bounds(b"blueberry"), (20, 20));
+    assert_eq!(bounds(b"zucchini"), (27, 27));
+
+    // Big-endian u32 keys with a byte of payload, and half a record at the
+    // end.
+    let mut records = Vec::new();
+    for key in [3_u32, 8, 8, 15, 40].iter() {
+      records.extend_from_slice(&key.to_be_bytes());
+      records.push(b'.');
+    }
+    records.extend_from_slice(&[0, 0]);
+    let search = |target: u32| {
+      search_records(&mut Cursor::new(&records[..]), 5, |record| {
+        let mut key = [0; 4];
+        key.copy_from_slice(&record[..4]);
+        if u32::from_be_bytes(key) < target {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      }).unwrap()
+    };
+    assert_eq!(search(0), 0);
+    assert_eq!(search(8), 1);
+    assert_eq!(search(9), 3);
+    assert_eq!(search(41), 5);
+    let zero = search_records(&mut Cursor::new(&records[..]), 0, |_| {
+      Direction::Low(())
+    });
+    assert_eq!(zero.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+  }
+
+  #[cfg(feature = "zerocopy")]
+  #[test]
+  fn searches_typed_records() {
+    let mut records = Vec::new();
+    for &(key, value) in [(3_u32, 30_u32), (8, 80), (8, 81), (15, 150)].iter() {
+      records.extend_from_slice(&key.to_ne_bytes());
+      records.extend_from_slice(&value.to_ne_bytes());
+    }
+    records.push(0);
+    let search = |target: u32| {
+      let mut reader = Cursor::new(&records[..]);
+      search_records_as(&mut reader, |record: &[u32; 2]| {
+        if record[0] < target {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      }).unwrap()
+    };
+    assert_eq!(search(0), 0);
+    assert_eq!(search(8), 1);
+    assert_eq!(search(9), 3);
+    assert_eq!(search(16), 4);
+    let zero = search_records_as(&mut Cursor::new(&records[..]), |_: &()| {
+      Direction::Low(())
+    });
+    assert_eq!(zero.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+  }
+}
@@ -0,0 +1,37 @@
+//! Joins between sorted series, such as aligning two streams of
+//! timestamped readings.
+
+use crate::cursor::Cursor;
+
+/// Find, for each element of `a`, the last element of `b` at or before it,
+/// as index pairs `(i, j)` in the order of `a`.
+///
+/// Elements of `a` with nothing at or before them in `b` are left out.
+/// `b` must be sorted, and each search starts from where the last one
+/// ended, so when `a` is sorted too the whole join takes `O(n + m)`
+/// comparisons.
+pub fn as_of_join<T>(a: &[T], b: &[T]) -> Vec<(usize, usize)>
+  where
+    T: Ord {
+  let mut cursor = Cursor::new(b);
+  a.iter().enumerate()
+    .filter_map(|(i, x)| Some((i, cursor.upper_bound(x).checked_sub(1)?)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn joins_each_to_the_latest_before() {
+    let trades = [3, 5, 10, 10, 11, 40];
+    let quotes = [4, 5, 9, 12, 30];
+    assert_eq!(
+      as_of_join(&trades, &quotes),
+      vec![(1, 1), (2, 2), (3, 2), (4, 2), (5, 4)],
+    );
+    assert_eq!(as_of_join(&[6, 1, 31], &quotes), vec![(0, 1), (2, 4)]);
+    assert_eq!(as_of_join(&trades, &[]), vec![]);
+  }
+}
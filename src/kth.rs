@@ -0,0 +1,198 @@
+//! Finding the k-th smallest of a set of values that is too big to list, by
+//! bisecting the values themselves and counting how many fall at or below
+//! each probe.
+//!
+//! `k` counts from zero, as with `slice::select_nth_unstable`, except in
+//! `select_kth`, where it is a count.
+
+use core::ops::{Add, Sub};
+
+use crate::{binary_search, Betweenable, Direction};
+
+/// Count the elements of `matrix` that are at most `value`, where every
+/// row and every column is sorted ascending.
+///
+/// This walks a staircase from the bottom-left corner, so it takes
+/// `O(rows + columns)` comparisons.
+pub fn count_at_most<T, R>(matrix: &[R], value: &T) -> usize
+  where
+    T: Ord,
+    R: AsRef<[T]> {
+  let mut count = 0;
+  let mut column = 0;
+  for row in matrix.iter().rev() {
+    let row = row.as_ref();
+    while row.get(column).is_some_and(|x| x <= value) {
+      column += 1;
+    }
+    count += column;
+  }
+  count
+}
+
+/// Find the `k`th smallest element of `matrix`, whose rows and columns are
+/// each sorted ascending and whose rows are all the same length.
+pub fn kth_in_sorted_matrix<T, R>(matrix: &[R], k: usize) -> Option<T>
+  where
+    T: Betweenable + Ord,
+    R: AsRef<[T]> {
+  let first = *matrix.first()?.as_ref().first()?;
+  let last = *matrix.last()?.as_ref().last()?;
+  smallest_with_rank_above(first, last, k, |x| count_at_most(matrix, &x))
+}
+
+/// Find the `k`th smallest of the sums `a[i] + b[j]` of two sorted slices.
+pub fn kth_pairwise_sum<T>(a: &[T], b: &[T], k: usize) -> Option<T>
+  where
+    T: Betweenable + Ord + Add<Output = T> {
+  let low = *a.first()? + *b.first()?;
+  let high = *a.last()? + *b.last()?;
+  smallest_with_rank_above(low, high, k, |sum| {
+    // Walk `j` down as `i` goes up, counting the `b[j]` with
+    // `a[i] + b[j] <= sum`.
+    let mut j = b.len();
+    let mut count = 0;
+    for &x in a.iter() {
+      while j > 0 && b.get(j - 1).is_some_and(|&y| x + y > sum) {
+        j -= 1;
+      }
+      count += j;
+    }
+    count
+  })
+}
+
+/// Find the `k`th smallest of the distances `|a[i] - b[j]|` between the
+/// elements of two sorted slices.
+pub fn kth_pairwise_distance<T>(a: &[T], b: &[T], k: usize) -> Option<T>
+  where
+    T: Betweenable + Ord + Sub<Output = T> + From<u8> {
+  let distance = |x: T, y: T| if x < y { y - x } else { x - y };
+  let (&a_first, &a_last) = (a.first()?, a.last()?);
+  let (&b_first, &b_last) = (b.first()?, b.last()?);
+  let high = distance(a_first, b_last).max(distance(a_last, b_first));
+  smallest_with_rank_above(T::from(0_u8), high, k, |d| {
+    // For each `a[i]`, the `b[j]` within `d` of it are `b[start..end]`,
+    // and both ends only move forwards as `i` goes up.
+    let mut start = 0;
+    let mut end = 0;
+    let mut count = 0;
+    for &x in a.iter() {
+      while b.get(start).is_some_and(|&y| y < x && x - y > d) {
+        start += 1;
+      }
+      end = end.max(start);
+      while b.get(end).is_some_and(|&y| y <= x || y - x <= d) {
+        end += 1;
+      }
+      count += end - start;
+    }
+    count
+  })
+}
+
+/// Find the smallest `x` in `low..=high` for which `count(x)` is at least
+/// `k`, where `count` is monotone, such as the number of elements of an
+/// implicit multiset at or below `x`, or the cumulative count of a
+/// histogram.
+///
+/// Unlike the functions above, `k` here is a count rather than a rank, so
+/// the `k`th smallest element counting from one is `select_kth(.., k, ..)`.
+/// Returns `None` if `count(high)` is below `k`.
+pub fn select_kth<T, F>(low: T, high: T, k: usize, count: F) -> Option<T>
+  where
+    T: Betweenable,
+    F: FnMut(T) -> usize {
+  match k.checked_sub(1) {
+    None => Some(low),
+    Some(rank) => smallest_with_rank_above(low, high, rank, count),
+  }
+}
+
+/// Find the smallest value in `low..=high` with more than `k` values at or
+/// below it, where `rank(x)` counts those values and `rank(high)` is the
+/// total. Returns `None` if there are too few values.
+fn smallest_with_rank_above<T, F>(
+    low: T,
+    high: T,
+    k: usize,
+    mut rank: F,
+  ) -> Option<T>
+  where
+    T: Betweenable,
+    F: FnMut(T) -> usize {
+  if (rank)(high) <= k {
+    return None;
+  }
+  if (rank)(low) > k {
+    return Some(low);
+  }
+  let (_, (value, ())) =
+    binary_search((low, ()), (high, ()), |x| {
+      if (rank)(x) > k {
+        Direction::High(())
+      } else {
+        Direction::Low(())
+      }
+    });
+  Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn kth_in_sorted_matrix_test() {
+    let matrix = [[1, 5, 9], [10, 11, 13], [12, 13, 15]];
+    let mut all: Vec<i32> = matrix.iter().flatten().copied().collect();
+    all.sort();
+    for (k, &expected) in all.iter().enumerate() {
+      assert_eq!(kth_in_sorted_matrix(&matrix, k), Some(expected));
+    }
+    assert_eq!(kth_in_sorted_matrix(&matrix, 9), None);
+    assert_eq!(count_at_most(&matrix, &12), 6);
+    let empty: [[i32; 0]; 0] = [];
+    assert_eq!(kth_in_sorted_matrix(&empty, 0), None);
+  }
+
+  #[test]
+  fn kth_pairwise_test() {
+    let a = [1_u32, 3, 3, 10];
+    let b = [2_u32, 4, 9];
+    let mut sums = Vec::new();
+    let mut distances = Vec::new();
+    for &x in a.iter() {
+      for &y in b.iter() {
+        sums.push(x + y);
+        distances.push(x.abs_diff(y));
+      }
+    }
+    sums.sort();
+    distances.sort();
+    for k in 0..sums.len() {
+      assert_eq!(kth_pairwise_sum(&a, &b, k), Some(sums[k]));
+      assert_eq!(kth_pairwise_distance(&a, &b, k), Some(distances[k]));
+    }
+    assert_eq!(kth_pairwise_sum(&a, &b, 12), None);
+    assert_eq!(kth_pairwise_distance(&a, &[], 0), None);
+  }
+
+  #[test]
+  fn select_kth_test() {
+    // The multiples of 3 or 5, counted without listing them.
+    let count = |x: u64| (x / 3 + x / 5 - x / 15) as usize;
+    assert_eq!(select_kth(0, 1_000_000, 1, count), Some(3));
+    assert_eq!(select_kth(0, 1_000_000, 7, count), Some(15));
+    assert_eq!(select_kth(0, 1_000_000, 1000, count), Some(2142));
+    assert_eq!(select_kth(0, 100, 1000, count), None);
+    assert_eq!(select_kth(0, 100, 0, count), Some(0));
+
+    // The median of a histogram of latencies, in buckets of 10ms.
+    let buckets = [4_usize, 10, 30, 40, 12, 4];
+    let cumulative = |bucket: usize| buckets.iter().take(bucket + 1).sum();
+    let total: usize = buckets.iter().sum();
+    let median = select_kth(0, buckets.len() - 1, total / 2, cumulative);
+    assert_eq!(median, Some(3));
+  }
+}
@@ -1,56 +1,1171 @@
-use std::ops::{Shr, Add, BitAnd};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(
+  not(test),
+  deny(
+    clippy::panic,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::unreachable,
+  )
+)]
+// The original tests predate these lints.
+#![cfg_attr(
+  test,
+  allow(clippy::legacy_numeric_constants, clippy::unnecessary_cast)
+)]
 
+pub mod allocation;
+#[cfg(feature = "std")]
+pub mod bisect;
+#[cfg(feature = "std")]
+pub mod boundary;
+#[cfg(feature = "std")]
+pub mod broker;
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod cascade;
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod chunked;
+#[cfg(feature = "std")]
+pub mod cidr;
+#[cfg(feature = "std")]
+pub mod cluster;
+pub mod coarse;
+pub mod compose;
+#[cfg(feature = "std")]
+pub mod composite;
+pub mod const_search;
+pub mod constant_time;
+pub mod continuous;
+pub mod cost;
+pub mod cursor;
+pub mod decimal;
+pub mod epoch;
+pub mod exact;
+#[cfg(feature = "std")]
+pub mod eytzinger;
+#[cfg(feature = "std")]
+pub mod fallible;
+pub mod fast;
+pub mod float;
+pub mod gallop;
+#[cfg(feature = "std")]
+pub mod histogram;
+pub mod interpolation;
+pub mod intervals;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod join;
+pub mod kth;
+pub mod limit;
+pub mod mapped;
+#[cfg(feature = "memory")]
+pub mod memory;
+pub mod merge;
+pub mod narrowing;
+#[cfg(feature = "std")]
+pub mod noisy;
+#[cfg(feature = "std")]
+pub mod paged;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod peak;
+pub mod piecewise;
+#[cfg(feature = "std")]
+pub mod plan;
+#[cfg(feature = "std")]
+pub mod poset;
+#[cfg(feature = "std")]
+pub mod prefix_sum;
+pub mod quantize;
+pub mod records;
+#[cfg(feature = "std")]
+pub mod reduce;
+pub mod refine;
+pub mod retry;
+#[cfg(feature = "std")]
+pub mod runner;
+#[cfg(feature = "std")]
+pub mod schedule;
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod sentinel;
+#[cfg(feature = "std")]
+pub mod shard;
+#[cfg(feature = "std")]
+pub mod skip;
+#[cfg(feature = "std")]
+pub mod slice;
+#[cfg(feature = "std")]
+pub mod sorted;
+#[cfg(feature = "std")]
+pub mod split;
+pub mod state;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod sweep;
+pub mod symbols;
+pub mod time_id;
+pub mod tolerance;
+#[cfg(feature = "std")]
+pub mod trace;
+#[cfg(feature = "std")]
+pub mod track;
+#[cfg(feature = "std")]
+pub mod uniform;
+pub mod unimodal;
+#[cfg(feature = "verify")]
+mod verify;
+
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::ops::{Bound, RangeBounds};
+use core::num::{
+  NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize,
+  NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
 pub trait Betweenable where Self: Copy {
   fn between(x: Self, y: Self) -> Option<Self>;
 }
 
-impl<X> Betweenable for X
-    where
-      X: Copy,
-      X: Shr<i8, Output=X>,
-      X: Add<X, Output=X>,
-      X: BitAnd<X, Output=X>,
-      X: From<u8>,
-      X: PartialOrd {
+macro_rules! impl_betweenable {
+  ($($t:ty),*) => {
+    $(
+      impl Betweenable for $t {
+        fn between(low: Self, high: Self) -> Option<Self> {
+          // The floor of the mean, without overflow: `>>` rounds towards
+          // negative infinity for signed types too, and the last term adds
+          // back the half lost from each when both are odd.
+          let mid = (low >> 1) + (high >> 1) + (low & high & 1);
+          if low < mid && mid < high {
+            Some(mid)
+          } else {
+            None
+          }
+        }
+      }
+    )*
+  };
+}
+
+impl_betweenable!(u8, u16, u32, u64, u128, usize);
+impl_betweenable!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! impl_betweenable_nonzero {
+  ($($t:ty),*) => {
+    $(
+      impl Betweenable for $t {
+        fn between(low: Self, high: Self) -> Option<Self> {
+          // Both bounds are at least one, so the midpoint is too.
+          <$t>::new(Betweenable::between(low.get(), high.get())?)
+        }
+      }
+    )*
+  };
+}
+
+impl_betweenable_nonzero!(
+  NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize
+);
+
+// Signed non-zero integers are bisected as if zero weren't there, so a
+// bracket around zero still splits evenly and never yields it.
+macro_rules! impl_betweenable_nonzero_signed {
+  ($($t:ty),*) => {
+    $(
+      impl Betweenable for $t {
+        fn between(low: Self, high: Self) -> Option<Self> {
+          let close = |x: $t| if x.get() < 0 { x.get() } else { x.get() - 1 };
+          let mid = Betweenable::between(close(low), close(high))?;
+          <$t>::new(if mid < 0 { mid } else { mid + 1 })
+        }
+      }
+    )*
+  };
+}
+
+impl_betweenable_nonzero_signed!(
+  NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize
+);
+
+/// Characters are bisected by scalar value, as if the surrogate gap
+/// `0xD800..=0xDFFF` weren't there, so midpoints are always valid `char`s.
+impl Betweenable for char {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    const GAP_START: u32 = 0xD800;
+    const GAP_LEN: u32 = 0x800;
+    let close = |c: char| {
+      let c = u32::from(c);
+      if c < GAP_START { c } else { c - GAP_LEN }
+    };
+    let mid = u32::between(close(low), close(high))?;
+    let mid = if mid < GAP_START { mid } else { mid + GAP_LEN };
+    char::from_u32(mid)
+  }
+}
+
+fn duration_from_nanos(nanos: u128) -> Option<Duration> {
+  const NANOS_PER_SEC: u128 = 1_000_000_000;
+  let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+  let nanos = u32::try_from(nanos % NANOS_PER_SEC).ok()?;
+  Some(Duration::new(secs, nanos))
+}
+
+impl Betweenable for Duration {
   fn between(low: Self, high: Self) -> Option<Self> {
-    let one = X::from(1);
-    if high <= low + one {
-      None
+    duration_from_nanos(u128::between(low.as_nanos(), high.as_nanos())?)
+  }
+}
+
+impl Betweenable for Ipv4Addr {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    u32::between(low.into(), high.into()).map(Ipv4Addr::from)
+  }
+}
+
+impl Betweenable for Ipv6Addr {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    u128::between(low.into(), high.into()).map(Ipv6Addr::from)
+  }
+}
+
+#[cfg(feature = "std")]
+impl Betweenable for SystemTime {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    // Times are measured in nanoseconds from the epoch, negative before it.
+    let nanos = |t: SystemTime| {
+      match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => i128::try_from(d.as_nanos()).ok(),
+        Err(e) => i128::try_from(e.duration().as_nanos()).ok().map(|n| -n),
+      }
+    };
+    let mid = i128::between(nanos(low)?, nanos(high)?)?;
+    let offset = duration_from_nanos(mid.unsigned_abs())?;
+    if mid < 0 {
+      UNIX_EPOCH.checked_sub(offset)
     } else {
-      let mid = (low >> 1) + (high >> 1) + (low & high & one);
-      Some(mid)
+      UNIX_EPOCH.checked_add(offset)
     }
   }
 }
 
+/// Implement `Betweenable` for tuple structs that wrap a `Betweenable`
+/// value, such as `struct BlockHeight(u64)`, by bisecting the value inside.
+///
+/// `betweenable_newtype!(BlockHeight, PageId);` implements it for each type
+/// listed, which must be in scope and have a single field.
+#[macro_export]
+macro_rules! betweenable_newtype {
+  ($($t:path),* $(,)?) => {
+    $(
+      impl $crate::Betweenable for $t {
+        fn between(low: Self, high: Self) -> ::core::option::Option<Self> {
+          $crate::Betweenable::between(low.0, high.0).map($t)
+        }
+      }
+    )*
+  };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction<A, B> {
   Low(A),
   High(B),
 }
 
+/// The largest low and the smallest high found by a search, each with its
+/// witness.
+pub type Bracket<X, A, B> = ((X, A), (X, B));
+
+/// A `Bracket` with its parts named, for call sites where the tuple is easy
+/// to misread.
+///
+/// Make one with `new` or from a `Bracket`. It can't be built field by field
+/// outside this crate, as its `provenance` is only there with `std`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SearchResult<X, A, B> {
+  pub largest_low: X,
+  pub low_witness: A,
+  pub smallest_high: X,
+  pub high_witness: B,
+  /// How the result was found, if that was recorded.
+  #[cfg(feature = "std")]
+  pub provenance: Option<Provenance>,
+}
+
+/// How a search result was found, for auditing a reported threshold.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+  /// The version of this crate that ran the search.
+  pub version: String,
+  /// The search function used, such as `"binary_search"`.
+  pub strategy: String,
+  /// The seed of a randomized search.
+  pub seed: Option<u64>,
+  pub probes: usize,
+  /// A hash of every probe and its direction, in order, as computed by
+  /// `stats::Stats::trace_hash`.
+  pub trace_hash: u64,
+}
+
+#[cfg(feature = "std")]
+impl Provenance {
+  /// Provenance for a search run by this version of the crate.
+  pub fn new(
+      strategy: &str,
+      seed: Option<u64>,
+      probes: usize,
+      trace_hash: u64,
+    ) -> Self {
+    Provenance {
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      strategy: strategy.to_string(),
+      seed,
+      probes,
+      trace_hash,
+    }
+  }
+}
+
+impl<X, A, B> SearchResult<X, A, B> {
+  /// A result without provenance.
+  pub fn new(
+      largest_low: X,
+      low_witness: A,
+      smallest_high: X,
+      high_witness: B,
+    ) -> Self {
+    SearchResult {
+      largest_low,
+      low_witness,
+      smallest_high,
+      high_witness,
+      #[cfg(feature = "std")]
+      provenance: None,
+    }
+  }
+}
+
+impl<X, A, B> SearchResult<X, A, B>
+  where
+    X: Copy {
+  /// The largest low value and its witness.
+  pub fn low(&self) -> (X, &A) {
+    (self.largest_low, &self.low_witness)
+  }
+
+  /// The smallest high value and its witness.
+  pub fn high(&self) -> (X, &B) {
+    (self.smallest_high, &self.high_witness)
+  }
+
+  /// The smallest high value, which is where the transition is.
+  pub fn boundary(&self) -> X {
+    self.smallest_high
+  }
+
+  /// Attach a record of how the result was found.
+  #[cfg(feature = "std")]
+  pub fn with_provenance(self, provenance: Provenance) -> Self {
+    SearchResult { provenance: Some(provenance), ..self }
+  }
+
+  pub fn into_tuple(self) -> Bracket<X, A, B> {
+    (
+      (self.largest_low, self.low_witness),
+      (self.smallest_high, self.high_witness),
+    )
+  }
+}
+
+impl<X, A, B> From<Bracket<X, A, B>> for SearchResult<X, A, B> {
+  fn from(bracket: Bracket<X, A, B>) -> Self {
+    let ((largest_low, low_witness), (smallest_high, high_witness)) = bracket;
+    SearchResult::new(largest_low, low_witness, smallest_high, high_witness)
+  }
+}
+
+impl<X, A, B> fmt::Display for SearchResult<X, A, B>
+  where
+    X: fmt::Display {
+  /// Formats as, for example, `22 ≤ x < 23`, like `stats::Stats`.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} ≤ x < {}", self.largest_low, self.smallest_high)
+  }
+}
+
+/// Something to search over that isn't a range of values, such as the
+/// entries of a B-tree or an external index.
+///
+/// The positions of a search space are ordered, and a search keeps a
+/// bracket of them, narrowing it with each probe.
+pub trait SearchSpace {
+  type Position: Clone;
+
+  /// A position strictly between `low` and `high` to probe next, or `None`
+  /// if there isn't one, which ends the search.
+  fn candidate(
+    &mut self,
+    low: &Self::Position,
+    high: &Self::Position,
+  ) -> Option<Self::Position>;
+}
+
+/// The values of a `Betweenable` type, as a search space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Values<X>(PhantomData<X>);
+
+impl<X> Values<X> {
+  pub fn new() -> Self {
+    Values(PhantomData)
+  }
+}
+
+impl<X> Default for Values<X> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<X> SearchSpace for Values<X>
+  where
+    X: Betweenable {
+  type Position = X;
+
+  fn candidate(&mut self, &low: &X, &high: &X) -> Option<X> {
+    X::between(low, high)
+  }
+}
+
 pub fn binary_search<X, A, B, F>(
     low: (X, A),
     high: (X, B),
+    f: F,
+  ) -> ((X, A), (X, B))
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<A, B> {
+  binary_search_in(&mut Values::new(), low, high, f)
+}
+
+/// Like `binary_search`, but over the positions of `space`.
+pub fn binary_search_in<S, A, B, F>(
+    space: &mut S,
+    mut low: (S::Position, A),
+    mut high: (S::Position, B),
     mut f: F,
+  ) -> Bracket<S::Position, A, B>
+  where
+    S: SearchSpace + ?Sized,
+    F: FnMut(S::Position) -> Direction<A, B> {
+  while let Some(x) = space.candidate(&low.0, &high.0) {
+    match (f)(x.clone()) {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  (low, high)
+}
+
+/// How a search picks the next point to probe, given the bracket so far
+/// and its witnesses.
+///
+/// Closures taking the two ends of the bracket are strategies too.
+pub trait Strategy<X, A, B> {
+  /// A point strictly between `low` and `high` to probe next, or `None` to
+  /// end the search.
+  fn choose(&mut self, low: &(X, A), high: &(X, B)) -> Option<X>;
+}
+
+impl<X, A, B, F> Strategy<X, A, B> for F
+  where
+    F: FnMut(&(X, A), &(X, B)) -> Option<X> {
+  fn choose(&mut self, low: &(X, A), high: &(X, B)) -> Option<X> {
+    (self)(low, high)
+  }
+}
+
+/// Probing at `Betweenable::between`, as `binary_search` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Midpoint;
+
+impl<X, A, B> Strategy<X, A, B> for Midpoint
+  where
+    X: Betweenable {
+  fn choose(&mut self, low: &(X, A), high: &(X, B)) -> Option<X> {
+    X::between(low.0, high.0)
+  }
+}
+
+/// Probing closer to one end than the midpoint, by the given number of
+/// further halvings, for when the transition is expected to be near it.
+/// `Low(1)` probes a quarter of the way from the low end, for example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biased {
+  Low(u32),
+  High(u32),
+}
+
+impl<X, A, B> Strategy<X, A, B> for Biased
+  where
+    X: Betweenable {
+  fn choose(&mut self, low: &(X, A), high: &(X, B)) -> Option<X> {
+    let mut x = X::between(low.0, high.0)?;
+    let (halvings, toward_low) =
+      match *self {
+        Biased::Low(n) => (n, true),
+        Biased::High(n) => (n, false),
+      };
+    for _ in 0..halvings {
+      let closer =
+        if toward_low {
+          X::between(low.0, x)
+        } else {
+          X::between(x, high.0)
+        };
+      match closer {
+        Some(y) => x = y,
+        None => break,
+      }
+    }
+    Some(x)
+  }
+}
+
+/// Probing at a random point near the midpoint, for checking that a
+/// predicate doesn't depend on the exact sequence of probes, or for keeping
+/// a benchmark's probes from always hitting the same cache lines.
+///
+/// Each probe falls in the middle of the bracket, leaving out a part
+/// `1 / 2^(spread + 1)` of it at either end, so the search still takes
+/// logarithmically many probes. A `spread` of zero probes the midpoint. The
+/// points are drawn from a generator seeded with `seed`, so a run can be
+/// repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Jittered {
+  spread: u32,
+  state: u64,
+}
+
+impl Jittered {
+  pub fn new(seed: u64, spread: u32) -> Self {
+    Jittered { spread, state: seed }
+  }
+
+  /// The next output of a SplitMix64 generator.
+  fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+}
+
+impl<X, A, B> Strategy<X, A, B> for Jittered
+  where
+    X: Betweenable {
+  fn choose(&mut self, low: &(X, A), high: &(X, B)) -> Option<X> {
+    let middle = X::between(low.0, high.0)?;
+    let mut a = middle;
+    let mut b = middle;
+    for _ in 0..self.spread {
+      a = X::between(low.0, a).unwrap_or(a);
+      b = X::between(b, high.0).unwrap_or(b);
+    }
+    // A random descent through the halvings of the band, which gives each
+    // of its dyadic parts an equal chance down to a depth of 64.
+    let mut bits = self.next_u64();
+    let mut x = middle;
+    for _ in 0..64 {
+      x =
+        match X::between(a, b) {
+          Some(m) => m,
+          None => break,
+        };
+      if bits & 1 == 0 {
+        b = x;
+      } else {
+        a = x;
+      }
+      bits >>= 1;
+    }
+    Some(x)
+  }
+}
+
+/// Like `binary_search`, but probing wherever `strategy` chooses.
+pub fn binary_search_with_strategy<X, A, B, S, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    strategy: &mut S,
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Copy,
+    S: Strategy<X, A, B> + ?Sized,
+    F: FnMut(X) -> Direction<A, B> {
+  while let Some(x) = strategy.choose(&low, &high) {
+    match (f)(x) {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  (low, high)
+}
+
+/// Like `binary_search`, but takes the predicate as a trait object.
+///
+/// `binary_search` is instantiated for every distinct closure type it's
+/// called with. This is instantiated once per combination of `X`, `A` and
+/// `B`, so programs that run many different searches over the same types
+/// can use it to cut code size, at the cost of an indirect call per probe.
+pub fn binary_search_dyn<X, A, B>(
+    low: (X, A),
+    high: (X, B),
+    f: &mut dyn FnMut(X) -> Direction<A, B>,
   ) -> ((X, A), (X, B))
+  where
+    X: Betweenable {
+  binary_search(low, high, f)
+}
+
+/// Search the values in `range`, such as `1..=100`, when no witnesses are
+/// needed.
+///
+/// Unlike `binary_search`, nothing is assumed about the ends of the range,
+/// and any value in it may be probed. Returns the largest value found to be
+/// low and the smallest found to be high, either of which is `None` if the
+/// whole range is on the other side of the transition.
+pub fn binary_search_range<X, R, F>(
+    range: R,
+    mut f: F,
+  ) -> (Option<X>, Option<X>)
+  where
+    X: gallop::Steppable,
+    R: RangeBounds<X>,
+    F: FnMut(X) -> Direction<(), ()> {
+  let start =
+    match range.start_bound() {
+      Bound::Included(&x) => Some(x),
+      Bound::Excluded(&x) => x.forward(1),
+      Bound::Unbounded => Some(X::MIN),
+    };
+  let end =
+    match range.end_bound() {
+      Bound::Included(&x) => Some(x),
+      Bound::Excluded(&x) => x.backward(1),
+      Bound::Unbounded => Some(X::MAX),
+    };
+  let mut low = None;
+  let mut high = None;
+  let (mut start, mut end) =
+    match (start, end) {
+      (Some(start), Some(end)) => (start, end),
+      _ => return (low, high),
+    };
+  while start <= end {
+    let x = X::between(start, end).unwrap_or(start);
+    match (f)(x) {
+      Direction::Low(()) => {
+        low = Some(x);
+        match x.forward(1) {
+          Some(next) => start = next,
+          None => break,
+        }
+      },
+      Direction::High(()) => {
+        high = Some(x);
+        match x.backward(1) {
+          Some(next) => end = next,
+          None => break,
+        }
+      },
+    }
+  }
+  (low, high)
+}
+
+/// Like `binary_search`, but for predicates that go from high to low as `x`
+/// increases, such as remaining capacity as load grows.
+///
+/// `low` is high and `high` is low. Returns the largest value found to be
+/// high and the smallest found to be low.
+pub fn binary_search_rev<X, A, B, F>(
+    low: (X, B),
+    high: (X, A),
+    mut f: F,
+  ) -> ((X, B), (X, A))
   where
     X: Betweenable,
     F: FnMut(X) -> Direction<A, B> {
-  match X::between(low.0, high.0) {
-    None => {
-      (low, high)
-    },
-    Some(x) => {
+  binary_search(low, high, |x| {
+    match (f)(x) {
+      Direction::Low(a) => Direction::High(a),
+      Direction::High(b) => Direction::Low(b),
+    }
+  })
+}
+
+/// Like `binary_search`, but comparing each probe with a target the way
+/// `slice::binary_search_by` does: `Less` if it's below the target,
+/// `Greater` if it's above, and `Equal` if it matches, which ends the
+/// search.
+///
+/// `f` also gives a witness for each probe, which can be `()`. Returns `Ok`
+/// with the match and its witness, where `slice::binary_search_by` would
+/// return its index, or otherwise `Err` with the bracket around where the
+/// target would be. As with slices, if several points match, any of them
+/// may be returned.
+pub fn binary_search_by_ord<X, W, F>(
+    low: (X, W),
+    high: (X, W),
+    mut f: F,
+  ) -> Result<(X, W), Bracket<X, W, W>>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> (Ordering, W) {
+  let found =
+    exact::binary_search_exact(low, high, |x| {
       match (f)(x) {
-        Direction::Low(low) => {
-          binary_search((x, low), high, f)
-        },
-        Direction::High(high) => {
-          binary_search(low, (x, high), f)
-        },
+        (Ordering::Less, w) => exact::Comparison::Low(w),
+        (Ordering::Greater, w) => exact::Comparison::High(w),
+        (Ordering::Equal, w) => exact::Comparison::Exact(w),
       }
+    });
+  match found {
+    exact::Found::Exact(x, w) => Ok((x, w)),
+    exact::Found::Between(low, high) => Err((low, high)),
+  }
+}
+
+/// Like `binary_search`, but each probe is also given `context`, so state
+/// such as connections or logs can be kept across probes by the caller.
+pub fn binary_search_with_context<C, X, A, B, F>(
+    context: &mut C,
+    low: (X, A),
+    high: (X, B),
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable,
+    F: FnMut(&mut C, X) -> Direction<A, B> {
+  binary_search(low, high, |x| (f)(context, x))
+}
+
+/// Like `binary_search`, but the predicate is asynchronous, such as a
+/// request to a remote service. Each probe is awaited before the next is
+/// made.
+#[cfg(feature = "async")]
+pub async fn binary_search_async<X, A, B, F, Fut>(
+    mut low: (X, A),
+    mut high: (X, B),
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Fut,
+    Fut: std::future::Future<Output = Direction<A, B>> {
+  while let Some(x) = X::between(low.0, high.0) {
+    match (f)(x).await {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  (low, high)
+}
+
+/// Like `binary_search_async`, but each probe is also given `context`,
+/// which its future hands back when it finishes. Taking the context by
+/// value lets the future hold on to it while it's awaited.
+#[cfg(feature = "async")]
+pub async fn binary_search_async_with_context<C, X, A, B, F, Fut>(
+    mut context: C,
+    mut low: (X, A),
+    mut high: (X, B),
+    mut f: F,
+  ) -> (C, Bracket<X, A, B>)
+  where
+    X: Betweenable,
+    F: FnMut(C, X) -> Fut,
+    Fut: std::future::Future<Output = (C, Direction<A, B>)> {
+  while let Some(x) = X::between(low.0, high.0) {
+    let (returned, direction) = (f)(context, x).await;
+    context = returned;
+    match direction {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  (context, (low, high))
+}
+
+/// Like `binary_search_async`, but with up to `parallelism` probes in
+/// flight at once, for predicates that are slow to answer but cheap to ask
+/// many of, such as remote calls with high latency.
+///
+/// Probes are made by halving each gap between the ends of the bracket and
+/// the probes in flight in turn, and the bracket is narrowed as each
+/// finishes, in whatever order they do. Probes left outside the bracket are
+/// dropped, cancelling them, and new ones take their place. The futures
+/// are polled by this one, so they run concurrently but not in parallel
+/// unless they hand work off to something else.
+#[cfg(feature = "async")]
+pub async fn binary_search_concurrent<X, A, B, F, Fut>(
+    mut low: (X, A),
+    mut high: (X, B),
+    parallelism: usize,
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable + PartialOrd,
+    F: FnMut(X) -> Fut,
+    Fut: std::future::Future<Output = Direction<A, B>> {
+  use std::pin::Pin;
+  use std::task::Poll;
+
+  let mut in_flight: Vec<(X, Pin<Box<Fut>>)> = Vec::new();
+  loop {
+    in_flight.retain(|(x, _)| low.0 < *x && *x < high.0);
+    in_flight.sort_by(|(x, _), (y, _)| {
+      x.partial_cmp(y).unwrap_or(Ordering::Equal)
+    });
+    let points: Vec<X> = in_flight.iter().map(|&(x, _)| x).collect();
+    let count = parallelism.max(1).saturating_sub(points.len());
+    for x in subdivide(low.0, &points, high.0, count) {
+      in_flight.push((x, Box::pin((f)(x))));
+    }
+    if in_flight.is_empty() {
+      return (low, high);
+    }
+    let (i, direction) =
+      std::future::poll_fn(|context| {
+        for (i, (_, probe)) in in_flight.iter_mut().enumerate() {
+          if let Poll::Ready(direction) = probe.as_mut().poll(context) {
+            return Poll::Ready((i, direction));
+          }
+        }
+        Poll::Pending
+      }).await;
+    let (x, _) = in_flight.swap_remove(i);
+    match direction {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+}
+
+/// A search stopped by an error from the predicate, with the bracket it had
+/// narrowed to when the error happened.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Aborted<X, A, B, E> {
+  pub error: E,
+  pub low: (X, A),
+  pub high: (X, B),
+}
+
+impl<X, A, B, E> fmt::Display for Aborted<X, A, B, E>
+  where
+    X: fmt::Display,
+    E: fmt::Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "search aborted between {} and {}: {}",
+      self.low.0,
+      self.high.0,
+      self.error,
+    )
+  }
+}
+
+impl<X, A, B, E> core::error::Error for Aborted<X, A, B, E>
+  where
+    X: fmt::Debug + fmt::Display,
+    A: fmt::Debug,
+    B: fmt::Debug,
+    E: core::error::Error {}
+
+/// The outcome of `try_binary_search`.
+pub type TryBracket<X, A, B, E> =
+  Result<Bracket<X, A, B>, Aborted<X, A, B, E>>;
+
+/// Like `binary_search`, but the predicate can fail, which stops the search.
+pub fn try_binary_search<X, A, B, E, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    mut f: F,
+  ) -> TryBracket<X, A, B, E>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Result<Direction<A, B>, E> {
+  while let Some(x) = X::between(low.0, high.0) {
+    match (f)(x) {
+      Ok(Direction::Low(a)) => {
+        low = (x, a);
+      },
+      Ok(Direction::High(b)) => {
+        high = (x, b);
+      },
+      Err(error) => {
+        return Err(Aborted { error, low, high });
+      },
     }
   }
+  Ok((low, high))
+}
+
+/// Evidence that a predicate isn't monotone: it was low at `x_low` and high
+/// at `x_high`, though `x_high` isn't above `x_low`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NonMonotone<X> {
+  pub x_low: X,
+  pub x_high: X,
+}
+
+impl<X> fmt::Display for NonMonotone<X>
+  where
+    X: fmt::Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "predicate is not monotone: low at {} but high at {}",
+      self.x_low,
+      self.x_high,
+    )
+  }
+}
+
+impl<X> core::error::Error for NonMonotone<X>
+  where
+    X: fmt::Debug + fmt::Display {}
+
+/// Like `binary_search`, but checking the predicate as it goes, for when a
+/// predicate that isn't monotone would otherwise give a wrong answer
+/// without any sign of it.
+///
+/// Once the search is done, the ends of the bracket are tested again, and
+/// so is a point in each gap between the probes, which should agree with
+/// the probes either side of it. This takes about twice as many probes as
+/// `binary_search`, and can't catch every violation, but does catch
+/// predicates that change their answers and those that go back and forth
+/// over wide stretches.
+#[cfg(feature = "std")]
+pub fn binary_search_checked<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    mut f: F,
+  ) -> Result<Bracket<X, A, B>, NonMonotone<X>>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<A, B> {
+  let mut lows = vec![low.0];
+  let mut highs = vec![high.0];
+  let bracket =
+    binary_search(low, high, |x| {
+      let direction = (f)(x);
+      match direction {
+        Direction::Low(_) => lows.push(x),
+        Direction::High(_) => highs.push(x),
+      }
+      direction
+    });
+  let mut is_low = |x| matches!((f)(x), Direction::Low(_));
+  // The first of each is a bound, which may not be safe to probe.
+  if let Some(&x) = lows.iter().skip(1).last() {
+    if !is_low(x) {
+      return Err(NonMonotone { x_low: x, x_high: x });
+    }
+  }
+  if let Some(&x) = highs.iter().skip(1).last() {
+    if is_low(x) {
+      return Err(NonMonotone { x_low: x, x_high: x });
+    }
+  }
+  for (&a, &b) in lows.iter().zip(lows.iter().skip(1)) {
+    match X::between(a, b) {
+      Some(x) if !is_low(x) => return Err(NonMonotone { x_low: b, x_high: x }),
+      _ => {},
+    }
+  }
+  for (&a, &b) in highs.iter().zip(highs.iter().skip(1)) {
+    match X::between(b, a) {
+      Some(x) if is_low(x) => return Err(NonMonotone { x_low: x, x_high: b }),
+      _ => {},
+    }
+  }
+  Ok(bracket)
+}
+
+/// How a search with a budget ended, with the narrowest bracket it found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budgeted<X, A, B> {
+  /// The bracket narrowed to adjacent values.
+  Converged(Bracket<X, A, B>),
+  /// The probes ran out first.
+  Exhausted(Bracket<X, A, B>),
+  /// The search was cancelled first.
+  Cancelled(Bracket<X, A, B>),
+}
+
+impl<X, A, B> Budgeted<X, A, B> {
+  /// The bracket, whether or not the search converged.
+  pub fn bracket(self) -> Bracket<X, A, B> {
+    match self {
+      Budgeted::Converged(bracket) => bracket,
+      Budgeted::Exhausted(bracket) => bracket,
+      Budgeted::Cancelled(bracket) => bracket,
+    }
+  }
+
+  /// The bracket, if the search converged.
+  pub fn converged(self) -> Option<Bracket<X, A, B>> {
+    match self {
+      Budgeted::Converged(bracket) => Some(bracket),
+      Budgeted::Exhausted(_) | Budgeted::Cancelled(_) => None,
+    }
+  }
+}
+
+/// Like `binary_search`, but stopping early after `max_probes` probes, if
+/// given, or when `should_cancel` returns true, which it's asked before
+/// each probe. For predicates that are expensive enough that the work has
+/// to be capped.
+pub fn binary_search_with_budget<X, A, B, C, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    max_probes: Option<usize>,
+    mut should_cancel: C,
+    mut f: F,
+  ) -> Budgeted<X, A, B>
+  where
+    X: Betweenable,
+    C: FnMut() -> bool,
+    F: FnMut(X) -> Direction<A, B> {
+  let mut probes = 0;
+  while let Some(x) = X::between(low.0, high.0) {
+    if max_probes.is_some_and(|max| probes >= max) {
+      return Budgeted::Exhausted((low, high));
+    }
+    if (should_cancel)() {
+      return Budgeted::Cancelled((low, high));
+    }
+    probes += 1;
+    match (f)(x) {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  Budgeted::Converged((low, high))
+}
+
+/// Up to `count` new points between `low` and `high`, in order, found by
+/// halving each gap between `low`, the sorted `points` and `high` in turn.
+#[cfg(any(feature = "async", feature = "rayon"))]
+pub(crate) fn subdivide<X>(
+    low: X,
+    points: &[X],
+    high: X,
+    count: usize,
+  ) -> Vec<X>
+  where
+    X: Betweenable {
+  // Each point, and whether it's new.
+  let mut all: Vec<(X, bool)> =
+    std::iter::once(low)
+      .chain(points.iter().copied())
+      .chain(std::iter::once(high))
+      .map(|x| (x, false))
+      .collect();
+  let mut added = 0;
+  while added < count {
+    let before = added;
+    let mut next = Vec::with_capacity(all.len() * 2);
+    for (&a, &b) in all.iter().zip(all.iter().skip(1)) {
+      next.push(a);
+      if added < count {
+        if let Some(x) = X::between(a.0, b.0) {
+          next.push((x, true));
+          added += 1;
+        }
+      }
+    }
+    next.push((high, false));
+    all = next;
+    if added == before {
+      break;
+    }
+  }
+  all.into_iter().filter(|&(_, new)| new).map(|(x, _)| x).collect()
+}
+
+/// Find the number of leading indices in `0..len` for which `is_low` holds,
+/// where `is_low` is monotone: true up to some index and false after it.
+pub(crate) fn partition_point<F>(len: usize, mut is_low: F) -> usize
+  where
+    F: FnMut(usize) -> bool {
+  let last =
+    match len.checked_sub(1) {
+      None => return 0,
+      Some(last) => last,
+    };
+  if !(is_low)(0) {
+    return 0;
+  }
+  if (is_low)(last) {
+    return len;
+  }
+  let (_, (index, ())) =
+    binary_search((0, ()), (last, ()), |i| {
+      if (is_low)(i) {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    });
+  index
 }
 
 #[cfg(test)]
@@ -85,4 +1200,606 @@ mod tests {
       });
     assert_eq!(result, ((22, ()), (23, ())))
   }
+
+  /// The original, recursive form of `binary_search`.
+  fn recursive<X, A, B, F>(
+      low: (X, A),
+      high: (X, B),
+      mut f: F,
+    ) -> ((X, A), (X, B))
+    where
+      X: Betweenable,
+      F: FnMut(X) -> Direction<A, B> {
+    match X::between(low.0, high.0) {
+      None => (low, high),
+      Some(x) => {
+        match (f)(x) {
+          Direction::Low(low) => recursive((x, low), high, f),
+          Direction::High(high) => recursive(low, (x, high), f),
+        }
+      },
+    }
+  }
+
+  #[test]
+  fn iterative_matches_recursive() {
+    for threshold in -3..=40 {
+      let mut iterative_probes = Vec::new();
+      let mut recursive_probes = Vec::new();
+      let f = |probes: &mut Vec<i32>, x: i32| {
+        probes.push(x);
+        if x < threshold {
+          Direction::Low(x * 2)
+        } else {
+          Direction::High(x * 3)
+        }
+      };
+      assert_eq!(
+        binary_search((0, 0), (37, 0), |x| f(&mut iterative_probes, x)),
+        recursive((0, 0), (37, 0), |x| f(&mut recursive_probes, x)),
+      );
+      assert_eq!(iterative_probes, recursive_probes);
+    }
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn binary_search_async_test() {
+    use std::future::Future;
+    use std::task::{Context, Poll, Waker};
+
+    let mut probes = 0;
+    let search =
+      binary_search_async((0_u32, ()), (100, ()), |x| {
+        probes += 1;
+        std::future::ready(if x < 23 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        })
+      });
+    let mut search = Box::pin(search);
+    let mut context = Context::from_waker(Waker::noop());
+    match search.as_mut().poll(&mut context) {
+      Poll::Ready(result) => assert_eq!(result, ((22, ()), (23, ()))),
+      Poll::Pending => panic!("ready futures left the search pending"),
+    }
+    drop(search);
+    assert_eq!(probes, 7);
+  }
+
+  #[test]
+  fn search_result_names_the_bracket() {
+    let result: SearchResult<u32, &str, bool> =
+      binary_search((0, "start"), (100, false), |x| {
+        if x < 23 { Direction::Low("low") } else { Direction::High(true) }
+      }).into();
+    assert_eq!(result.low(), (22, &"low"));
+    assert_eq!(result.high(), (23, &true));
+    assert_eq!(result.boundary(), 23);
+    assert_eq!(result.to_string(), "22 ≤ x < 23");
+    assert_eq!(result, SearchResult::new(22, "low", 23, true));
+    assert_eq!(result.into_tuple(), ((22, "low"), (23, true)));
+  }
+
+  #[test]
+  fn binary_search_range_test() {
+    let threshold = |t: u8| {
+      move |x| if x < t { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let bracket = binary_search_range(1..=100, threshold(23));
+    assert_eq!(bracket, (Some(22), Some(23)));
+    assert_eq!(binary_search_range(1..=100, threshold(0)), (None, Some(1)));
+    assert_eq!(binary_search_range(1..100, threshold(200)), (Some(99), None));
+    assert_eq!(binary_search_range(.., threshold(0)), (None, Some(0)));
+    assert_eq!(binary_search_range(.., threshold(255)), (Some(254), Some(255)));
+    assert_eq!(binary_search_range(5..5, threshold(3)), (None, None));
+    let mut probes = Vec::new();
+    binary_search_range(10..=20, |x| {
+      probes.push(x);
+      threshold(15)(x)
+    });
+    assert!(probes.iter().all(|x| (10..=20).contains(x)));
+  }
+
+  #[test]
+  fn binary_search_rev_test() {
+    // The capacity left at each load, until it runs out.
+    let remaining = |load: u32| 1000_u32.checked_sub(load * 3);
+    let result =
+      binary_search_rev((0, 1000), (1000, ()), |load| {
+        match remaining(load) {
+          Some(capacity) if capacity >= 100 => Direction::High(capacity),
+          _ => Direction::Low(()),
+        }
+      });
+    assert_eq!(result, ((300, 100), (301, ())));
+  }
+
+  #[test]
+  fn context_is_threaded_through_probes() {
+    let threshold = |x: u32| {
+      if x < 23 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let mut log = Vec::new();
+    let result =
+      binary_search_with_context(&mut log, (0, ()), (100, ()), |log, x| {
+        log.push(x);
+        threshold(x)
+      });
+    assert_eq!(result, ((22, ()), (23, ())));
+    assert_eq!(log, vec![50, 25, 12, 18, 21, 23, 22]);
+
+    #[cfg(feature = "async")]
+    {
+      use std::future::Future;
+      use std::task::{Context, Poll, Waker};
+
+      let search =
+        binary_search_async_with_context(
+          Vec::new(),
+          (0, ()),
+          (100, ()),
+          |mut log: Vec<u32>, x| {
+            log.push(x);
+            std::future::ready((log, threshold(x)))
+          },
+        );
+      let mut search = Box::pin(search);
+      let mut context = Context::from_waker(Waker::noop());
+      match search.as_mut().poll(&mut context) {
+        Poll::Ready((async_log, result)) => {
+          assert_eq!(result, ((22, ()), (23, ())));
+          assert_eq!(async_log, log);
+        },
+        Poll::Pending => panic!("ready futures left the search pending"),
+      }
+    }
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn binary_search_concurrent_test() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    assert_eq!(subdivide(0_u32, &[], 8, 7), [1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(subdivide(0_u32, &[], 8, 3), [2, 4, 6]);
+    assert_eq!(subdivide(0_u32, &[4], 8, 3), [1, 2, 6]);
+    assert_eq!(subdivide(0_u32, &[], 3, 7), [1, 2]);
+
+    // A probe that takes longer the further it is from a multiple of
+    // eight, so they finish out of order.
+    struct Slow {
+      x: u32,
+      polls: u32,
+    }
+    impl Future for Slow {
+      type Output = Direction<(), ()>;
+      fn poll(
+          mut self: Pin<&mut Self>,
+          cx: &mut Context,
+        ) -> Poll<Self::Output> {
+        if self.polls < self.x % 8 {
+          self.polls += 1;
+          cx.waker().wake_by_ref();
+          return Poll::Pending;
+        }
+        Poll::Ready(if self.x < 777 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        })
+      }
+    }
+
+    let mut started = Vec::new();
+    let mut search =
+      Box::pin(binary_search_concurrent((0_u32, ()), (1000, ()), 4, |x| {
+        started.push(x);
+        Slow { x, polls: 0 }
+      }));
+    let mut context = Context::from_waker(Waker::noop());
+    let result =
+      loop {
+        if let Poll::Ready(result) = search.as_mut().poll(&mut context) {
+          break result;
+        }
+      };
+    drop(search);
+    assert_eq!(result, ((776, ()), (777, ())));
+    assert_eq!(started.get(..4), Some(&[125, 250, 500, 750][..]));
+  }
+
+  #[test]
+  fn betweenable_newtype_test() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct BlockHeight(u64);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct PageId(u32);
+    betweenable_newtype!(BlockHeight, PageId);
+
+    assert_eq!(
+      BlockHeight::between(BlockHeight(10), BlockHeight(20)),
+      Some(BlockHeight(15)),
+    );
+    assert_eq!(PageId::between(PageId(3), PageId(4)), None);
+    let ((low, ()), (high, ())) =
+      binary_search((BlockHeight(0), ()), (BlockHeight(1 << 20), ()), |h| {
+        if h.0 < 777_777 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!((low, high), (BlockHeight(777_776), BlockHeight(777_777)));
+  }
+
+  #[test]
+  fn binary_search_by_ord_test() {
+    let squares = |target: u64| {
+      move |x: u64| ((x * x).cmp(&target), x * x)
+    };
+    assert_eq!(
+      binary_search_by_ord((0, 0), (100, 10_000), squares(1369)),
+      Ok((37, 1369)),
+    );
+    assert_eq!(
+      binary_search_by_ord((0, 0), (100, 10_000), squares(1370)),
+      Err(((37, 1369), (38, 1444))),
+    );
+    // The same answers as searching a slice, with the bracket's high end
+    // where the target would be inserted.
+    let slice = [1, 3, 3, 8, 13];
+    for target in 0..15 {
+      let found =
+        binary_search_by_ord((-1_i64, ()), (5, ()), |i| {
+          let value = usize::try_from(i).ok().and_then(|i| slice.get(i));
+          (value.map_or(Ordering::Greater, |v| v.cmp(&target)), ())
+        });
+      let found =
+        found
+          .map(|(i, ())| usize::try_from(i).ok())
+          .map_err(|(_, (i, ()))| usize::try_from(i).ok());
+      let expected =
+        slice.binary_search(&target).map(Some).map_err(Some);
+      assert_eq!(found, expected, "{}", target);
+    }
+  }
+
+  #[test]
+  fn try_binary_search_test() {
+    let f = |x: u32| {
+      match x {
+        0..=22 => Ok(Direction::Low(())),
+        40 => Err("timeout"),
+        _ => Ok(Direction::High(())),
+      }
+    };
+    assert_eq!(
+      try_binary_search((0, ()), (100, ()), f),
+      Ok(((22, ()), (23, ()))),
+    );
+    assert_eq!(
+      try_binary_search((0, ()), (80, ()), f),
+      Err(Aborted { error: "timeout", low: (0, ()), high: (80, ()) }),
+    );
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn binary_search_checked_test() {
+    let threshold = |x: u32| {
+      if x < 23 { Direction::Low(x) } else { Direction::High(x) }
+    };
+    assert_eq!(
+      binary_search_checked((0, 0), (100, 100), threshold),
+      Ok(((22, 22), (23, 23))),
+    );
+    // Low again from 30 to 40, which the search alone never sees.
+    let dip = |x: u32| {
+      if x < 10 || (30..40).contains(&x) {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    };
+    assert_eq!(
+      binary_search((0, ()), (100, ()), dip),
+      ((9, ()), (10, ())),
+    );
+    assert_eq!(
+      binary_search_checked((0, ()), (100, ()), dip),
+      Err(NonMonotone { x_low: 37, x_high: 25 }),
+    );
+    let mut calls = 0;
+    let flaky = |x: u32| {
+      calls += 1;
+      if x < 23 || calls > 7 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    assert_eq!(
+      binary_search_checked((0, ()), (100, ()), flaky),
+      Err(NonMonotone { x_low: 23, x_high: 23 }),
+    );
+  }
+
+  #[test]
+  fn binary_search_with_budget_test() {
+    let threshold = |x: u32| {
+      if x < 23 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let never = || false;
+    assert_eq!(
+      binary_search_with_budget((0, ()), (100, ()), None, never, threshold),
+      Budgeted::Converged(((22, ()), (23, ()))),
+    );
+    assert_eq!(
+      binary_search_with_budget((0, ()), (100, ()), Some(7), never, threshold),
+      Budgeted::Converged(((22, ()), (23, ()))),
+    );
+    let result =
+      binary_search_with_budget((0, ()), (100, ()), Some(3), never, threshold);
+    assert_eq!(result, Budgeted::Exhausted(((12, ()), (25, ()))));
+    assert_eq!(result.converged(), None);
+    let mut asked = 0;
+    let cancel = || {
+      asked += 1;
+      asked > 2
+    };
+    let result =
+      binary_search_with_budget((0, ()), (100, ()), None, cancel, threshold);
+    assert_eq!(result.bracket(), ((0, ()), (25, ())));
+    assert!(matches!(result, Budgeted::Cancelled(_)));
+  }
+
+  #[test]
+  fn direction_is_comparable_and_hashable() {
+    let low: Direction<u8, char> = Direction::Low(1);
+    let copy = low;
+    assert_eq!(low, copy);
+    assert_ne!(low, Direction::High('a'));
+    let seen: std::collections::HashSet<_> =
+      [low, Direction::High('a'), copy].iter().copied().collect();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(format!("{:?}", low), "Low(1)");
+  }
+
+  #[test]
+  fn between_is_the_floor_of_the_mean() {
+    for low in i8::MIN..=i8::MAX {
+      for high in i8::MIN..=i8::MAX {
+        let mean = (i16::from(low) + i16::from(high)).div_euclid(2) as i8;
+        let expected = (low < mean && mean < high).then_some(mean);
+        assert_eq!(i8::between(low, high), expected, "{} {}", low, high);
+      }
+    }
+    assert_eq!(u8::between(0, u8::MAX), Some(127));
+    assert_eq!(u8::between(u8::MAX - 2, u8::MAX), Some(u8::MAX - 1));
+    assert_eq!(i128::between(i128::MIN, i128::MAX), Some(-1));
+    assert_eq!(i128::between(i128::MIN, i128::MIN + 2), Some(i128::MIN + 1));
+    assert_eq!(u128::between(u128::MAX - 2, u128::MAX), Some(u128::MAX - 1));
+    assert_eq!(isize::between(-3, -1), Some(-2));
+  }
+
+  #[test]
+  fn between_chars_skips_surrogates() {
+    assert_eq!(char::between('a', 'e'), Some('c'));
+    assert_eq!(char::between('a', 'b'), None);
+    assert_eq!(char::between('\u{D7FF}', '\u{E000}'), None);
+    assert_eq!(char::between('\u{D7FE}', '\u{E000}'), Some('\u{D7FF}'));
+    assert_eq!(char::between('\u{D7FF}', '\u{E001}'), Some('\u{E000}'));
+    let step = 0x1F3;
+    for low in (0..=0x10FFFF).step_by(step).filter_map(char::from_u32) {
+      for &offset in [1, 2, 3, 0x801, 0x7FF, 0x10000].iter() {
+        let high =
+          match (u32::from(low) + offset..=0x10FFFF).find_map(char::from_u32) {
+            Some(high) => high,
+            None => continue,
+          };
+        if let Some(mid) = char::between(low, high) {
+          assert!(low < mid && mid < high, "{:?} {:?} {:?}", low, mid, high);
+        }
+      }
+    }
+    let ((_, ()), (first, ())) =
+      binary_search(('\0', ()), (char::MAX, ()), |c| {
+        if c < '\u{E000}' {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(first, '\u{E000}');
+  }
+
+  #[test]
+  fn between_non_zero() {
+    let u = |x| NonZeroUsize::new(x).unwrap();
+    assert_eq!(NonZeroUsize::between(u(1), u(9)), Some(u(5)));
+    assert_eq!(NonZeroUsize::between(u(1), u(2)), None);
+    let i = |x| NonZeroI8::new(x).unwrap();
+    assert_eq!(NonZeroI8::between(i(-1), i(1)), None);
+    assert_eq!(NonZeroI8::between(i(-2), i(1)), Some(i(-1)));
+    assert_eq!(NonZeroI8::between(i(-1), i(2)), Some(i(1)));
+    assert_eq!(NonZeroI8::between(i(-3), i(3)), Some(i(-1)));
+    let all: Vec<NonZeroI8> =
+      (i8::MIN..=i8::MAX).filter_map(NonZeroI8::new).collect();
+    for (a, &low) in all.iter().enumerate() {
+      for &high in all.iter().skip(a) {
+        match NonZeroI8::between(low, high) {
+          Some(mid) => assert!(low < mid && mid < high),
+          None => assert!(all.iter().all(|&x| x <= low || x >= high)),
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn between_addresses() {
+    let v4 = Ipv4Addr::new;
+    assert_eq!(
+      Ipv4Addr::between(v4(10, 0, 0, 0), v4(10, 0, 2, 0)),
+      Some(v4(10, 0, 1, 0)),
+    );
+    assert_eq!(Ipv4Addr::between(v4(10, 0, 0, 0), v4(10, 0, 0, 1)), None);
+    let v6 = |s: &str| s.parse::<Ipv6Addr>().unwrap();
+    assert_eq!(
+      Ipv6Addr::between(v6("2001:db8::"), v6("2001:db8::ff")),
+      Some(v6("2001:db8::7f")),
+    );
+    // The first address of a blocked subnet.
+    let subnet = (v4(192, 168, 0, 0), v4(192, 168, 255, 255));
+    let ((_, ()), (first, ())) =
+      binary_search((subnet.0, ()), (subnet.1, ()), |a| {
+        if a < v4(192, 168, 64, 0) {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(first, v4(192, 168, 64, 0));
+  }
+
+  #[test]
+  fn between_times() {
+    let secs = Duration::from_secs;
+    assert_eq!(Duration::between(secs(0), secs(60)), Some(secs(30)));
+    assert_eq!(
+      Duration::between(Duration::from_nanos(1), secs(1)),
+      Some(Duration::from_nanos(500_000_000)),
+    );
+    assert_eq!(
+      Duration::between(Duration::from_nanos(1), Duration::from_nanos(2)),
+      None,
+    );
+    assert!(Duration::between(Duration::ZERO, Duration::MAX).is_some());
+    let ((_, ()), (timeout, ())) =
+      binary_search((Duration::ZERO, ()), (secs(60), ()), |d| {
+        if d < Duration::from_millis(1500) {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(timeout, Duration::from_millis(1500));
+
+    #[cfg(feature = "std")]
+    {
+      let before = UNIX_EPOCH - secs(10);
+      let after = UNIX_EPOCH + secs(20);
+      assert_eq!(
+        SystemTime::between(before, after),
+        Some(UNIX_EPOCH + secs(5)),
+      );
+      assert_eq!(
+        SystemTime::between(before, UNIX_EPOCH),
+        Some(UNIX_EPOCH - secs(5)),
+      );
+    }
+  }
+
+  #[test]
+  fn between_never_panics_at_extremes() {
+    let values = [0, 1, 2, usize::MAX - 2, usize::MAX - 1, usize::MAX];
+    for &low in values.iter() {
+      for &high in values.iter() {
+        let result = std::panic::catch_unwind(|| usize::between(low, high));
+        assert!(result.is_ok(), "between({}, {}) panicked", low, high);
+      }
+    }
+    assert_eq!(usize::between(usize::MAX, usize::MAX), None);
+    assert_eq!(i64::between(i64::MIN, i64::MAX), Some(-1));
+    assert_eq!(i64::between(i64::MAX - 1, i64::MAX), None);
+  }
+
+  #[test]
+  fn binary_search_never_panics_over_full_range() {
+    for &threshold in [0, 1, u64::MAX / 2, u64::MAX - 1, u64::MAX].iter() {
+      let result = std::panic::catch_unwind(|| {
+        binary_search((0_u64, ()), (u64::MAX, ()), |x| {
+          if x < threshold {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        })
+      });
+      assert!(result.is_ok(), "search for {} panicked", threshold);
+    }
+  }
+
+  #[test]
+  fn binary_search_in_custom_space() {
+    use std::collections::BTreeSet;
+    use std::ops::Bound::Excluded;
+
+    // The elements of a set, probed at the median of those in the bracket.
+    struct Elements(BTreeSet<u32>);
+
+    impl SearchSpace for Elements {
+      type Position = u32;
+
+      fn candidate(&mut self, &low: &u32, &high: &u32) -> Option<u32> {
+        let inside = || self.0.range((Excluded(low), Excluded(high)));
+        inside().nth(inside().count() / 2).copied()
+      }
+    }
+
+    let mut space = Elements((0..50).map(|x| x * x).collect());
+    let mut probes = 0;
+    let result =
+      binary_search_in(&mut space, (0, ()), (2401, ()), |x| {
+        probes += 1;
+        if x < 1000 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(result, ((961, ()), (1024, ())));
+    assert!(probes <= 6);
+  }
+
+  #[test]
+  fn strategies_choose_probes() {
+    let threshold = |x: u32| {
+      if x < 40 { Direction::Low(x) } else { Direction::High(x) }
+    };
+    let expected = binary_search((0, 0), (1 << 20, 1 << 20), threshold);
+    let search = |strategy: &mut dyn Strategy<u32, u32, u32>| {
+      let mut probes = Vec::new();
+      let result =
+        binary_search_with_strategy((0, 0), (1 << 20, 1 << 20), strategy, |x| {
+          probes.push(x);
+          threshold(x)
+        });
+      assert_eq!(result, expected);
+      probes
+    };
+    let bisected = search(&mut Midpoint);
+    assert_eq!(bisected.len(), 20);
+    assert!(search(&mut Biased::Low(3)).len() < bisected.len());
+    search(&mut Biased::High(1));
+    let mut jittered = Jittered::new(7, 2);
+    let probes = search(&mut jittered);
+    assert!(probes != bisected && probes.len() < 80, "{:?}", probes);
+    assert_eq!(search(&mut Jittered::new(7, 2)), probes);
+    assert_ne!(search(&mut jittered), probes);
+    assert_eq!(search(&mut Jittered::new(7, 0)), bisected);
+
+    // Scanning up one at a time from the low end.
+    let mut scan = |low: &(u32, u32), high: &(u32, u32)| {
+      Some(low.0 + 1).filter(|&x| x < high.0)
+    };
+    assert_eq!(search(&mut scan), (1..=40).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn binary_search_dyn_test() {
+    let mut probes = 0;
+    let result =
+      binary_search_dyn((0_u64, "low"), (1000, "high"), &mut |x| {
+        probes += 1;
+        if x < 777 {
+          Direction::Low("low")
+        } else {
+          Direction::High("high")
+        }
+      });
+    assert_eq!(result, ((776, "low"), (777, "high")));
+    assert_eq!(probes, 10);
+  }
 }
@@ -0,0 +1,74 @@
+//! Discovering a limit, such as the largest packet a path will carry, with
+//! probes that are sometimes lost.
+
+use crate::gallop::{nearest_transition, Steppable};
+use crate::Direction;
+
+/// How repeated attempts at one value are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Policy {
+  /// A value is accepted if any attempt succeeds, for when failures may
+  /// just be losses.
+  AnySuccess,
+  /// A value is accepted only if every attempt succeeds, for when
+  /// occasional success over the limit is possible.
+  AllSuccess,
+}
+
+/// Find the limit of `probe`, starting from a guess and galloping away from
+/// it before bisecting.
+///
+/// Each value is tried up to `attempts` times, stopping as soon as
+/// `policy` is decided. Returns the largest value accepted and the smallest
+/// rejected, or `None` if every value up to the end of the type is accepted
+/// or every value down to the start is rejected.
+pub fn discover_limit<X, F>(
+    guess: X,
+    attempts: usize,
+    policy: Policy,
+    mut probe: F,
+  ) -> Option<(X, X)>
+  where
+    X: Steppable,
+    F: FnMut(X) -> bool {
+  let attempts = attempts.max(1);
+  let (low, high) =
+    nearest_transition(guess, |x| {
+      let mut tries = (0..attempts).map(|_| (probe)(x));
+      let accepted =
+        match policy {
+          Policy::AnySuccess => tries.any(|ok| ok),
+          Policy::AllSuccess => tries.all(|ok| ok),
+        };
+      if accepted {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    })?;
+  Some((low.0, high.0))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_limit_through_losses() {
+    // Every third probe is lost, whatever its size.
+    let mut sent = 0;
+    let mut lossy = |size: u32| {
+      sent += 1;
+      size <= 1472 && sent % 3 != 0
+    };
+    assert_eq!(
+      discover_limit(1000, 3, Policy::AnySuccess, &mut lossy),
+      Some((1472, 1473)),
+    );
+    assert_eq!(
+      discover_limit(1000, 1, Policy::AllSuccess, |size| size <= 9000),
+      Some((9000, 9001)),
+    );
+    assert_eq!(discover_limit(5_u8, 2, Policy::AllSuccess, |_| true), None);
+  }
+}
@@ -0,0 +1,72 @@
+//! Searching ordered types through a bijection with a `Betweenable` domain.
+//!
+//! Rather than implementing `Betweenable` for an exotic ordered type, you
+//! can supply a monotone bijection between it and a canonical domain, such
+//! as `u64`, and search through that instead.
+
+use crate::{binary_search, Betweenable, Direction};
+
+/// A pair of functions mapping a domain into a canonical `Betweenable`
+/// domain and back again.
+///
+/// `into_canonical` must be strictly increasing, and `from_canonical` must
+/// be its inverse over the range that is searched.
+pub struct MappedDomain<I, F> {
+  into_canonical: I,
+  from_canonical: F,
+}
+
+impl<I, F> MappedDomain<I, F> {
+  pub fn new(into_canonical: I, from_canonical: F) -> Self {
+    MappedDomain { into_canonical, from_canonical }
+  }
+
+  /// Like `binary_search`, but over the mapped domain. Probes and results
+  /// are mapped back out of the canonical domain.
+  pub fn binary_search<Y, X, A, B, P>(
+      &self,
+      low: (Y, A),
+      high: (Y, B),
+      mut f: P,
+    ) -> ((Y, A), (Y, B))
+    where
+      X: Betweenable,
+      I: Fn(Y) -> X,
+      F: Fn(X) -> Y,
+      P: FnMut(Y) -> Direction<A, B> {
+    let low = ((self.into_canonical)(low.0), low.1);
+    let high = ((self.into_canonical)(high.0), high.1);
+    let ((x, a), (y, b)) =
+      binary_search(low, high, |x| (f)((self.from_canonical)(x)));
+    (((self.from_canonical)(x), a), ((self.from_canonical)(y), b))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn search_durations_by_millisecond() {
+    let millis =
+      MappedDomain::new(
+        |d: Duration| d.as_millis() as u64,
+        Duration::from_millis,
+      );
+    let ((low, ()), (high, ())) =
+      millis.binary_search(
+        (Duration::ZERO, ()),
+        (Duration::from_secs(10), ()),
+        |d| {
+          if d < Duration::from_micros(1_234_500) {
+            Direction::Low(())
+          } else {
+            Direction::High(())
+          }
+        },
+      );
+    assert_eq!(low, Duration::from_millis(1234));
+    assert_eq!(high, Duration::from_millis(1235));
+  }
+}
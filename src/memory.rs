@@ -0,0 +1,122 @@
+//! Finding the extent of readable memory in another process, as debuggers
+//! and memory scanners do, through a reader such as a wrapper around
+//! `process_vm_readv` or `/proc/PID/mem`.
+//!
+//! Memory is probed a page at a time, by reading the first byte of the
+//! page. The searches assume that readable pages around the starting
+//! address are contiguous within the bounds given: if there's a gap
+//! followed by more readable memory, either side of it may be reported.
+//! Bounds taken from a memory map keep the search within one mapping.
+
+use std::io;
+use std::ops::Range;
+
+use crate::{binary_search, Direction};
+
+/// Read access to the memory of another process.
+pub trait ProcessMemory {
+  /// Fill `buf` with the bytes starting at `address`, or fail if any of
+  /// them can't be read.
+  fn read_at(&mut self, address: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+fn is_readable<M>(memory: &mut M, page: u64, page_size: u64) -> bool
+  where
+    M: ProcessMemory + ?Sized {
+  page.checked_mul(page_size)
+    .is_some_and(|address| memory.read_at(address, &mut [0]).is_ok())
+}
+
+/// Find the readable pages around `address`, within `bounds`, as a range of
+/// addresses clipped to `bounds`.
+///
+/// Returns `None` if `address` isn't readable or isn't within `bounds`, or
+/// if `page_size` is zero. Takes `O(log n)` reads, where `n` is the number
+/// of pages within `bounds`.
+pub fn readable_region<M>(
+    memory: &mut M,
+    address: u64,
+    bounds: Range<u64>,
+    page_size: u64,
+  ) -> Option<Range<u64>>
+  where
+    M: ProcessMemory + ?Sized {
+  if !bounds.contains(&address) {
+    return None;
+  }
+  let page = address.checked_div(page_size)?;
+  if !is_readable(memory, page, page_size) {
+    return None;
+  }
+  let first = bounds.start / page_size;
+  let end = bounds.end.div_ceil(page_size);
+  let ((_, ()), (end_page, ())) =
+    binary_search((page, ()), (end, ()), |p| {
+      if is_readable(memory, p, page_size) {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    });
+  // Page `q - 1` is searched for at `q`, so that the bound below the first
+  // page is representable.
+  let ((_, ()), (start_page, ())) =
+    binary_search((first, ()), (page + 1, ()), |q| {
+      if is_readable(memory, q - 1, page_size) {
+        Direction::High(())
+      } else {
+        Direction::Low(())
+      }
+    });
+  let start = ((start_page - 1) * page_size).max(bounds.start);
+  let end = end_page.saturating_mul(page_size).min(bounds.end);
+  Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Mapped(Vec<Range<u64>>);
+
+  impl ProcessMemory for Mapped {
+    fn read_at(&mut self, address: u64, buf: &mut [u8]) -> io::Result<()> {
+      let end = address + buf.len() as u64;
+      if self.0.iter().any(|r| r.start <= address && end <= r.end) {
+        Ok(())
+      } else {
+        Err(io::Error::other("unmapped"))
+      }
+    }
+  }
+
+  #[test]
+  fn finds_readable_regions() {
+    let mut memory = Mapped(vec![0x1000..0x5000, 0x8000..0x9000]);
+    let everywhere = 0..u64::MAX;
+    assert_eq!(
+      readable_region(&mut memory, 0x2345, everywhere.clone(), 0x1000),
+      Some(0x1000..0x5000),
+    );
+    assert_eq!(
+      readable_region(&mut memory, 0x8000, 0x5000..0x10000, 0x1000),
+      Some(0x8000..0x9000),
+    );
+    assert_eq!(
+      readable_region(&mut memory, 0x4fff, 0x1800..0x4800, 0x1000),
+      None,
+    );
+    assert_eq!(
+      readable_region(&mut memory, 0x2000, 0x1800..0x4800, 0x1000),
+      Some(0x1800..0x4800),
+    );
+    assert_eq!(readable_region(&mut memory, 0x6000, everywhere, 0x1000), None);
+    assert_eq!(readable_region(&mut memory, 0x2000, 0..0x9000, 0), None);
+
+    let mut low = Mapped(vec![0..0x3000, 0x20000..0x21000]);
+    assert_eq!(
+      readable_region(&mut low, 0x10, 0..0x10000, 0x1000),
+      Some(0..0x3000),
+    );
+  }
+}
@@ -0,0 +1,89 @@
+//! Merging a stream into a sorted slice, skipping over runs of the slice
+//! rather than comparing element by element.
+
+use core::iter::Peekable;
+
+use crate::cursor::Cursor;
+
+/// The merge of a sorted iterator with a sorted slice, in order.
+///
+/// Each time the iterator's next item is compared with the slice, the
+/// whole run of slice elements that come before it is found by galloping,
+/// so a run of length `d` costs `O(log d)` comparisons. Slice elements
+/// come before equal items from the iterator.
+pub struct GallopMerge<'a, T, I>
+  where
+    I: Iterator<Item = &'a T> {
+  iter: Peekable<I>,
+  slice: &'a [T],
+  cursor: Cursor<'a, T>,
+  next: usize,
+  run_end: usize,
+}
+
+impl<'a, T, I> Iterator for GallopMerge<'a, T, I>
+  where
+    T: Ord,
+    I: Iterator<Item = &'a T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<&'a T> {
+    if self.next >= self.run_end {
+      self.run_end =
+        match self.iter.peek() {
+          Some(head) => self.cursor.upper_bound(head),
+          None => self.slice.len(),
+        };
+    }
+    if self.next < self.run_end {
+      let item = self.slice.get(self.next);
+      self.next += 1;
+      item
+    } else {
+      self.iter.next()
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let rest = self.slice.len().saturating_sub(self.next);
+    let (low, high) = self.iter.size_hint();
+    (low.saturating_add(rest), high.and_then(|high| high.checked_add(rest)))
+  }
+}
+
+/// Merge the sorted items of `iter` into the sorted `slice`.
+pub fn gallop_merge<'a, T, I>(
+    iter: I,
+    slice: &'a [T],
+  ) -> GallopMerge<'a, T, I::IntoIter>
+  where
+    T: Ord,
+    I: IntoIterator<Item = &'a T> {
+  GallopMerge {
+    iter: iter.into_iter().peekable(),
+    slice,
+    cursor: Cursor::new(slice),
+    next: 0,
+    run_end: 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn merges_in_order() {
+    let slice: Vec<u32> = (0..100).map(|x| x * 3).collect();
+    let stream = [0_u32, 1, 2, 3, 150, 151, 400, 401];
+    let merged: Vec<u32> = gallop_merge(&stream, &slice).copied().collect();
+    let mut expected = [slice.clone(), stream.to_vec()].concat();
+    expected.sort();
+    assert_eq!(merged, expected);
+
+    let mut merge = gallop_merge(&stream, &[]);
+    assert_eq!(merge.size_hint(), (8, Some(8)));
+    assert_eq!(merge.next(), Some(&0));
+    assert_eq!(gallop_merge(&[], &slice).count(), 100);
+  }
+}
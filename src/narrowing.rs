@@ -0,0 +1,193 @@
+//! A search that can be watched as it converges.
+//!
+//! `narrowing` is an iterator: each call to `next` makes one probe and
+//! yields the bracket it narrowed to. With the `async` feature,
+//! `narrowing_async` does the same with an asynchronous predicate, as a
+//! stream whose `poll_next` has the signature of `futures::Stream`'s, so it
+//! can be wrapped as one without the crate depending on `futures`.
+//! Rendering each item shows the search converging live, and dropping the
+//! iterator or stream cancels the search, along with any probe in flight.
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+use crate::{Betweenable, Direction};
+
+/// The brackets of a search, from the first probe to the last.
+pub struct Narrowing<X, F> {
+  low: X,
+  high: X,
+  f: F,
+}
+
+impl<X, F> Narrowing<X, F> {
+  /// The bracket the search has narrowed to so far.
+  pub fn bracket(&self) -> (X, X)
+    where
+      X: Copy {
+    (self.low, self.high)
+  }
+}
+
+impl<X, F> Iterator for Narrowing<X, F>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<(), ()> {
+  type Item = (X, X);
+
+  fn next(&mut self) -> Option<(X, X)> {
+    let x = X::between(self.low, self.high)?;
+    match (self.f)(x) {
+      Direction::Low(()) => self.low = x,
+      Direction::High(()) => self.high = x,
+    }
+    Some((self.low, self.high))
+  }
+}
+
+/// Search between `low` and `high` one probe at a time, yielding each
+/// narrower bracket. The last item is the result of the search.
+pub fn narrowing<X, F>(low: X, high: X, f: F) -> Narrowing<X, F>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<(), ()> {
+  Narrowing { low, high, f }
+}
+
+/// The brackets of a search with an asynchronous predicate, from the first
+/// probe to the last.
+#[cfg(feature = "async")]
+pub struct NarrowingAsync<X, F, Fut> {
+  low: X,
+  high: X,
+  f: F,
+  probe: Option<(X, Pin<Box<Fut>>)>,
+}
+
+// The probe in flight is boxed, and nothing else is pinned.
+#[cfg(feature = "async")]
+impl<X, F, Fut> Unpin for NarrowingAsync<X, F, Fut> {}
+
+#[cfg(feature = "async")]
+impl<X, F, Fut> NarrowingAsync<X, F, Fut>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Fut,
+    Fut: Future<Output = Direction<(), ()>> {
+  /// The bracket the search has narrowed to so far.
+  pub fn bracket(&self) -> (X, X) {
+    (self.low, self.high)
+  }
+
+  /// Make the next probe, or carry on with the one in flight, as
+  /// `futures::Stream::poll_next` does, finishing with `None` once the
+  /// search is done.
+  pub fn poll_next(
+      self: Pin<&mut Self>,
+      context: &mut Context<'_>,
+    ) -> Poll<Option<(X, X)>> {
+    let this = self.get_mut();
+    let (x, probe) =
+      match &mut this.probe {
+        Some(probe) => probe,
+        None => {
+          let x =
+            match X::between(this.low, this.high) {
+              Some(x) => x,
+              None => return Poll::Ready(None),
+            };
+          this.probe.insert((x, Box::pin((this.f)(x))))
+        },
+      };
+    let direction =
+      match probe.as_mut().poll(context) {
+        Poll::Ready(direction) => direction,
+        Poll::Pending => return Poll::Pending,
+      };
+    match direction {
+      Direction::Low(()) => this.low = *x,
+      Direction::High(()) => this.high = *x,
+    }
+    this.probe = None;
+    Poll::Ready(Some((this.low, this.high)))
+  }
+
+  /// The next bracket, or `None` once the search is done.
+  pub async fn next(&mut self) -> Option<(X, X)> {
+    std::future::poll_fn(|context| Pin::new(&mut *self).poll_next(context))
+      .await
+  }
+}
+
+/// Like `narrowing`, but with an asynchronous predicate, such as a request
+/// to a remote service. Each probe is awaited before the next is made.
+#[cfg(feature = "async")]
+pub fn narrowing_async<X, F, Fut>(
+    low: X,
+    high: X,
+    f: F,
+  ) -> NarrowingAsync<X, F, Fut>
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Fut,
+    Fut: Future<Output = Direction<(), ()>> {
+  NarrowingAsync { low, high, f, probe: None }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn yields_each_bracket() {
+    let threshold = |x: u32| {
+      if x < 6 {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    };
+    let brackets: Vec<(u32, u32)> = narrowing(0, 16, threshold).collect();
+    assert_eq!(brackets, vec![(0, 8), (4, 8), (4, 6), (5, 6)]);
+
+    let mut search = narrowing(0, 16, threshold);
+    assert_eq!(search.nth(1), Some((4, 8)));
+    assert_eq!(search.bracket(), (4, 8));
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn streams_each_bracket() {
+    use std::task::Waker;
+
+    let threshold = |x: u32| {
+      std::future::ready(if x < 6 {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      })
+    };
+    let mut context = Context::from_waker(Waker::noop());
+    let mut search = narrowing_async(0, 16, threshold);
+    let mut brackets = Vec::new();
+    while let Poll::Ready(Some(bracket)) =
+      Pin::new(&mut search).poll_next(&mut context) {
+      brackets.push(bracket);
+    }
+    assert_eq!(brackets, vec![(0, 8), (4, 8), (4, 6), (5, 6)]);
+    assert_eq!(search.bracket(), (5, 6));
+
+    let mut search = narrowing_async(0, 16, threshold);
+    let mut next = Box::pin(search.next());
+    assert_eq!(next.as_mut().poll(&mut context), Poll::Ready(Some((0, 8))));
+
+    let pending = |_: u32| std::future::pending::<Direction<(), ()>>();
+    let mut stuck = narrowing_async(0, 16, pending);
+    assert_eq!(Pin::new(&mut stuck).poll_next(&mut context), Poll::Pending);
+    assert_eq!(stuck.bracket(), (0, 16));
+  }
+}
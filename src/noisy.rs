@@ -0,0 +1,369 @@
+//! Searches where the predicate sometimes gives the wrong answer.
+//!
+//! Rather than trusting each answer, these keep a probability for where the
+//! transition is and update it with each answer according to how reliable
+//! it is, probing where the evidence is most evenly split. A wrong answer
+//! then only costs a few extra probes to undo, instead of sending the
+//! search the wrong way for good.
+
+use std::convert::TryFrom;
+
+use crate::{Betweenable, Direction};
+
+/// Where a noisy search believes the transition is: between `low` and
+/// `high`, with probability `confidence`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate<X> {
+  pub low: X,
+  pub high: X,
+  pub confidence: f64,
+  pub probes: usize,
+}
+
+/// Search between `low` and `high` with a predicate that reports how
+/// likely each of its answers is to be right.
+///
+/// `f` returns a direction and the probability that it's correct, from
+/// 0.5 for a coin toss up to 1 for certain. Probes continue until some
+/// adjacent pair of points brackets the transition with probability at
+/// least `target`, or `max_probes` have been made, in which case the most
+/// likely bracket found so far is returned.
+pub fn weighted_search<X, F>(
+    low: X,
+    high: X,
+    target: f64,
+    max_probes: usize,
+    mut f: F,
+  ) -> Estimate<X>
+  where
+    X: Betweenable + Ord,
+    F: FnMut(X) -> (Direction<(), ()>, f64) {
+  // Contiguous regions `(a, b]` of where the first high point might be,
+  // each with the probability that it's there.
+  let mut regions = vec![(low, high, 1.0_f64)];
+  let target = target.min(1.0);
+  let mut probes = 0;
+  loop {
+    let best =
+      regions.iter().copied()
+        .max_by(|x, y| x.2.total_cmp(&y.2))
+        .unwrap_or((low, high, 1.0));
+    let narrow = X::between(best.0, best.1).is_none();
+    let x =
+      match probe_point(&regions, high) {
+        Some(x) if probes < max_probes && !(narrow && best.2 >= target) => x,
+        _ => {
+          return Estimate {
+            low: best.0,
+            high: best.1,
+            confidence: best.2,
+            probes,
+          };
+        },
+      };
+    split_at(&mut regions, x);
+
+    let (direction, confidence) = (f)(x);
+    probes += 1;
+    let confidence = confidence.clamp(0.5, 1.0);
+    let (below, above) =
+      match direction {
+        Direction::Low(()) => (1.0 - confidence, confidence),
+        Direction::High(()) => (confidence, 1.0 - confidence),
+      };
+    let updated: Vec<f64> =
+      regions.iter()
+        .map(|&(_, b, p)| if b <= x { p * below } else { p * above })
+        .collect();
+    // Certain answers that contradict each other leave nothing to go on,
+    // so the later one is ignored.
+    let total: f64 = updated.iter().sum();
+    if total > 0.0 {
+      for (region, p) in regions.iter_mut().zip(updated) {
+        region.2 = p / total;
+      }
+    }
+  }
+}
+
+/// The fewest samples `benchmark_search` tests, below which the normal
+/// approximation it uses can't be trusted.
+const MIN_SAMPLES: usize = 5;
+
+/// Search for the first point whose benchmark is slower than `threshold`,
+/// a noisy search where each probe runs as many samples as it needs.
+///
+/// `sample(x)` runs the benchmark once at `x`, returning a measurement
+/// such as a time. Each probe takes samples until a two-sided test at
+/// significance `alpha` says their mean differs from `threshold`, or until
+/// `max_samples`, and then answers `Low` if the mean is below `threshold`.
+/// The test's p-value sets how far the answer is trusted, so a probe that
+/// ran out of samples close to the threshold counts for little. Returns the
+/// estimate and the total number of samples taken.
+///
+/// Each probe takes at least five samples before testing, however small
+/// `max_samples` is. The p-value is never taken to be below `2^(1 - n)`,
+/// that of a sign test with all `n` samples on one side of `threshold`, so
+/// samples that all agree, as a coarse timer's often do, still leave some
+/// doubt, and no answer is ever certain.
+pub fn benchmark_search<X, F>(
+    low: X,
+    high: X,
+    threshold: f64,
+    alpha: f64,
+    max_samples: usize,
+    max_probes: usize,
+    mut sample: F,
+  ) -> (Estimate<X>, usize)
+  where
+    X: Betweenable + Ord,
+    F: FnMut(X) -> f64 {
+  let mut total_samples = 0;
+  let estimate =
+    weighted_search(low, high, 1.0 - alpha, max_probes, |x| {
+      let mut mean = 0.0;
+      let mut m2 = 0.0;
+      let mut p = 1.0;
+      for count in 1..=max_samples.max(MIN_SAMPLES) {
+        // Welford's running mean and variance.
+        let value = (sample)(x);
+        total_samples += 1;
+        let n = count as f64;
+        let delta = value - mean;
+        mean += delta / n;
+        m2 += delta * (value - mean);
+        if count >= MIN_SAMPLES {
+          let error = (m2 / (n - 1.0) / n).sqrt();
+          let sign_test = 2.0_f64.powf(1.0 - n);
+          p =
+            if error > 0.0 {
+              2.0 * (1.0 - normal_cdf(((mean - threshold) / error).abs()))
+            } else if mean == threshold {
+              1.0
+            } else {
+              0.0
+            };
+          p = p.max(sign_test);
+          if p < alpha {
+            break;
+          }
+        }
+      }
+      let confidence = 1.0 - p / 2.0;
+      if mean < threshold {
+        (Direction::Low(()), confidence)
+      } else {
+        (Direction::High(()), confidence)
+      }
+    });
+  (estimate, total_samples)
+}
+
+/// Search with a predicate that's sometimes wrong but doesn't know when,
+/// asking it up to `samples` times at each probe and going with the
+/// majority.
+///
+/// Sampling at a probe stops as soon as the majority can't change. How far
+/// the majority is trusted comes from how much the answers agreed: with
+/// each answer taken to be right with probability `q`, estimated from the
+/// votes as `(winner + 1) / (n + 2)`, a lead of `d` votes is right with
+/// odds of `(q / (1 - q))^d`. A split vote at one probe then counts for
+/// little, and the search goes back to check it. Otherwise this works as
+/// `weighted_search` does, and returns its estimate and the total number of
+/// samples taken.
+pub fn majority_search<X, F>(
+    low: X,
+    high: X,
+    samples: usize,
+    target: f64,
+    max_probes: usize,
+    mut f: F,
+  ) -> (Estimate<X>, usize)
+  where
+    X: Betweenable + Ord,
+    F: FnMut(X) -> Direction<(), ()> {
+  let samples = samples.max(1);
+  let mut total_samples = 0;
+  let estimate =
+    weighted_search(low, high, target, max_probes, |x| {
+      let (mut lows, mut highs) = (0_usize, 0_usize);
+      while lows.max(highs) <= samples / 2 && lows + highs < samples {
+        match (f)(x) {
+          Direction::Low(()) => lows += 1,
+          Direction::High(()) => highs += 1,
+        }
+        total_samples += 1;
+      }
+      let (winner, loser) = (lows.max(highs), lows.min(highs));
+      let q = (winner as f64 + 1.0) / ((winner + loser) as f64 + 2.0);
+      let lead = i32::try_from(winner - loser).unwrap_or(i32::MAX);
+      let confidence = 1.0 / (1.0 + ((1.0 - q) / q).powi(lead));
+      if lows > highs {
+        (Direction::Low(()), confidence)
+      } else {
+        (Direction::High(()), confidence)
+      }
+    });
+  (estimate, total_samples)
+}
+
+/// The standard normal CDF, by Abramowitz and Stegun's 7.1.26, which is
+/// accurate to about 1e-7.
+fn normal_cdf(z: f64) -> f64 {
+  let x = z.abs() / std::f64::consts::SQRT_2;
+  let t = 1.0 / (1.0 + 0.327_591_1 * x);
+  let poly =
+    t * (0.254_829_592
+      + t * (-0.284_496_736
+        + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+  let erf = 1.0 - poly * (-x * x).exp();
+  if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// The point at which the probability of the transition being at or below
+/// it is closest to a half, among region ends and midpoints.
+fn probe_point<X>(regions: &[(X, X, f64)], high: X) -> Option<X>
+  where
+    X: Betweenable + Ord {
+  let mut below = 0.0;
+  let mut best: Option<(X, f64)> = None;
+  let mut consider = |x: X, p: f64| {
+    let gap = (p - 0.5).abs();
+    if best.is_none_or(|(_, best_gap)| gap < best_gap) {
+      best = Some((x, gap));
+    }
+  };
+  for &(a, b, p) in regions {
+    if let Some(mid) = X::between(a, b) {
+      (consider)(mid, below + p / 2.0);
+    }
+    below += p;
+    if b < high {
+      (consider)(b, below);
+    }
+  }
+  best.map(|(x, _)| x)
+}
+
+/// Split the region that contains `x` in its interior at `x`, sharing its
+/// probability evenly.
+fn split_at<X>(regions: &mut Vec<(X, X, f64)>, x: X)
+  where
+    X: Ord + Copy {
+  let position = regions.iter().position(|&(a, b, _)| a < x && x < b);
+  if let Some(i) = position {
+    if let Some(region) = regions.get_mut(i) {
+      let (a, b, p) = *region;
+      *region = (a, x, p / 2.0);
+      regions.insert(i + 1, (x, b, p / 2.0));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn recovers_from_wrong_answers() {
+    // Answers are wrong one time in five, from a fixed pseudo-random
+    // sequence.
+    let mut state = 12345_u32;
+    let result =
+      weighted_search(0_u32, 1000, 0.99, 200, |x| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        let correct = !(state >> 16).is_multiple_of(5);
+        if (x < 700) == correct {
+          (Direction::Low(()), 0.8)
+        } else {
+          (Direction::High(()), 0.8)
+        }
+      });
+    assert_eq!((result.low, result.high), (699, 700));
+    assert!(result.confidence >= 0.99);
+    assert!(result.probes < 200);
+  }
+
+  #[test]
+  fn certain_answers_bisect() {
+    let result =
+      weighted_search(0_u32, 1024, 1.0, 100, |x| {
+        if x < 300 {
+          (Direction::Low(()), 1.0)
+        } else {
+          (Direction::High(()), 1.0)
+        }
+      });
+    assert_eq!(
+      result,
+      Estimate { low: 299, high: 300, confidence: 1.0, probes: 10 },
+    );
+  }
+
+  #[test]
+  fn benchmark_search_adapts_samples() {
+    // Commit 600 made the benchmark 10% slower, under noise of about 5%.
+    let mut state = 7_u32;
+    let mut samples_at = std::collections::HashMap::new();
+    let (estimate, total) =
+      benchmark_search(0_u32, 1000, 105.0, 0.01, 200, 100, |commit| {
+        *samples_at.entry(commit).or_insert(0) += 1;
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        let noise = f64::from((state >> 16) % 1000) / 100.0 - 5.0;
+        let base = if commit < 600 { 100.0 } else { 110.0 };
+        base + noise
+      });
+    assert_eq!((estimate.low, estimate.high), (599, 600));
+    assert!(estimate.confidence >= 0.99);
+    assert_eq!(samples_at.values().sum::<usize>(), total);
+    assert!(total < 100 * estimate.probes, "{} samples", total);
+    assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+  }
+
+  #[test]
+  fn benchmark_search_doubts_identical_samples() {
+    // A timer with a coarse resolution reads the same every time.
+    let (estimate, total) =
+      benchmark_search(0_u32, 1000, 105.0, 0.01, 200, 100, |commit| {
+        if commit < 600 { 100.0 } else { 110.0 }
+      });
+    assert_eq!((estimate.low, estimate.high), (599, 600));
+    assert!(estimate.confidence < 1.0);
+    // Eight samples that agree are the fewest that pass a test at 1%.
+    assert_eq!(total, 8 * estimate.probes);
+
+    // Two samples aren't tested at all, and five only trusted as far as a
+    // sign test allows.
+    let (estimate, total) =
+      benchmark_search(0_u32, 2, 105.0, 0.01, 2, 1, |_| 100.0);
+    assert_eq!((estimate.low, estimate.high, total), (1, 2, 5));
+    assert_eq!(estimate.confidence, 1.0 - 0.5_f64.powi(5));
+  }
+
+  #[test]
+  fn majority_search_outvotes_misfires() {
+    // Answers are wrong one time in four.
+    let mut state = 99_u32;
+    let (estimate, total) =
+      majority_search(0_u32, 1000, 7, 0.99, 200, |x| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        let correct = !(state >> 16).is_multiple_of(4);
+        if (x < 321) == correct {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!((estimate.low, estimate.high), (320, 321));
+    assert!(estimate.confidence >= 0.99);
+    assert!(total <= 7 * estimate.probes);
+
+    // A predicate that's never wrong needs only four votes at each probe.
+    let (estimate, total) =
+      majority_search(0_u32, 1024, 7, 0.99, 100, |x| {
+        if x < 300 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!((estimate.low, estimate.high), (299, 300));
+    assert_eq!(total, 4 * estimate.probes);
+  }
+}
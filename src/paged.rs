@@ -0,0 +1,118 @@
+//! Two-level searches over paged data, such as sorted files in remote
+//! storage, that fetch as few pages as possible.
+//!
+//! The page summaries are searched first, without fetching anything, and
+//! then only the page that must contain the answer is fetched.
+
+use crate::partition_point;
+
+/// A sorted sequence of records split into pages.
+///
+/// Summaries are expected to be cheap, typically held in memory, while
+/// fetching a page may be expensive.
+pub trait PagedSource {
+  type Key: Ord;
+  type Record;
+  type Error;
+
+  fn page_count(&self) -> usize;
+
+  /// The smallest and largest keys on `page`.
+  fn page_summary(&self, page: usize) -> (Self::Key, Self::Key);
+
+  fn fetch_page(
+      &mut self,
+      page: usize,
+    ) -> Result<Vec<Self::Record>, Self::Error>;
+
+  fn key(&self, record: &Self::Record) -> Self::Key;
+}
+
+/// The first record at or after a key, and where it was found.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PagedHit<T> {
+  pub page: usize,
+  pub index: usize,
+  pub record: T,
+}
+
+/// Find the first record whose key is at least `key`, fetching at most one
+/// page. Returns `None` if every key is smaller.
+pub fn lower_bound<S>(
+    source: &mut S,
+    key: &S::Key,
+  ) -> Result<Option<PagedHit<S::Record>>, S::Error>
+  where
+    S: PagedSource {
+  let count = source.page_count();
+  let page = partition_point(count, |page| source.page_summary(page).1 < *key);
+  if page == count {
+    return Ok(None);
+  }
+  let (min, _) = source.page_summary(page);
+  let mut records = source.fetch_page(page)?;
+  let index =
+    if *key <= min {
+      0
+    } else {
+      partition_point(records.len(), |i| {
+        records.get(i).is_some_and(|record| source.key(record) < *key)
+      })
+    };
+  if index < records.len() {
+    Ok(Some(PagedHit { page, index, record: records.swap_remove(index) }))
+  } else {
+    Ok(None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Pages {
+    pages: Vec<Vec<u32>>,
+    fetches: usize,
+  }
+
+  impl PagedSource for Pages {
+    type Key = u32;
+    type Record = u32;
+    type Error = ();
+
+    fn page_count(&self) -> usize {
+      self.pages.len()
+    }
+
+    fn page_summary(&self, page: usize) -> (u32, u32) {
+      let records = &self.pages[page];
+      (records[0], records[records.len() - 1])
+    }
+
+    fn fetch_page(&mut self, page: usize) -> Result<Vec<u32>, ()> {
+      self.fetches += 1;
+      Ok(self.pages[page].clone())
+    }
+
+    fn key(&self, record: &u32) -> u32 {
+      *record
+    }
+  }
+
+  #[test]
+  fn lower_bound_fetches_one_page() {
+    let mut source =
+      Pages {
+        pages: vec![vec![1, 3, 5], vec![8, 9], vec![10, 20, 30, 40]],
+        fetches: 0,
+      };
+    let expected =
+      [(0, 0, 0, 1), (4, 0, 2, 5), (6, 1, 0, 8), (9, 1, 1, 9), (25, 2, 2, 30)];
+    for &(key, page, index, record) in expected.iter() {
+      let hit = lower_bound(&mut source, &key).unwrap();
+      assert_eq!(hit, Some(PagedHit { page, index, record }));
+    }
+    assert_eq!(lower_bound(&mut source, &41).unwrap(), None);
+    assert_eq!(source.fetches, expected.len());
+  }
+}
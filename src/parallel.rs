@@ -0,0 +1,66 @@
+//! Searches that make several probes at once, for predicates that are
+//! expensive but can run in parallel, such as builds or benchmarks on a
+//! pool of machines.
+
+use rayon::prelude::*;
+
+use crate::{subdivide, Betweenable, Bracket, Direction};
+
+/// Like `binary_search`, but each round probes up to `probes` points at
+/// once, on rayon's thread pool, and narrows the bracket to the gap
+/// between them where the predicate changes.
+///
+/// With `probes` one less than a power of two, the points of a round divide
+/// the bracket evenly, so it takes `log(n) / log(probes + 1)` rounds rather
+/// than `log2(n)`, at the cost of more probes in all. Probes above the
+/// first that's high are made redundant by it, and their results discarded.
+pub fn binary_search_parallel<X, A, B, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    probes: usize,
+    f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable + Send + Sync,
+    A: Send,
+    B: Send,
+    F: Fn(X) -> Direction<A, B> + Sync {
+  loop {
+    let points = subdivide(low.0, &[], high.0, probes.max(1));
+    if points.is_empty() {
+      return (low, high);
+    }
+    let directions: Vec<Direction<A, B>> =
+      points.par_iter().map(|&x| (f)(x)).collect();
+    for (x, direction) in points.into_iter().zip(directions) {
+      match direction {
+        Direction::Low(a) => {
+          low = (x, a);
+        },
+        Direction::High(b) => {
+          high = (x, b);
+          break;
+        },
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  #[test]
+  fn takes_fewer_rounds() {
+    let calls = AtomicUsize::new(0);
+    let bracket =
+      binary_search_parallel((0_u32, ()), (4096, ()), 7, |x| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        if x < 1234 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(bracket, ((1233, ()), (1234, ())));
+    // Each round narrows the bracket eightfold, so four rounds suffice.
+    assert_eq!(calls.into_inner(), 4 * 7);
+  }
+}
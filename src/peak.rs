@@ -0,0 +1,86 @@
+//! Finding a local maximum of a grid without looking at all of it.
+
+use crate::{binary_search, Direction};
+
+/// Find a local maximum of `grid`, an element at least as large as each of
+/// its neighbours above, below, left and right, as `(row, column)`.
+///
+/// Every row must be the same length. This bisects the columns, finding
+/// the largest element of each column it probes and heading towards a
+/// larger neighbour, so it takes `O(rows * log columns)` comparisons.
+/// Returns `None` only for an empty grid.
+pub fn peak_2d<T, R>(grid: &[R]) -> Option<(usize, usize)>
+  where
+    T: Ord,
+    R: AsRef<[T]> {
+  let columns = grid.first()?.as_ref().len();
+  let last = columns.checked_sub(1)?;
+  let at = |row: usize, column: usize| {
+    grid.get(row).and_then(|r| r.as_ref().get(column))
+  };
+  // Low if the largest element of the column is smaller than the element
+  // to its right, and otherwise High, with the row of that element.
+  let probe = |column: usize| {
+    let row =
+      (0..grid.len())
+        .max_by_key(|&row| at(row, column))
+        .unwrap_or(0);
+    let right = column.checked_add(1).and_then(|c| at(row, c));
+    if right > at(row, column) {
+      Direction::Low(())
+    } else {
+      Direction::High(row)
+    }
+  };
+  // Between a Low column and the High column after it, the High column's
+  // largest element is larger than everything in the Low column, so it's a
+  // peak. Off either side of the grid counts as Low on the left and High
+  // on the right.
+  let (column, row) =
+    match (probe)(0) {
+      Direction::High(row) => (0, row),
+      Direction::Low(()) => {
+        let row =
+          match (probe)(last) {
+            Direction::High(row) => row,
+            Direction::Low(()) => 0,
+          };
+        let (_, (column, row)) = binary_search((0, ()), (last, row), probe);
+        (column, row)
+      },
+    };
+  Some((row, column))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn is_peak(grid: &[Vec<i32>], (row, column): (usize, usize)) -> bool {
+    let x = grid[row][column];
+    let neighbours = [
+      row.checked_sub(1).map(|r| grid[r][column]),
+      grid.get(row + 1).map(|r| r[column]),
+      column.checked_sub(1).map(|c| grid[row][c]),
+      grid[row].get(column + 1).copied(),
+    ];
+    neighbours.iter().flatten().all(|&n| n <= x)
+  }
+
+  #[test]
+  fn finds_a_peak() {
+    let grids = [
+      vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]],
+      vec![vec![9, 8, 7], vec![6, 5, 4]],
+      vec![vec![1, 9, 1, 1, 1], vec![1, 1, 1, 8, 1], vec![1, 1, 1, 1, 1]],
+      vec![vec![3]],
+      vec![vec![2, 2, 2, 2]],
+    ];
+    for grid in grids.iter() {
+      let peak = peak_2d(grid).unwrap();
+      assert!(is_peak(grid, peak), "{:?} in {:?}", peak, grid);
+    }
+    assert_eq!(peak_2d::<i32, Vec<i32>>(&[]), None);
+    assert_eq!(peak_2d::<i32, Vec<i32>>(&[vec![]]), None);
+  }
+}
@@ -0,0 +1,83 @@
+//! Monotone piecewise-linear functions given by their breakpoints, such as
+//! calibration curves and tone maps.
+//!
+//! A function is a list of `(x, y)` breakpoints sorted by `x`, whose `y`s
+//! are either non-decreasing or non-increasing, joined by straight lines.
+//! Beyond the first and last breakpoints the function is flat.
+
+use crate::partition_point;
+
+/// The value the same fraction of the way from `a` to `b` as `t` is from
+/// `t0` to `t1`.
+fn lerp(a: f64, b: f64, t0: f64, t1: f64, t: f64) -> f64 {
+  a + (b - a) * ((t - t0) / (t1 - t0))
+}
+
+/// Evaluate the function at `x`, or `None` if it has no breakpoints.
+pub fn evaluate(breakpoints: &[(f64, f64)], x: f64) -> Option<f64> {
+  let index =
+    partition_point(breakpoints.len(), |i| {
+      breakpoints.get(i).is_some_and(|&(xi, _)| xi <= x)
+    });
+  let after = breakpoints.get(index);
+  match (index.checked_sub(1).and_then(|i| breakpoints.get(i)), after) {
+    (Some(&(x0, y0)), Some(&(x1, y1))) => Some(lerp(y0, y1, x0, x1, x)),
+    (Some(&(_, y)), None) | (None, Some(&(_, y))) => Some(y),
+    (None, None) => None,
+  }
+}
+
+/// Find the smallest `x` at which the function reaches `y`, or `None` if it
+/// has no breakpoints.
+///
+/// A `y` beyond the range of the function gives the `x` of the breakpoint
+/// closest to it.
+pub fn invert(breakpoints: &[(f64, f64)], y: f64) -> Option<f64> {
+  let (first, last) = (breakpoints.first()?, breakpoints.last()?);
+  let increasing = first.1 <= last.1;
+  let index =
+    partition_point(breakpoints.len(), |i| {
+      breakpoints.get(i).is_some_and(|&(_, yi)| {
+        if increasing { yi < y } else { yi > y }
+      })
+    });
+  let after = breakpoints.get(index);
+  match (index.checked_sub(1).and_then(|i| breakpoints.get(i)), after) {
+    (Some(&(x0, y0)), Some(&(x1, y1))) => Some(lerp(x0, x1, y0, y1, y)),
+    (Some(&(x, _)), None) | (None, Some(&(x, _))) => Some(x),
+    (None, None) => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn evaluates_and_inverts() {
+    let curve = [(0.0, 0.0), (1.0, 10.0), (2.0, 10.0), (4.0, 30.0)];
+    assert_eq!(evaluate(&curve, -1.0), Some(0.0));
+    assert_eq!(evaluate(&curve, 0.5), Some(5.0));
+    assert_eq!(evaluate(&curve, 1.5), Some(10.0));
+    assert_eq!(evaluate(&curve, 3.0), Some(20.0));
+    assert_eq!(evaluate(&curve, 9.0), Some(30.0));
+    assert_eq!(invert(&curve, -5.0), Some(0.0));
+    assert_eq!(invert(&curve, 5.0), Some(0.5));
+    assert_eq!(invert(&curve, 10.0), Some(1.0));
+    assert_eq!(invert(&curve, 20.0), Some(3.0));
+    assert_eq!(invert(&curve, 50.0), Some(4.0));
+
+    let falling = [(0.0, 1.0), (10.0, 0.5), (20.0, 0.0)];
+    assert_eq!(evaluate(&falling, 15.0), Some(0.25));
+    assert_eq!(invert(&falling, 0.75), Some(5.0));
+    assert_eq!(invert(&falling, 0.25), Some(15.0));
+    assert_eq!(invert(&falling, 2.0), Some(0.0));
+
+    // A jump, where two breakpoints share an `x`.
+    let step = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (2.0, 1.0)];
+    assert_eq!(evaluate(&step, 1.0), Some(1.0));
+    assert_eq!(invert(&step, 0.5), Some(1.0));
+    assert_eq!(evaluate(&[], 1.0), None);
+    assert_eq!(invert(&[(3.0, 7.0)], 1.0), Some(3.0));
+  }
+}
@@ -0,0 +1,170 @@
+//! Estimating what a search will cost before running it, for when each
+//! probe is expensive enough to budget for.
+//!
+//! Plans are worked out from the width of the search, the number of values
+//! the transition could be at, which is `high - low`. They count the calls
+//! made to the predicate, which for batched searches, as in `chunked`, each
+//! decide several probes. `schedule::probe_schedule` lists the probes
+//! themselves.
+
+use std::collections::BTreeMap;
+
+/// How a planned search will be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlanOptions {
+  /// The number of candidates decided by each call, rounded down to one
+  /// less than a power of two as `chunked::binary_search` does. One is a
+  /// plain bisection.
+  pub batch: usize,
+  /// The most calls the search may make.
+  pub max_calls: Option<usize>,
+}
+
+impl Default for PlanOptions {
+  fn default() -> Self {
+    PlanOptions { batch: 1, max_calls: None }
+  }
+}
+
+/// What a search will cost, in the worst case and on average over every
+/// place the transition could be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plan {
+  pub worst_case_calls: usize,
+  pub worst_case_probes: u128,
+  pub expected_calls: f64,
+  pub expected_probes: f64,
+  /// The widest the bracket can be when the search finishes, which is one
+  /// unless the calls run out first.
+  pub final_width: u128,
+}
+
+/// The probes made by one call narrowing a bracket of `width` by `levels`
+/// rounds of bisection, and the widths of the brackets it could leave, with
+/// how many of each.
+fn round(width: u128, levels: u32) -> (u128, BTreeMap<u128, u128>) {
+  let mut probes = 0;
+  let mut widths = BTreeMap::new();
+  widths.insert(width, 1);
+  for _ in 0..levels {
+    let mut next = BTreeMap::new();
+    for (w, count) in widths {
+      if w >= 2 {
+        probes += count;
+        *next.entry(w / 2).or_insert(0) += count;
+        *next.entry(w - w / 2).or_insert(0) += count;
+      } else {
+        *next.entry(w).or_insert(0) += count;
+      }
+    }
+    widths = next;
+  }
+  (probes, widths)
+}
+
+struct Planner {
+  levels: u32,
+  expected: BTreeMap<(u128, usize), (f64, f64)>,
+}
+
+impl Planner {
+  /// The expected calls and probes to narrow `width` with `calls` left.
+  fn expected(&mut self, width: u128, calls: usize) -> (f64, f64) {
+    if width <= 1 || calls == 0 {
+      return (0.0, 0.0);
+    }
+    if let Some(&cached) = self.expected.get(&(width, calls)) {
+      return cached;
+    }
+    let (probes, widths) = round(width, self.levels);
+    let mut result = (1.0, probes as f64);
+    for (w, count) in widths {
+      let weight = (w as f64) * (count as f64) / (width as f64);
+      let (c, p) = self.expected(w, calls - 1);
+      result.0 += weight * c;
+      result.1 += weight * p;
+    }
+    self.expected.insert((width, calls), result);
+    result
+  }
+}
+
+/// Work out what a search of `width` will cost under `options`, without
+/// probing anything.
+pub fn plan(width: u128, options: PlanOptions) -> Plan {
+  let levels = (options.batch.max(1) as u128 + 1).ilog2();
+  let max_calls = options.max_calls.unwrap_or(usize::MAX);
+  let mut worst_case_calls = 0;
+  let mut worst_case_probes = 0;
+  let mut final_width = width;
+  // The widest bracket left by each call is also the one that takes the
+  // most probes to finish.
+  while final_width > 1 && worst_case_calls < max_calls {
+    let (probes, widths) = round(final_width, levels);
+    worst_case_calls += 1;
+    worst_case_probes += probes;
+    final_width = widths.keys().next_back().copied().unwrap_or(0);
+  }
+  let mut planner = Planner { levels, expected: BTreeMap::new() };
+  let (expected_calls, expected_probes) = planner.expected(width, max_calls);
+  Plan {
+    worst_case_calls,
+    worst_case_probes,
+    expected_calls,
+    expected_probes,
+    final_width,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{binary_search_with_budget, chunked, Direction};
+
+  #[test]
+  fn plans_match_searches() {
+    for &(width, batch) in [(100_u32, 1), (100, 7), (37, 3), (1, 1)].iter() {
+      let options = PlanOptions { batch, max_calls: None };
+      let plan = plan(u128::from(width), options);
+      let (mut worst_calls, mut worst_probes) = (0, 0);
+      let (mut total_calls, mut total_probes) = (0, 0);
+      for t in 1..=width {
+        let (mut calls, mut probes) = (0, 0);
+        let result =
+          chunked::binary_search((0, ()), (width, ()), batch, |xs| {
+            calls += 1;
+            probes += xs.len();
+            xs.iter().map(|&x| {
+              if x < t { Direction::Low(()) } else { Direction::High(()) }
+            }).collect()
+          });
+        assert!(result.is_ok());
+        worst_calls = worst_calls.max(calls);
+        worst_probes = worst_probes.max(probes);
+        total_calls += calls;
+        total_probes += probes;
+      }
+      assert_eq!(plan.worst_case_calls, worst_calls);
+      assert_eq!(plan.worst_case_probes, worst_probes as u128);
+      let n = f64::from(width);
+      assert!((plan.expected_calls - total_calls as f64 / n).abs() < 1e-9);
+      assert!((plan.expected_probes - total_probes as f64 / n).abs() < 1e-9);
+      assert_eq!(plan.final_width, 1);
+    }
+
+    let budget = PlanOptions { batch: 1, max_calls: Some(3) };
+    let plan = plan(100, budget);
+    assert_eq!((plan.worst_case_calls, plan.final_width), (3, 13));
+    let widest =
+      (1..=100).map(|t| {
+        let f = |x| {
+          if x < t { Direction::Low(()) } else { Direction::High(()) }
+        };
+        let (low, high) = ((0_u32, ()), (100, ()));
+        let ((low, ()), (high, ())) =
+          binary_search_with_budget(low, high, Some(3), || false, f).bracket();
+        high - low
+      }).max();
+    assert_eq!(widest, Some(13));
+  }
+}
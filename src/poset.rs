@@ -0,0 +1,142 @@
+//! Experimental: searches over partial orders, such as sets of
+//! configuration flags ordered by inclusion, rather than over a line.
+//!
+//! The predicate must be monotone in the order: everything above a `High`
+//! element is `High`. There may then be many minimal `High` elements
+//! rather than a single transition, and these find one of them.
+
+use std::collections::BTreeSet;
+
+use crate::{binary_search, Bracket, Direction};
+
+/// Like `binary_search`, but over a partial order, with `split(low, high)`
+/// giving an element strictly between `low` and `high` to probe, or `None`
+/// if there isn't one.
+///
+/// Returns a `Low` element and a `High` element above it that `split`
+/// can't go between: a maximal `Low` and a minimal `High` element of the
+/// chain the search followed. Whether they're maximal and minimal in the
+/// whole order depends on `split`.
+pub fn binary_search_poset<P, A, B, S, F>(
+    mut low: (P, A),
+    mut high: (P, B),
+    mut split: S,
+    mut f: F,
+  ) -> Bracket<P, A, B>
+  where
+    S: FnMut(&P, &P) -> Option<P>,
+    F: FnMut(&P) -> Direction<A, B> {
+  while let Some(x) = (split)(&low.0, &high.0) {
+    match (f)(&x) {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  (low, high)
+}
+
+/// A `split` for `binary_search_poset` over sets, adding half of the
+/// elements `high` has that `low` doesn't.
+pub fn split_sets<T>(
+    low: &BTreeSet<T>,
+    high: &BTreeSet<T>,
+  ) -> Option<BTreeSet<T>>
+  where
+    T: Ord + Clone {
+  let extra: Vec<&T> = high.difference(low).collect();
+  if extra.len() < 2 {
+    return None;
+  }
+  let half = extra.len() / 2;
+  Some(low.iter().chain(extra.into_iter().take(half)).cloned().collect())
+}
+
+/// Find a minimal `High` set between `low`, which is `Low`, and `high`,
+/// which is `High`: one from which removing any element not in `low` makes
+/// it `Low`.
+///
+/// Each element of the result beyond `low` is found by bisecting the
+/// elements still in question, so this takes `O(k log n)` probes for a
+/// result with `k` more elements than `low`, where `high` has `n` more.
+pub fn minimal_high_set<T, F>(
+    low: &BTreeSet<T>,
+    high: &BTreeSet<T>,
+    mut f: F,
+  ) -> BTreeSet<T>
+  where
+    T: Ord + Clone,
+    F: FnMut(&BTreeSet<T>) -> Direction<(), ()> {
+  // `required` is always `Low` and `required` with all of `rest` `High`.
+  // Each round finds the shortest prefix of `rest` that makes `required`
+  // `High`; its last element is needed with the elements before it, and
+  // the elements after it aren't needed at all.
+  let mut required = low.clone();
+  let mut rest: Vec<T> = high.difference(low).cloned().collect();
+  loop {
+    let with_prefix = |required: &BTreeSet<T>, n: usize| {
+      let mut set = required.clone();
+      set.extend(rest.iter().take(n).cloned());
+      set
+    };
+    let (_, (n, ())) =
+      binary_search((0, ()), (rest.len(), ()), |n| {
+        (f)(&with_prefix(&required, n))
+      });
+    rest.truncate(n);
+    match rest.pop() {
+      Some(x) => required.insert(x),
+      None => return required,
+    };
+    if rest.is_empty() || matches!((f)(&required), Direction::High(())) {
+      return required;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_minimal_high_sets() {
+    // Fails when flags 3 and 7 are both on, or 9 is.
+    let fails = |flags: &BTreeSet<u32>| {
+      if flags.contains(&9) || (flags.contains(&3) && flags.contains(&7)) {
+        Direction::High(())
+      } else {
+        Direction::Low(())
+      }
+    };
+    let none = BTreeSet::new();
+    let all: BTreeSet<u32> = (0..16).collect();
+    let mut probes = 0;
+    let minimal =
+      minimal_high_set(&none, &all, |flags| {
+        probes += 1;
+        fails(flags)
+      });
+    assert_eq!(minimal, [3, 7].iter().copied().collect());
+    assert!(probes <= 10, "{} probes", probes);
+    let without_3: BTreeSet<u32> =
+      all.iter().copied().filter(|&x| x != 3).collect();
+    assert_eq!(
+      minimal_high_set(&none, &without_3, fails),
+      [9].iter().copied().collect(),
+    );
+    let with_7 = [7].iter().copied().collect();
+    assert_eq!(
+      minimal_high_set(&with_7, &all, fails),
+      [3, 7].iter().copied().collect(),
+    );
+
+    let ((low, ()), (high, ())) =
+      binary_search_poset((none, ()), (all, ()), split_sets, fails);
+    assert_eq!(high.difference(&low).count(), 1);
+    assert_eq!(fails(&low), Direction::Low(()));
+    assert_eq!(fails(&high), Direction::High(()));
+  }
+}
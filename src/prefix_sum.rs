@@ -0,0 +1,434 @@
+//! Searches over running sums, for weighted selection and bucket routing.
+
+#[cfg(feature = "rand")]
+use std::cmp::Ordering;
+use std::ops::{Add, Sub};
+
+use crate::partition_point;
+
+/// Find the first index whose running sum exceeds `target`.
+///
+/// `cumsum` must be a non-decreasing table of prefix sums, so that
+/// `cumsum[i]` is the total weight of buckets `0..=i`. Returns `None` when
+/// even the total doesn't exceed `target`.
+pub fn find_by_cumsum<T>(cumsum: &[T], target: &T) -> Option<usize>
+  where
+    T: PartialOrd {
+  find_by_cumulative(
+    cumsum.len(),
+    |i| cumsum.get(i),
+    |c| c.is_none_or(|c| c > target),
+  )
+}
+
+/// Like `find_by_cumsum`, but the running sum at index `i` is produced by
+/// `cumulative(i)` for `i` in `0..len`, and `exceeds` decides whether it is
+/// past the target. `exceeds` must be monotone in `i`.
+pub fn find_by_cumulative<T, C, E>(
+    len: usize,
+    mut cumulative: C,
+    mut exceeds: E,
+  ) -> Option<usize>
+  where
+    C: FnMut(usize) -> T,
+    E: FnMut(T) -> bool {
+  let index = partition_point(len, |i| !(exceeds)((cumulative)(i)));
+  if index < len {
+    Some(index)
+  } else {
+    None
+  }
+}
+
+/// A Fenwick tree (binary indexed tree) of prefix sums.
+///
+/// Node `i` (one-based) holds the sum of the `i & -i` weights ending at
+/// weight `i`, stored at offset `i - 1`.
+#[derive(Debug, Clone)]
+pub struct Fenwick<T> {
+  tree: Vec<T>,
+}
+
+impl<T> Fenwick<T>
+  where
+    T: Copy + Default + PartialOrd + Add<Output=T> + Sub<Output=T> {
+  /// Build a tree from a slice of weights in linear time.
+  pub fn from_weights(weights: &[T]) -> Self {
+    let mut tree = weights.to_vec();
+    for i in 1..=tree.len() {
+      let parent = i + (i & i.wrapping_neg());
+      let value = tree.get(i - 1).copied();
+      if let (Some(value), Some(node)) = (value, tree.get_mut(parent - 1)) {
+        *node = *node + value;
+      }
+    }
+    Fenwick { tree }
+  }
+
+  pub fn len(&self) -> usize {
+    self.tree.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.tree.is_empty()
+  }
+
+  /// The raw nodes, in the layout expected by `fenwick_descend`.
+  pub fn as_slice(&self) -> &[T] {
+    &self.tree
+  }
+
+  /// Add `delta` to the weight at `index`. Out of range indices are ignored.
+  pub fn add(&mut self, index: usize, delta: T) {
+    if index >= self.tree.len() {
+      return;
+    }
+    let mut i = index + 1;
+    while let Some(node) = self.tree.get_mut(i - 1) {
+      *node = *node + delta;
+      i += i & i.wrapping_neg();
+    }
+  }
+
+  /// The sum of the first `end` weights.
+  pub fn prefix_sum(&self, end: usize) -> T {
+    let mut i = end.min(self.tree.len());
+    let mut sum = T::default();
+    while i > 0 {
+      if let Some(&node) = self.tree.get(i - 1) {
+        sum = sum + node;
+      }
+      i &= i - 1;
+    }
+    sum
+  }
+
+  /// Find the smallest index whose running sum is at least `k`.
+  pub fn lower_bound(&self, k: T) -> Option<usize> {
+    fenwick_descend(&self.tree, k)
+  }
+}
+
+/// Find the smallest index whose running sum is at least `k` in a Fenwick
+/// tree with the layout used by `Fenwick`, with non-negative weights.
+///
+/// This descends the implicit tree from the root, halving the step at each
+/// level, so it takes `O(log n)` steps rather than the `O(log² n)` of
+/// bisecting over `prefix_sum`. Returns `None` if the total is less than
+/// `k`.
+pub fn fenwick_descend<T>(tree: &[T], k: T) -> Option<usize>
+  where
+    T: Copy + PartialOrd + Sub<Output=T> {
+  let mut position = 0;
+  let mut remaining = k;
+  let mut step = match tree.len() {
+    0 => return None,
+    n => 1 << (usize::BITS - 1 - n.leading_zeros()),
+  };
+  while step > 0 {
+    if let Some(&node) = tree.get(position + step - 1) {
+      if node < remaining {
+        position += step;
+        remaining = remaining - node;
+      }
+    }
+    step >>= 1;
+  }
+  if position < tree.len() {
+    Some(position)
+  } else {
+    None
+  }
+}
+
+/// A discrete distribution that samples indices in proportion to their
+/// weights by bisecting a cumulative-weight table.
+///
+/// The table is split into blocks of about `√n` weights, each with its own
+/// running sums, and a running sum of the block totals. `set_weight` is
+/// `O(1)`, marking only its block as changed, and the next sample rebuilds
+/// just the changed blocks and the block totals, in `O(√n)` each, rather
+/// than the whole table. Sampling bisects the block totals and then one
+/// block, in `O(log n)`.
+///
+/// `sample_at` takes the random draw from the caller, so any source of
+/// randomness can be used. With the `rand` feature, `sample` draws from an
+/// `Rng`.
+#[derive(Debug, Clone)]
+pub struct WeightedCdf<T> {
+  weights: Vec<T>,
+  block_len: usize,
+  /// The running sums of the weights within each block.
+  within: Vec<T>,
+  /// The running sums of the block totals.
+  blocks: Vec<T>,
+  /// The blocks whose weights changed since `within` was last rebuilt.
+  stale: Vec<bool>,
+  any_stale: bool,
+}
+
+impl<T> WeightedCdf<T>
+  where
+    T: Copy + Default + PartialOrd + Add<Output=T> {
+  pub fn new(weights: Vec<T>) -> Self {
+    let block_len = weights.len().isqrt().max(1);
+    let block_count = weights.len().div_ceil(block_len);
+    WeightedCdf {
+      within: vec![T::default(); weights.len()],
+      blocks: vec![T::default(); block_count],
+      stale: vec![true; block_count],
+      any_stale: true,
+      block_len,
+      weights,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.weights.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.weights.is_empty()
+  }
+
+  pub fn weight(&self, index: usize) -> Option<T> {
+    self.weights.get(index).copied()
+  }
+
+  /// Replace the weight at `index`, returning the old weight, or `None` if
+  /// `index` is out of range.
+  pub fn set_weight(&mut self, index: usize, weight: T) -> Option<T> {
+    let slot = self.weights.get_mut(index)?;
+    let old = std::mem::replace(slot, weight);
+    if let Some(stale) = self.stale.get_mut(index / self.block_len) {
+      *stale = true;
+      self.any_stale = true;
+    }
+    Some(old)
+  }
+
+  pub fn total(&mut self) -> T {
+    self.refresh();
+    self.blocks.last().copied().unwrap_or_default()
+  }
+
+  /// Find the index selected by `point`, which should be drawn uniformly
+  /// from `[0, total)`. Returns `None` if `point` is at or past the total.
+  pub fn sample_at(&mut self, point: T) -> Option<usize> {
+    self.refresh();
+    let block = find_by_cumsum(&self.blocks, &point)?;
+    let before =
+      match block.checked_sub(1) {
+        Some(previous) => self.blocks.get(previous).copied()?,
+        None => T::default(),
+      };
+    let within = self.within.chunks(self.block_len).nth(block)?;
+    let offset =
+      find_by_cumulative(
+        within.len(),
+        |i| within.get(i).map(|&sum| before + sum),
+        |c| c.is_none_or(|c| c > point),
+      )?;
+    Some(block * self.block_len + offset)
+  }
+
+  /// Draw an index at random from `rng`, or `None` if the total weight
+  /// isn't positive.
+  #[cfg(feature = "rand")]
+  pub fn sample<R>(&mut self, rng: &mut R) -> Option<usize>
+    where
+      T: rand::distr::uniform::SampleUniform,
+      R: rand::Rng + ?Sized {
+    let total = self.total();
+    if T::default().partial_cmp(&total) != Some(Ordering::Less) {
+      return None;
+    }
+    self.sample_at(rng.random_range(T::default()..total))
+  }
+
+  /// Rebuild the running sums of the changed blocks, and the block totals.
+  fn refresh(&mut self) {
+    if !self.any_stale {
+      return;
+    }
+    let mut total = T::default();
+    let blocks =
+      self.weights.chunks(self.block_len)
+        .zip(self.within.chunks_mut(self.block_len))
+        .zip(self.stale.iter_mut())
+        .zip(self.blocks.iter_mut());
+    for (((weights, within), stale), block_total) in blocks {
+      if *stale {
+        let mut sum = T::default();
+        for (&weight, running) in weights.iter().zip(within.iter_mut()) {
+          sum = sum + weight;
+          *running = sum;
+        }
+        *stale = false;
+      }
+      total = total + within.last().copied().unwrap_or_default();
+      *block_total = total;
+    }
+    self.any_stale = false;
+  }
+}
+
+impl WeightedCdf<f64> {
+  /// Sample using `u`, drawn uniformly from `[0, 1)`.
+  pub fn sample_unit(&mut self, u: f64) -> Option<usize> {
+    let total = self.total();
+    self.sample_at(u * total)
+  }
+}
+
+/// The group holding a quantile of grouped counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupRank<'a, B> {
+  /// The index of the group.
+  pub group: usize,
+  /// The group's boundary.
+  pub bound: &'a B,
+  /// The zero-based rank of the quantile among the group's items.
+  pub rank: u64,
+}
+
+/// Find the group holding the `q` quantile of items that have been counted
+/// into groups, such as the buckets of a latency histogram.
+///
+/// `counts[i]` is the number of items in the group with boundary
+/// `bounds[i]`, and the groups are in order. The quantile is the item with
+/// the smallest rank covering `q` of the total, so `q = 0.5` is the median
+/// and `q = 1.0` the last item. Returns `None` if the slices differ in
+/// length, there are no items, or `q` isn't in `[0, 1]`.
+pub fn quantile_of_groups<'a, B>(
+    bounds: &'a [B],
+    counts: &[u64],
+    q: f64,
+  ) -> Option<GroupRank<'a, B>> {
+  if bounds.len() != counts.len() || !(0.0..=1.0).contains(&q) {
+    return None;
+  }
+  let cumsum: Vec<u64> =
+    counts.iter().scan(0_u64, |sum, &count| {
+      *sum = sum.saturating_add(count);
+      Some(*sum)
+    }).collect();
+  let total = cumsum.last().copied().filter(|&total| total > 0)?;
+  // The rank is chosen by the nearest-rank method, counting from zero.
+  let rank = ((q * total as f64).ceil() as u64).clamp(1, total) - 1;
+  let group = find_by_cumsum(&cumsum, &rank)?;
+  let before =
+    group.checked_sub(1).and_then(|i| cumsum.get(i)).copied().unwrap_or(0);
+  Some(GroupRank { group, bound: bounds.get(group)?, rank: rank - before })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn find_by_cumsum_test() {
+    let cumsum = [2, 5, 5, 9];
+    assert_eq!(find_by_cumsum(&cumsum, &0), Some(0));
+    assert_eq!(find_by_cumsum(&cumsum, &2), Some(1));
+    assert_eq!(find_by_cumsum(&cumsum, &5), Some(3));
+    assert_eq!(find_by_cumsum(&cumsum, &8), Some(3));
+    assert_eq!(find_by_cumsum(&cumsum, &9), None);
+    assert_eq!(find_by_cumsum(&[] as &[u32], &0), None);
+  }
+
+  #[test]
+  fn find_by_cumulative_test() {
+    let index = find_by_cumulative(1000, |i| i * i, |c| c > 5000);
+    assert_eq!(index, Some(71));
+  }
+
+  #[test]
+  fn fenwick_test() {
+    let weights = [3, 0, 1, 4, 1, 5, 9, 2, 6];
+    let mut fenwick = Fenwick::from_weights(&weights);
+    for end in 0..=weights.len() {
+      assert_eq!(fenwick.prefix_sum(end), weights[..end].iter().sum::<i32>());
+    }
+    for k in 0..33 {
+      let expected =
+        (0..weights.len())
+          .find(|&i| weights[..=i].iter().sum::<i32>() >= k);
+      assert_eq!(fenwick.lower_bound(k), expected);
+    }
+    fenwick.add(1, 10);
+    assert_eq!(fenwick.prefix_sum(2), 13);
+    assert_eq!(fenwick.lower_bound(4), Some(1));
+    let total = fenwick.prefix_sum(usize::MAX);
+    fenwick.add(weights.len(), 1);
+    fenwick.add(usize::MAX, 1);
+    assert_eq!(fenwick.prefix_sum(usize::MAX), total);
+  }
+
+  #[test]
+  fn weighted_cdf_test() {
+    let mut cdf = WeightedCdf::new(vec![1.0, 0.0, 3.0]);
+    assert_eq!(cdf.total(), 4.0);
+    assert_eq!(cdf.sample_unit(0.0), Some(0));
+    assert_eq!(cdf.sample_unit(0.3), Some(2));
+    assert_eq!(cdf.set_weight(1, 4.0), Some(0.0));
+    assert_eq!(cdf.sample_unit(0.3), Some(1));
+    assert_eq!(cdf.sample_unit(0.7), Some(2));
+    assert_eq!(cdf.sample_at(8.0), None);
+
+    // Several blocks, changed one weight at a time.
+    let mut weights: Vec<u32> = (0..50).map(|i| i % 7).collect();
+    let mut cdf = WeightedCdf::new(weights.clone());
+    for step in 0..20 {
+      let index = step * 13 % weights.len();
+      weights[index] = step as u32;
+      cdf.set_weight(index, step as u32);
+      let total: u32 = weights.iter().sum();
+      assert_eq!(cdf.total(), total);
+      for point in 0..total {
+        let expected =
+          (0..weights.len())
+            .find(|&i| weights[..=i].iter().sum::<u32>() > point);
+        assert_eq!(cdf.sample_at(point), expected);
+      }
+      assert_eq!(cdf.sample_at(total), None);
+    }
+  }
+
+  #[cfg(feature = "rand")]
+  #[test]
+  fn weighted_cdf_samples_from_rng() {
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut cdf = WeightedCdf::new(vec![1_u32, 0, 3]);
+    let mut counts = [0; 3];
+    for _ in 0..4000 {
+      if let Some(i) = cdf.sample(&mut rng) {
+        counts[i] += 1;
+      }
+    }
+    assert_eq!(counts[1], 0);
+    assert!((2700..3300).contains(&counts[2]), "{:?}", counts);
+    assert_eq!(WeightedCdf::new(vec![0.0; 3]).sample(&mut rng), None);
+  }
+
+  #[test]
+  fn quantile_of_groups_test() {
+    let bounds = ["10ms", "100ms", "1s", "10s"];
+    let counts = [50, 0, 45, 5];
+    let quantile = |q| {
+      quantile_of_groups(&bounds, &counts, q)
+        .map(|g| (g.group, *g.bound, g.rank))
+    };
+    assert_eq!(quantile(0.0), Some((0, "10ms", 0)));
+    assert_eq!(quantile(0.5), Some((0, "10ms", 49)));
+    assert_eq!(quantile(0.51), Some((2, "1s", 0)));
+    assert_eq!(quantile(0.95), Some((2, "1s", 44)));
+    assert_eq!(quantile(0.99), Some((3, "10s", 3)));
+    assert_eq!(quantile(1.0), Some((3, "10s", 4)));
+    assert_eq!(quantile(1.5), None);
+    assert_eq!(quantile_of_groups(&bounds, &[0; 4], 0.5), None);
+    assert_eq!(quantile_of_groups(&bounds, &[1], 0.5), None);
+  }
+}
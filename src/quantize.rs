@@ -0,0 +1,182 @@
+//! Searches that only need the transition to the nearest point of a grid,
+//! such as to the nearest 10 ms, or the nearest power of two.
+//!
+//! Only grid points are probed, so the search stops as soon as the
+//! transition is known to lie between two adjacent points, without the
+//! probes that would narrow it further.
+
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+
+use crate::{binary_search_in, Bracket, Direction, SearchSpace};
+
+/// The points that results are rounded to.
+pub trait Grid<X> {
+  /// The greatest grid point not greater than `x`.
+  fn floor(&self, x: X) -> X;
+
+  /// The least grid point not less than `x`.
+  fn ceil(&self, x: X) -> X;
+
+  /// A grid point strictly between `low` and `high`, near the middle of the
+  /// grid points between them, or `None` if there isn't one.
+  fn between(&self, low: X, high: X) -> Option<X>;
+}
+
+/// The multiples of a step, which must be positive. Rounding saturates at
+/// the bounds of the type. A step that isn't positive has no points between
+/// any two values, so a search with it probes nothing and rounds nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Multiples<X>(pub X);
+
+/// Zero and the powers of two. Rounding up saturates at the greatest value
+/// of the type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PowersOfTwo;
+
+macro_rules! impl_multiples {
+  ($($t:ty),*) => {
+    $(
+      impl Grid<$t> for Multiples<$t> {
+        fn floor(&self, x: $t) -> $t {
+          match x.checked_rem_euclid(self.0) {
+            Some(rem) => x.checked_sub(rem).unwrap_or(<$t>::MIN),
+            None => x,
+          }
+        }
+
+        fn ceil(&self, x: $t) -> $t {
+          let floor = self.floor(x);
+          if floor < x { floor.saturating_add(self.0) } else { floor }
+        }
+
+        fn between(&self, low: $t, high: $t) -> Option<$t> {
+          // The indices of the grid points, which don't overflow in `i128`.
+          let step = Some(self.0 as i128).filter(|&step| step > 0)?;
+          let first = (low as i128).div_euclid(step) + 1;
+          let last = (high as i128 - 1).div_euclid(step);
+          if first > last {
+            return None;
+          }
+          <$t>::try_from((first + (last - first) / 2) * step).ok()
+        }
+      }
+    )*
+  };
+}
+
+impl_multiples!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+macro_rules! impl_powers_of_two {
+  ($($t:ty),*) => {
+    $(
+      impl Grid<$t> for PowersOfTwo {
+        fn floor(&self, x: $t) -> $t {
+          x.checked_ilog2().map_or(0, |k| 1 << k)
+        }
+
+        fn ceil(&self, x: $t) -> $t {
+          x.checked_next_power_of_two().unwrap_or(<$t>::MAX)
+        }
+
+        fn between(&self, low: $t, high: $t) -> Option<$t> {
+          // Zero is index 0, and `2^k` is index `k + 1`.
+          let index = |x: $t| x.checked_ilog2().map_or(0, |k| k + 1);
+          let first = index(low) + 1;
+          let last =
+            index(high).checked_sub(u32::from(high.is_power_of_two()))?;
+          let k = first + (last.checked_sub(first)? / 2);
+          k.checked_sub(1).map(|e| 1 << e)
+        }
+      }
+    )*
+  };
+}
+
+impl_powers_of_two!(u8, u16, u32, u64, u128, usize);
+
+/// A grid as a search space.
+struct Points<'a, G, X>(&'a G, PhantomData<X>);
+
+impl<'a, X, G> SearchSpace for Points<'a, G, X>
+  where
+    X: Copy,
+    G: Grid<X> {
+  type Position = X;
+
+  fn candidate(&mut self, &low: &X, &high: &X) -> Option<X> {
+    self.0.between(low, high)
+  }
+}
+
+/// Like `binary_search`, but only probing points of `grid`, and rounding
+/// the bracket found outward to it.
+///
+/// The ends of the bracket are adjacent points of the grid. Only `low` and
+/// `high` themselves may not be grid points, so if either end is one of
+/// them, it's rounded, keeping its witness.
+pub fn binary_search<X, G, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    grid: &G,
+    f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Copy,
+    G: Grid<X>,
+    F: FnMut(X) -> Direction<A, B> {
+  let ((x_low, a), (x_high, b)) =
+    binary_search_in(&mut Points(grid, PhantomData), low, high, f);
+  ((grid.floor(x_low), a), (grid.ceil(x_high), b))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stops_at_grid_resolution() {
+    let threshold = |t: i64| move |x: i64| {
+      if x < t { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let mut probes = Vec::new();
+    let result =
+      binary_search((0, ()), (10_000, ()), &Multiples(10), |x| {
+        probes.push(x);
+        threshold(777)(x)
+      });
+    assert_eq!(result, ((770, ()), (780, ())));
+    assert!(probes.iter().all(|x| x % 10 == 0));
+    assert!(probes.len() <= 10, "{:?}", probes);
+
+    let search = |t| {
+      binary_search((3, ()), (995, ()), &Multiples(10), threshold(t))
+    };
+    assert_eq!(search(5), ((0, ()), (10, ())));
+    assert_eq!(search(991), ((990, ()), (1000, ())));
+    let result =
+      binary_search((-95, ()), (95, ()), &Multiples(20), threshold(-30));
+    assert_eq!(result, ((-40, ()), (-20, ())));
+    let no_probes = |x: i64| -> Direction<(), ()> {
+      panic!("probed {} with no grid points", x)
+    };
+    let result = binary_search((3, ()), (995, ()), &Multiples(0), no_probes);
+    assert_eq!(result, ((3, ()), (995, ())));
+
+    let mut probes = 0;
+    let result =
+      binary_search((0_u64, ()), (1 << 40, ()), &PowersOfTwo, |x| {
+        probes += 1;
+        if x < 777 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(result, ((512, ()), (1024, ())));
+    assert!(probes <= 6);
+    let search = |t: u8| {
+      binary_search((0_u8, ()), (200, ()), &PowersOfTwo, |x| {
+        if x < t { Direction::Low(()) } else { Direction::High(()) }
+      })
+    };
+    assert_eq!(search(1), ((0, ()), (1, ())));
+    assert_eq!(search(150), ((128, ()), (255, ())));
+  }
+}
@@ -0,0 +1,191 @@
+//! Searches over byte buffers of fixed-size records, such as a memory-mapped
+//! index file, decoding each probed record in place.
+//!
+//! With the `zerocopy` feature, `RecordView` reads records of any type
+//! that `zerocopy` can read from bytes, such as integers, arrays of them,
+//! or `#[repr(C)]` structs deriving its traits. `ByteRecords` hands out
+//! each record as a slice of the buffer, for layouts a key has to be
+//! extracted from by hand.
+
+#[cfg(feature = "zerocopy")]
+use core::marker::PhantomData;
+#[cfg(feature = "zerocopy")]
+use core::mem::size_of;
+
+#[cfg(feature = "zerocopy")]
+use zerocopy::{FromBytes, Immutable, KnownLayout};
+
+use crate::partition_point;
+
+/// A byte buffer viewed as an array of `T` records in native byte order.
+///
+/// Records are copied out of the buffer one at a time as they're probed,
+/// with no alignment requirement on the buffer and no allocation.
+#[cfg(feature = "zerocopy")]
+#[derive(Debug, Clone, Copy)]
+pub struct RecordView<'a, T> {
+  bytes: &'a [u8],
+  record: PhantomData<T>,
+}
+
+#[cfg(feature = "zerocopy")]
+impl<'a, T> RecordView<'a, T>
+  where
+    T: FromBytes + Immutable + KnownLayout {
+  /// View `bytes` as records, or `None` if its length isn't a whole number
+  /// of records.
+  pub fn new(bytes: &'a [u8]) -> Option<Self> {
+    let size = size_of::<T>();
+    if size == 0 || !bytes.len().is_multiple_of(size) {
+      return None;
+    }
+    Some(RecordView { bytes, record: PhantomData })
+  }
+
+  pub fn len(&self) -> usize {
+    self.bytes.len() / size_of::<T>()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.bytes.is_empty()
+  }
+
+  /// Decode the `i`th record.
+  pub fn get(&self, i: usize) -> Option<T> {
+    let size = size_of::<T>();
+    let start = i.checked_mul(size)?;
+    let bytes = self.bytes.get(start..start.checked_add(size)?)?;
+    T::read_from_bytes(bytes).ok()
+  }
+
+  /// Find the number of leading records for which `pred` holds.
+  pub fn partition_point<P>(&self, mut pred: P) -> usize
+    where
+      P: FnMut(&T) -> bool {
+    partition_point(self.len(), |i| self.get(i).is_some_and(|r| (pred)(&r)))
+  }
+
+  /// Find the index of the first record whose key is not less than `key`.
+  pub fn lower_bound_by_key<K, F>(&self, key: &K, mut extract: F) -> usize
+    where
+      K: Ord,
+      F: FnMut(&T) -> K {
+    self.partition_point(|record| (extract)(record) < *key)
+  }
+}
+
+/// A byte buffer viewed as an array of records of `record_len` bytes each,
+/// such as the entries of an on-disk index, for records whose layout isn't
+/// a type `RecordView` can read.
+///
+/// Records are handed out as slices of the buffer, so nothing is copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByteRecords<'a> {
+  bytes: &'a [u8],
+  record_len: usize,
+}
+
+impl<'a> ByteRecords<'a> {
+  /// View `bytes` as records, or `None` if `record_len` is zero or the
+  /// length of `bytes` isn't a whole number of records.
+  pub fn new(bytes: &'a [u8], record_len: usize) -> Option<Self> {
+    if record_len == 0 || !bytes.len().is_multiple_of(record_len) {
+      return None;
+    }
+    Some(ByteRecords { bytes, record_len })
+  }
+
+  pub fn len(&self) -> usize {
+    self.bytes.len() / self.record_len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.bytes.is_empty()
+  }
+
+  /// The bytes of the `i`th record.
+  pub fn get(&self, i: usize) -> Option<&'a [u8]> {
+    let start = i.checked_mul(self.record_len)?;
+    self.bytes.get(start..start.checked_add(self.record_len)?)
+  }
+
+  /// Find the number of leading records for which `pred` holds.
+  pub fn partition_point<P>(&self, mut pred: P) -> usize
+    where
+      P: FnMut(&[u8]) -> bool {
+    partition_point(self.len(), |i| self.get(i).is_some_and(&mut pred))
+  }
+
+  /// Find the first record whose key, as read by `extract`, is not less
+  /// than `key`, with its index, which is `len()` if there's no such
+  /// record.
+  pub fn lower_bound_by_key<K, F>(
+      &self,
+      key: &K,
+      mut extract: F,
+    ) -> (usize, Option<&'a [u8]>)
+    where
+      K: Ord,
+      F: FnMut(&[u8]) -> K {
+    let index = self.partition_point(|record| (extract)(record) < *key);
+    (index, self.get(index))
+  }
+
+  /// Like `lower_bound_by_key`, but finding the first record whose key is
+  /// greater than `key`.
+  pub fn upper_bound_by_key<K, F>(
+      &self,
+      key: &K,
+      mut extract: F,
+    ) -> (usize, Option<&'a [u8]>)
+    where
+      K: Ord,
+      F: FnMut(&[u8]) -> K {
+    let index = self.partition_point(|record| (extract)(record) <= *key);
+    (index, self.get(index))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "zerocopy")]
+  #[test]
+  fn searches_unaligned_records() {
+    let records: [[u32; 2]; 4] = [[1, 10], [3, 30], [3, 31], [8, 80]];
+    let mut bytes = vec![0_u8];
+    for record in records.iter() {
+      for field in record.iter() {
+        bytes.extend_from_slice(&field.to_ne_bytes());
+      }
+    }
+    let view = RecordView::<[u32; 2]>::new(&bytes[1..]).unwrap();
+    assert_eq!(view.len(), 4);
+    assert_eq!(view.get(2), Some([3, 31]));
+    assert_eq!(view.get(4), None);
+    assert_eq!(view.lower_bound_by_key(&3, |r| r[0]), 1);
+    assert_eq!(view.lower_bound_by_key(&9, |r| r[0]), 4);
+    assert!(RecordView::<u32>::new(&bytes[..3]).is_none());
+  }
+
+  #[test]
+  fn searches_byte_records() {
+    // Six-byte records of a big-endian `u16` key and a four-byte payload.
+    let bytes = b"\x00\x01aaaa\x00\x05bbbb\x00\x05cccc\x01\x00dddd";
+    let records = ByteRecords::new(bytes, 6).unwrap();
+    let key = |record: &[u8]| u16::from_be_bytes([record[0], record[1]]);
+    assert_eq!(records.len(), 4);
+    assert_eq!(
+      records.lower_bound_by_key(&5, key),
+      (1, Some(&b"\x00\x05bbbb"[..])),
+    );
+    assert_eq!(
+      records.upper_bound_by_key(&5, key),
+      (3, Some(&b"\x01\x00dddd"[..])),
+    );
+    assert_eq!(records.lower_bound_by_key(&300, key), (4, None));
+    assert!(ByteRecords::new(&bytes[..7], 6).is_none());
+    assert!(ByteRecords::new(bytes, 0).is_none());
+  }
+}
@@ -0,0 +1,107 @@
+//! Shrinking a failing input to its shortest failing prefix, for reducing
+//! crashes found by a fuzzer.
+//!
+//! This assumes that once a prefix of the input fails, every longer prefix
+//! does too, as when the failure is triggered by something the parser
+//! reaches part way through the input.
+
+use std::collections::HashMap;
+
+use crate::{binary_search, Direction};
+
+/// Truncates `input` and tests the truncations with `fails`.
+///
+/// Each truncation is tested at most once, so following a search by lines
+/// with a search by bytes reuses the results they have in common. To reduce
+/// a file, read it with `std::fs::read` and write out the prefix found.
+pub struct PrefixReducer<'a, F> {
+  input: &'a [u8],
+  fails: F,
+  cache: HashMap<usize, bool>,
+}
+
+impl<'a, F> PrefixReducer<'a, F>
+  where
+    F: FnMut(&[u8]) -> bool {
+  pub fn new(input: &'a [u8], fails: F) -> Self {
+    PrefixReducer { input, fails, cache: HashMap::new() }
+  }
+
+  /// The number of distinct truncations tested so far.
+  pub fn tests_run(&self) -> usize {
+    self.cache.len()
+  }
+
+  /// Find the length of the shortest prefix that fails, or `None` if the
+  /// whole input passes.
+  pub fn shortest_bytes(&mut self) -> Option<usize> {
+    self.shortest_by(self.input.len() + 1, |i| i)
+  }
+
+  /// Find the length of the shortest prefix made of whole lines that fails,
+  /// or `None` if the whole input passes. The final line counts as whole
+  /// even without a trailing newline.
+  pub fn shortest_lines(&mut self) -> Option<usize> {
+    let mut cuts = vec![0];
+    cuts.extend(
+      self.input.iter().enumerate()
+        .filter(|&(_, &byte)| byte == b'\n')
+        .map(|(i, _)| i + 1)
+    );
+    if cuts.last() != Some(&self.input.len()) {
+      cuts.push(self.input.len());
+    }
+    self.shortest_by(cuts.len(), |i| cuts.get(i).copied().unwrap_or(0))
+  }
+
+  fn test(&mut self, len: usize) -> bool {
+    let input = self.input;
+    let fails = &mut self.fails;
+    *self.cache.entry(len).or_insert_with(|| {
+      (fails)(input.get(..len).unwrap_or(input))
+    })
+  }
+
+  /// Find the shortest failing prefix among `count` increasing lengths.
+  fn shortest_by<C>(&mut self, count: usize, cut: C) -> Option<usize>
+    where
+      C: Fn(usize) -> usize {
+    let last = count.checked_sub(1)?;
+    if !self.test((cut)(last)) {
+      return None;
+    }
+    if self.test((cut)(0)) {
+      return Some((cut)(0));
+    }
+    let (_, (high, ())) =
+      binary_search((0, ()), (last, ()), |i| {
+        if self.test((cut)(i)) {
+          Direction::High(())
+        } else {
+          Direction::Low(())
+        }
+      });
+    Some((cut)(high))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_shortest_failing_prefix() {
+    let input = b"ok\nok\nok BOOM ok\nok\n";
+    let fails = |prefix: &[u8]| prefix.windows(4).any(|w| w == b"BOOM");
+    let mut reducer = PrefixReducer::new(input, fails);
+    assert_eq!(reducer.shortest_lines(), Some(17));
+    assert_eq!(reducer.shortest_bytes(), Some(13));
+    let tests = reducer.tests_run();
+    assert_eq!(reducer.shortest_bytes(), Some(13));
+    assert_eq!(reducer.tests_run(), tests);
+
+    let mut reducer = PrefixReducer::new(b"fine", fails);
+    assert_eq!(reducer.shortest_bytes(), None);
+    assert_eq!(reducer.shortest_lines(), None);
+  }
+}
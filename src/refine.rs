@@ -0,0 +1,99 @@
+//! Re-running a search from a bracket found earlier, which may since have
+//! gone stale, such as one loaded from a cache.
+
+use core::ops::Bound;
+
+use crate::gallop::{gallop_down, gallop_up, Steppable};
+use crate::{binary_search, Bracket, Direction};
+
+/// Where `refine` may look for the transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RefineOptions<X> {
+  /// The lowest value to probe when widening the bracket.
+  pub min: X,
+  /// The highest value to probe when widening the bracket.
+  pub max: X,
+}
+
+impl<X> Default for RefineOptions<X>
+  where
+    X: Steppable {
+  fn default() -> Self {
+    RefineOptions { min: X::MIN, max: X::MAX }
+  }
+}
+
+/// Find the transition starting from `bracket`, an earlier result that may
+/// no longer hold.
+///
+/// Both ends of the bracket are probed. If the transition has moved out of
+/// it, the search gallops away from the end that no longer holds, taking
+/// `O(log d)` probes where `d` is how far the transition moved, and then
+/// bisects. Returns `None` if there's no transition between `options.min`
+/// and `options.max`.
+pub fn refine<X, A, B, F>(
+    bracket: (X, X),
+    mut f: F,
+    options: RefineOptions<X>,
+  ) -> Option<Bracket<X, A, B>>
+  where
+    X: Steppable,
+    F: FnMut(X) -> Direction<A, B> {
+  let (low, high) = bracket;
+  let low = low.clamp(options.min, options.max);
+  let high = high.clamp(options.min, options.max);
+  match (f)(low) {
+    Direction::High(b) => {
+      let (low, high) =
+        gallop_down((low, b), Bound::Included(options.min), &mut f).ok()?;
+      Some(binary_search(low, high, f))
+    },
+    Direction::Low(a) => {
+      let mut low = (low, a);
+      if low.0 < high {
+        match (f)(high) {
+          Direction::Low(a) => low = (high, a),
+          Direction::High(b) => {
+            return Some(binary_search(low, (high, b), f));
+          },
+        }
+      }
+      let (low, high) =
+        gallop_up(low, Bound::Included(options.max), &mut f).ok()?;
+      Some(binary_search(low, high, f))
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn refines_stale_brackets() {
+    let options = RefineOptions { min: 0, max: 10_000 };
+    for &(bracket, t, max_probes) in [
+      ((40, 60), 50, 8),
+      ((49, 50), 50, 2),
+      ((40, 60), 70, 10),
+      ((40, 60), 61, 3),
+      ((40, 60), 30, 10),
+      ((60, 40), 50, 12),
+      ((0, 10_000), 1, 16),
+      ((5000, 5001), 10_000, 26),
+    ].iter() {
+      let mut probes = 0;
+      let result =
+        refine(bracket, |x| {
+          probes += 1;
+          if x < t { Direction::Low(x) } else { Direction::High(x) }
+        }, options);
+      assert_eq!(result, Some(((t - 1, t - 1), (t, t))));
+      assert!(probes <= max_probes, "{} probes for {:?}", probes, bracket);
+    }
+    let all_high = |x: u32| Direction::High::<(), u32>(x);
+    assert_eq!(refine((40, 60), all_high, options), None);
+    let all_low = |x: u32| Direction::Low::<u32, ()>(x);
+    assert_eq!(refine((40, 60), all_low, RefineOptions::default()), None);
+  }
+}
@@ -0,0 +1,132 @@
+//! Retrying probes that can fail transiently, such as those that make a
+//! network request or run a job on a shared CI runner.
+
+use crate::{Aborted, Betweenable, Direction};
+
+/// How often to try a failing probe, and what to do between attempts.
+///
+/// `backoff(retry, &error)` is called before each retry, numbered from 1,
+/// with the error of the attempt before it, and is where to sleep or log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Retry<W> {
+  /// The most times a probe is tried, including the first.
+  pub max_attempts: usize,
+  pub backoff: W,
+}
+
+impl<W> Retry<W> {
+  pub fn new(max_attempts: usize, backoff: W) -> Self {
+    Retry { max_attempts, backoff }
+  }
+}
+
+/// The bracket found by `try_binary_search`, with how many retries the
+/// probes at its ends needed.
+///
+/// An end that is still a bound of the search wasn't probed, and needed no
+/// retries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Retried<X, A, B> {
+  pub low: (X, A),
+  pub high: (X, B),
+  pub low_retries: usize,
+  pub high_retries: usize,
+  /// The retries taken by every probe of the search.
+  pub total_retries: usize,
+}
+
+/// The outcome of `try_binary_search`.
+pub type TryRetried<X, A, B, E> =
+  Result<Retried<X, A, B>, Aborted<X, A, B, E>>;
+
+/// Like `crate::try_binary_search`, but a probe that fails is tried again
+/// as `retry` says, and only stops the search once it has failed
+/// `retry.max_attempts` times, with the last of its errors.
+pub fn try_binary_search<X, A, B, E, W, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    retry: &mut Retry<W>,
+    mut f: F,
+  ) -> TryRetried<X, A, B, E>
+  where
+    X: Betweenable,
+    W: FnMut(usize, &E),
+    F: FnMut(X) -> Result<Direction<A, B>, E> {
+  let mut low_retries = 0;
+  let mut high_retries = 0;
+  let mut total_retries = 0;
+  while let Some(x) = X::between(low.0, high.0) {
+    let mut retries = 0;
+    let direction =
+      loop {
+        match (f)(x) {
+          Ok(direction) => break direction,
+          Err(error) if retries + 1 >= retry.max_attempts => {
+            return Err(Aborted { error, low, high });
+          },
+          Err(error) => {
+            retries += 1;
+            total_retries += 1;
+            (retry.backoff)(retries, &error);
+          },
+        }
+      };
+    match direction {
+      Direction::Low(a) => {
+        low = (x, a);
+        low_retries = retries;
+      },
+      Direction::High(b) => {
+        high = (x, b);
+        high_retries = retries;
+      },
+    }
+  }
+  Ok(Retried { low, high, low_retries, high_retries, total_retries })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  // Probes of multiples of ten time out twice before they succeed.
+  fn flaky() -> impl FnMut(u32) -> Result<Direction<(), ()>, &'static str> {
+    let mut failures = HashMap::new();
+    move |x| {
+      let failed = failures.entry(x).or_insert(0);
+      if x % 10 == 0 && *failed < 2 {
+        *failed += 1;
+        Err("timeout")
+      } else if x < 30 {
+        Ok(Direction::Low(()))
+      } else {
+        Ok(Direction::High(()))
+      }
+    }
+  }
+
+  #[test]
+  fn retries_until_attempts_run_out() {
+    let mut waits = Vec::new();
+    let mut retry = Retry::new(3, |retry, _: &&str| waits.push(retry));
+    let result = try_binary_search((0, ()), (100, ()), &mut retry, flaky());
+    assert_eq!(
+      result,
+      Ok(Retried {
+        low: (29, ()),
+        high: (30, ()),
+        low_retries: 0,
+        high_retries: 2,
+        total_retries: 4,
+      }),
+    );
+    assert_eq!(waits, [1, 2, 1, 2]);
+
+    let mut retry = Retry::new(2, |_, _: &&str| {});
+    assert_eq!(
+      try_binary_search((0, ()), (100, ()), &mut retry, flaky()),
+      Err(Aborted { error: "timeout", low: (0, ()), high: (100, ()) }),
+    );
+  }
+}
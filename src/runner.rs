@@ -0,0 +1,375 @@
+//! Bisecting an integer range by running an external command for each
+//! probe, as `git bisect run` does, for finding the build or input at
+//! which a regression appeared.
+//!
+//! The command is run with the probed value in `BISECT_VALUE`, and in place
+//! of any `{}` argument. By default its exit code decides the probe, as it
+//! does for `git bisect run`: 0 means good, 125 means the value can't be
+//! tested, and anything else up to 127 means bad. `Runner::classify_output`
+//! replaces that with a function of its status and output.
+//!
+//! With `Runner::resume`, every result is appended to a log file, one
+//! `VALUE good|bad|skip` line each, and results already in it are reused
+//! rather than run again, so an interrupted search carries on where it
+//! left off.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+
+use crate::builder::InvalidBounds;
+use crate::skip::next_probe;
+
+/// What a probe found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+  /// The value is before the regression.
+  Good,
+  /// The value is at or after the regression.
+  Bad,
+  /// The value can't be tested.
+  Skip,
+}
+
+impl Verdict {
+  /// The verdict of `git bisect run` for a command that exited with
+  /// `status`, or `None` if the status should abort the search.
+  pub fn from_exit_status(status: ExitStatus) -> Option<Verdict> {
+    match status.code()? {
+      0 => Some(Verdict::Good),
+      125 => Some(Verdict::Skip),
+      code if (1..128).contains(&code) => Some(Verdict::Bad),
+      _ => None,
+    }
+  }
+
+  /// The verdict's name in the log, `good`, `bad` or `skip`.
+  pub fn name(self) -> &'static str {
+    match self {
+      Verdict::Good => "good",
+      Verdict::Bad => "bad",
+      Verdict::Skip => "skip",
+    }
+  }
+
+  fn parse(name: &str) -> Option<Verdict> {
+    match name {
+      "good" => Some(Verdict::Good),
+      "bad" => Some(Verdict::Bad),
+      "skip" => Some(Verdict::Skip),
+      _ => None,
+    }
+  }
+}
+
+/// Why a run stopped without a result.
+#[derive(Debug)]
+pub enum RunError {
+  /// The low end of the range isn't below the high end.
+  Bounds(InvalidBounds<i64>),
+  /// There was no command to run.
+  NoCommand,
+  /// The command couldn't be started.
+  Spawn(io::Error),
+  /// The log couldn't be read or written.
+  Log(io::Error),
+  /// A line of the log isn't a value and a verdict.
+  BadLog(String),
+  /// The command's status or output didn't give a verdict for `value`.
+  Unclassified { value: i64, status: ExitStatus },
+}
+
+impl fmt::Display for RunError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      RunError::Bounds(e) => write!(f, "{}", e),
+      RunError::NoCommand => write!(f, "no command to run"),
+      RunError::Spawn(e) => write!(f, "couldn't run command: {}", e),
+      RunError::Log(e) => write!(f, "log: {}", e),
+      RunError::BadLog(line) => write!(f, "log: bad line {:?}", line),
+      RunError::Unclassified { value, status } => {
+        write!(f, "command ended with {} at {}", status, value)
+      },
+    }
+  }
+}
+
+impl std::error::Error for RunError {}
+
+/// The outcome of `Runner::run`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Report {
+  pub last_good: i64,
+  pub first_bad: i64,
+  /// The skipped values between `last_good` and `first_bad`, in order,
+  /// any of which could be the first bad value instead.
+  pub untestable: Vec<i64>,
+  /// The number of times the command was run, not counting results read
+  /// from the log.
+  pub runs: usize,
+  /// Whether the search stopped at `Runner::max_probes` before finishing.
+  pub exhausted: bool,
+}
+
+/// A bisection of `low..=high` by running a command, configured a step at
+/// a time, as with `builder::BinarySearch`.
+///
+/// `low` is taken to be good and `high` bad, without running the command
+/// for them. `Runner::run` fails with `InvalidBounds::Empty` unless `low`
+/// is below `high`.
+pub struct Runner<C = fn(&Output) -> Option<Verdict>> {
+  command: Vec<String>,
+  low: i64,
+  high: i64,
+  classify: Option<C>,
+  resume: Option<PathBuf>,
+  max_probes: Option<usize>,
+}
+
+impl Runner {
+  /// Bisect between `low` and `high` by running `command`, the program
+  /// followed by its arguments.
+  pub fn new<I, S>(low: i64, high: i64, command: I) -> Self
+    where
+      I: IntoIterator<Item = S>,
+      S: Into<String> {
+    Runner {
+      command: command.into_iter().map(Into::into).collect(),
+      low,
+      high,
+      classify: None,
+      resume: None,
+      max_probes: None,
+    }
+  }
+}
+
+impl<C> Runner<C>
+  where
+    C: FnMut(&Output) -> Option<Verdict> {
+  /// Capture the command's output and decide each probe with `classify`
+  /// instead of by the exit code, returning `None` to abort the search.
+  pub fn classify_output<D>(self, classify: D) -> Runner<D>
+    where
+      D: FnMut(&Output) -> Option<Verdict> {
+    Runner {
+      command: self.command,
+      low: self.low,
+      high: self.high,
+      classify: Some(classify),
+      resume: self.resume,
+      max_probes: self.max_probes,
+    }
+  }
+
+  /// Log results to `path`, reusing those already in it.
+  pub fn resume<P>(mut self, path: P) -> Self
+    where
+      P: Into<PathBuf> {
+    self.resume = Some(path.into());
+    self
+  }
+
+  /// Stop after running the command `n` times, even if the search isn't
+  /// finished. Results from the log don't count.
+  pub fn max_probes(mut self, n: usize) -> Self {
+    self.max_probes = Some(n);
+    self
+  }
+
+  /// Run the search, calling `trace(x, (low, high), verdict)` for each
+  /// probe, whether run or read from the log, with the bracket it was
+  /// made in.
+  pub fn run<T>(&mut self, mut trace: T) -> Result<Report, RunError>
+    where
+      T: FnMut(i64, (i64, i64), Verdict) {
+    if self.low >= self.high {
+      let empty = InvalidBounds::Empty { low: self.low, high: self.high };
+      return Err(RunError::Bounds(empty));
+    }
+    let mut known =
+      match &self.resume {
+        Some(path) => load(path)?,
+        None => BTreeMap::new(),
+      };
+    let mut log =
+      match &self.resume {
+        Some(path) => {
+          let file =
+            OpenOptions::new().create(true).append(true).open(path)
+              .map_err(RunError::Log)?;
+          Some(file)
+        },
+        None => None,
+      };
+
+    let mut low = self.low;
+    let mut high = self.high;
+    for (&x, &verdict) in known.iter() {
+      match verdict {
+        Verdict::Good if low < x && x < high => low = x,
+        Verdict::Bad if low < x && x < high => high = x,
+        _ => {},
+      }
+    }
+    let mut skipped: BTreeSet<i64> =
+      known.iter()
+        .filter(|&(&x, &verdict)| {
+          verdict == Verdict::Skip && low < x && x < high
+        })
+        .map(|(&x, _)| x)
+        .collect();
+
+    let mut runs = 0;
+    let mut exhausted = false;
+    while let Some(x) = next_probe(low, high, &skipped) {
+      let verdict =
+        match known.get(&x) {
+          Some(&verdict) => verdict,
+          None => {
+            if self.max_probes.is_some_and(|max| runs >= max) {
+              exhausted = true;
+              break;
+            }
+            runs += 1;
+            let verdict = self.probe(x)?;
+            if let Some(log) = &mut log {
+              writeln!(log, "{} {}", x, verdict.name())
+                .map_err(RunError::Log)?;
+            }
+            known.insert(x, verdict);
+            verdict
+          },
+        };
+      (trace)(x, (low, high), verdict);
+      match verdict {
+        Verdict::Good => low = x,
+        Verdict::Bad => high = x,
+        Verdict::Skip => {
+          skipped.insert(x);
+        },
+      }
+    }
+
+    let untestable =
+      skipped.range(low.saturating_add(1)..high).copied().collect();
+    Ok(Report { last_good: low, first_bad: high, untestable, runs, exhausted })
+  }
+
+  /// Run the command for `x`.
+  fn probe(&mut self, x: i64) -> Result<Verdict, RunError> {
+    let value = x.to_string();
+    let mut args =
+      self.command.iter().map(|arg| if arg == "{}" { &value } else { arg });
+    let program = args.next().ok_or(RunError::NoCommand)?;
+    let mut command = Command::new(program);
+    command.args(args).env("BISECT_VALUE", &value);
+    let (status, verdict) =
+      match &mut self.classify {
+        None => {
+          let status = command.status().map_err(RunError::Spawn)?;
+          (status, Verdict::from_exit_status(status))
+        },
+        Some(classify) => {
+          let output = command.output().map_err(RunError::Spawn)?;
+          (output.status, (classify)(&output))
+        },
+      };
+    verdict.ok_or(RunError::Unclassified { value: x, status })
+  }
+}
+
+/// Read the results recorded by an earlier run, if there was one.
+fn load(path: &Path) -> Result<BTreeMap<i64, Verdict>, RunError> {
+  let mut known = BTreeMap::new();
+  let file =
+    match File::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(known),
+      Err(e) => return Err(RunError::Log(e)),
+    };
+  for line in BufReader::new(file).lines() {
+    let line = line.map_err(RunError::Log)?;
+    let mut words = line.split_whitespace();
+    let entry =
+      match (words.next(), words.next()) {
+        (Some(x), Some(verdict)) => {
+          x.parse().ok().zip(Verdict::parse(verdict))
+        },
+        _ => None,
+      };
+    match entry {
+      Some((x, verdict)) => {
+        known.insert(x, verdict);
+      },
+      None => return Err(RunError::BadLog(line)),
+    }
+  }
+  Ok(known)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn runs_commands_and_resumes() {
+    // Values from 40 to 44 can't be tested, and 42 is the first bad one.
+    let script =
+      "if [ $BISECT_VALUE -ge 40 ] && [ $BISECT_VALUE -le 44 ]; then \
+         exit 125; fi; [ $1 -lt 42 ]";
+    let command = ["sh", "-c", script, "sh", "{}"];
+    let mut probes = Vec::new();
+    let report =
+      Runner::new(0, 100, command.iter().copied())
+        .run(|x, _, verdict| probes.push((x, verdict)))
+        .unwrap();
+    assert_eq!((report.last_good, report.first_bad), (39, 45));
+    assert_eq!(report.untestable, vec![40, 41, 42, 43, 44]);
+    assert_eq!(report.runs, probes.len());
+    assert!(!report.exhausted);
+
+    let path =
+      std::env::temp_dir()
+        .join(format!("binary-search-runner-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let capped =
+      Runner::new(0, 100, command.iter().copied())
+        .resume(&path)
+        .max_probes(2)
+        .run(|_, _, _| {})
+        .unwrap();
+    assert!(capped.exhausted);
+    assert_eq!(capped.runs, 2);
+    let resumed =
+      Runner::new(0, 100, command.iter().copied())
+        .resume(&path)
+        .run(|_, _, _| {})
+        .unwrap();
+    assert_eq!((resumed.last_good, resumed.first_bad), (39, 45));
+    assert_eq!(resumed.runs, report.runs - 2);
+    std::fs::remove_file(&path).unwrap();
+
+    // Classified by what it prints rather than how it exits.
+    let square = ["sh", "-c", "echo $(($1 * $1))", "sh", "{}"];
+    let report =
+      Runner::new(0, 100, square.iter().copied())
+        .classify_output(|output| {
+          let square: i64 =
+            String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+          Some(if square < 2000 { Verdict::Good } else { Verdict::Bad })
+        })
+        .run(|_, _, _| {})
+        .unwrap();
+    assert_eq!((report.last_good, report.first_bad), (44, 45));
+
+    let error = Runner::new(5, 5, command.iter().copied()).run(|_, _, _| {});
+    assert!(matches!(
+      error,
+      Err(RunError::Bounds(InvalidBounds::Empty { low: 5, high: 5 })),
+    ));
+  }
+}
@@ -0,0 +1,251 @@
+//! Enumerating every probe a search could make, ahead of time.
+//!
+//! This is for predicates where each value has to be prepared before it can
+//! be probed, such as builds to prefetch or resources to provision.
+
+use crate::{binary_search, Betweenable, Bracket, Direction};
+
+/// The widest range `binary_search_min_cost` plans probes for. Wider ranges
+/// are bisected as by `binary_search`, as the plan would take too long to
+/// work out and too much memory to keep.
+pub const MAX_PLANNED_WIDTH: usize = 256;
+
+/// The decision tree of a search over fixed bounds, in depth-first order,
+/// low side first.
+///
+/// Each item is `(low, x, high)`: when the bracket has narrowed to
+/// `(low, high)`, the search probes `x`. Every point strictly between the
+/// bounds appears as a probe exactly once, so this is as long as the range
+/// is wide.
+#[derive(Debug, Clone)]
+pub struct ProbeSchedule<X> {
+  stack: Vec<(X, X)>,
+}
+
+impl<X> Iterator for ProbeSchedule<X>
+  where
+    X: Betweenable {
+  type Item = (X, X, X);
+
+  fn next(&mut self) -> Option<(X, X, X)> {
+    loop {
+      let (low, high) = self.stack.pop()?;
+      if let Some(x) = X::between(low, high) {
+        self.stack.push((x, high));
+        self.stack.push((low, x));
+        return Some((low, x, high));
+      }
+    }
+  }
+}
+
+/// Enumerate every probe a search between `low` and `high` could make.
+pub fn probe_schedule<X>(low: X, high: X) -> ProbeSchedule<X>
+  where
+    X: Betweenable {
+  ProbeSchedule { stack: vec![(low, high)] }
+}
+
+/// Like `binary_search` over indices, but choosing probes to minimise the
+/// expected cost of moving a system from one value to the next, such as
+/// resizing a cluster, where scaling up may be cheaper than scaling down.
+///
+/// The system starts at `state`, and `cost(from, to)` is the cost of moving
+/// it from one value to another. The plan assumes the transition is equally
+/// likely to be anywhere in the range, and is worked out in full before the
+/// first probe, which takes `O(n³)` time and `O(n²)` space for a range of
+/// `n` values, so this is for short ranges of expensive probes. Ranges wider
+/// than `MAX_PLANNED_WIDTH` are bisected without regard to cost.
+pub fn binary_search_min_cost<A, B, C, F>(
+    low: (usize, A),
+    high: (usize, B),
+    state: usize,
+    mut cost: C,
+    mut f: F,
+  ) -> Bracket<usize, A, B>
+  where
+    C: FnMut(usize, usize) -> f64,
+    F: FnMut(usize) -> Direction<A, B> {
+  let base = low.0;
+  let n = high.0.saturating_sub(low.0);
+  let size =
+    Some(n)
+      .filter(|&n| n <= MAX_PLANNED_WIDTH)
+      .and_then(|n| n.checked_add(1))
+      .and_then(|side| side.checked_mul(side))
+      .and_then(|size| size.checked_mul(2));
+  let size =
+    match size {
+      Some(size) => size,
+      None => return binary_search(low, high, f),
+    };
+  // The best probe and its expected cost for each bracket `(a, b)` of
+  // offsets from `low`, when the system was left at `a` or at `b`, which
+  // it always is after the first probe.
+  let index = |a: usize, b: usize, at_high: bool| {
+    (a * (n + 1) + b) * 2 + usize::from(at_high)
+  };
+  let mut plan = vec![(0.0_f64, 0_usize); size];
+  let mut best = |plan: &[(f64, usize)], from: usize, a: usize, b: usize| {
+    let width = (b - a) as f64;
+    let expected = |i| plan.get(i).map_or(0.0, |&(e, _)| e);
+    ((a + 1)..b).map(|x| {
+      let move_cost = (cost)(from, base + x);
+      let after_low = (b - x) as f64 * expected(index(x, b, false));
+      let after_high = (x - a) as f64 * expected(index(a, x, true));
+      (move_cost + (after_low + after_high) / width, x)
+    }).min_by(|p, q| p.0.total_cmp(&q.0))
+  };
+  for width in 2..=n {
+    for a in 0..=(n - width) {
+      let b = a + width;
+      for &at_high in [false, true].iter() {
+        let from = base + if at_high { b } else { a };
+        if let Some(choice) = best(&plan, from, a, b) {
+          if let Some(entry) = plan.get_mut(index(a, b, at_high)) {
+            *entry = choice;
+          }
+        }
+      }
+    }
+  }
+  let (mut low, mut high) = (low, high);
+  let mut next = best(&plan, state, 0, n).map(|(_, x)| x);
+  while let Some(x) = next {
+    let (a, b) = (low.0 - base, high.0 - base);
+    match (f)(base + x) {
+      Direction::Low(w) => {
+        low = (base + x, w);
+        next = plan.get(index(x, b, false)).map(|&(_, x)| x);
+      },
+      Direction::High(w) => {
+        high = (base + x, w);
+        next = plan.get(index(a, x, true)).map(|&(_, x)| x);
+      },
+    }
+    next = next.filter(|&x| low.0 - base < x && x < high.0 - base);
+  }
+  (low, high)
+}
+
+/// Like `binary_search` over indices, but biasing probes towards values
+/// that are cheap to probe, such as low optimization levels when each probe
+/// is a build, where `cost(x)` is the cost of probing `x`.
+///
+/// This is `binary_search_min_cost` with costs that don't depend on the
+/// last probe, so the plan minimises the expected total cost of the probes
+/// in the same way, with the same `O(n³)` planning time, and the same
+/// fallback to bisection for ranges wider than `MAX_PLANNED_WIDTH`.
+pub fn binary_search_weighted<A, B, C, F>(
+    low: (usize, A),
+    high: (usize, B),
+    mut cost: C,
+    f: F,
+  ) -> Bracket<usize, A, B>
+  where
+    C: FnMut(usize) -> f64,
+    F: FnMut(usize) -> Direction<A, B> {
+  let state = low.0;
+  binary_search_min_cost(low, high, state, |_, x| (cost)(x), f)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{binary_search, Direction};
+
+  #[test]
+  fn min_cost_search_is_no_costlier_than_bisection() {
+    // Scaling up costs one per step, and scaling down ten.
+    let cost = |from: usize, to: usize| {
+      if to >= from { (to - from) as f64 } else { (from - to) as f64 * 10.0 }
+    };
+    let mut planned_total = 0.0;
+    let mut bisection_total = 0.0;
+    for threshold in 1..=40 {
+      let threshold = move |x: usize| {
+        if x < threshold { Direction::Low(()) } else { Direction::High(()) }
+      };
+      let mut state = 0;
+      let result =
+        binary_search_min_cost((0, ()), (40, ()), 0, cost, |x| {
+          planned_total += cost(state, x);
+          state = x;
+          threshold(x)
+        });
+      assert_eq!(result, binary_search((0, ()), (40, ()), threshold));
+      let mut state = 0;
+      binary_search((0, ()), (40, ()), |x| {
+        bisection_total += cost(state, x);
+        state = x;
+        threshold(x)
+      });
+    }
+    assert!(planned_total < bisection_total);
+    let result =
+      binary_search_min_cost((5, 'l'), (6, 'h'), 0, cost, |_| unreachable!());
+    assert_eq!(result, ((5, 'l'), (6, 'h')));
+
+    // Too wide to plan, so bisected.
+    let mut probes = 0;
+    let result =
+      binary_search_min_cost((0, ()), (usize::MAX, ()), 0, cost, |x| {
+        probes += 1;
+        if x < 1000 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(result, ((999, ()), (1000, ())));
+    assert_eq!(probes, 64);
+  }
+
+  #[test]
+  fn weighted_search_favours_cheap_probes() {
+    // Probes from 80 up are fifty times the cost of those below.
+    let cost = |x: usize| if x < 80 { 1.0 } else { 50.0 };
+    let mut weighted_total = 0.0;
+    let mut bisection_total = 0.0;
+    for threshold in 1..=100 {
+      let threshold = move |x: usize| {
+        if x < threshold { Direction::Low(()) } else { Direction::High(()) }
+      };
+      let result =
+        binary_search_weighted((0, ()), (100, ()), cost, |x| {
+          weighted_total += cost(x);
+          threshold(x)
+        });
+      assert_eq!(result, binary_search((0, ()), (100, ()), threshold));
+      binary_search((0, ()), (100, ()), |x| {
+        bisection_total += cost(x);
+        threshold(x)
+      });
+    }
+    assert!(weighted_total < bisection_total * 0.95);
+
+    let mut probes = Vec::new();
+    binary_search_weighted((0, ()), (64, ()), |_| 1.0, |x| {
+      probes.push(x);
+      if x < 40 { Direction::Low(()) } else { Direction::High(()) }
+    });
+    assert_eq!(probes.len(), 6);
+  }
+
+  #[test]
+  fn schedule_covers_every_search() {
+    let schedule: Vec<(u32, u32, u32)> = probe_schedule(0, 6).collect();
+    assert_eq!(
+      schedule,
+      vec![(0, 3, 6), (0, 1, 3), (1, 2, 3), (3, 4, 6), (4, 5, 6)],
+    );
+
+    let schedule: Vec<(u32, u32, u32)> = probe_schedule(0, 100).collect();
+    for threshold in 1..=100 {
+      binary_search((0, ()), (100, ()), |x| {
+        assert!(schedule.iter().any(|&(_, probe, _)| probe == x));
+        if x < threshold {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    }
+  }
+}
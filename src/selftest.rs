@@ -0,0 +1,272 @@
+//! Checking a `Betweenable` implementation against searches designed to
+//! break it, for crates that implement it for their own types.
+
+use std::fmt;
+
+use crate::{binary_search_with_budget, Betweenable, Budgeted, Direction};
+
+/// The most probes any search in `self_test` may take before it's taken not
+/// to terminate.
+pub const MAX_PROBES: usize = 1024;
+
+/// The kind of search a failure happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scenario<X> {
+  /// `between` itself, for the pair of points.
+  Between,
+  /// A search where every probe is low.
+  AllLow,
+  /// A search where every probe is high.
+  AllHigh,
+  /// A search whose transition is at the given point.
+  Transition(X),
+}
+
+/// A search that didn't behave as it should have.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Failure<X> {
+  pub scenario: Scenario<X>,
+  pub low: X,
+  pub high: X,
+  pub problem: String,
+}
+
+impl<X> fmt::Display for Failure<X>
+  where
+    X: fmt::Debug {
+  /// Formats as, for example, `between(3, 4): gave 4`, or for a search,
+  /// `AllLow search from 3 to 9: probed 9, outside`.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.scenario {
+      Scenario::Between => {
+        write!(f, "between({:?}, {:?}): ", self.low, self.high)?;
+      },
+      scenario => {
+        write!(
+          f,
+          "{:?} search from {:?} to {:?}: ",
+          scenario,
+          self.low,
+          self.high,
+        )?;
+      },
+    }
+    write!(f, "{}", self.problem)
+  }
+}
+
+impl<X> std::error::Error for Failure<X>
+  where
+    X: fmt::Debug {}
+
+/// The outcome of `self_test`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Report<X> {
+  /// The number of searches and checks run.
+  pub cases: usize,
+  pub failures: Vec<Failure<X>>,
+}
+
+impl<X> Report<X> {
+  pub fn is_ok(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
+
+impl<X> fmt::Display for Report<X>
+  where
+    X: fmt::Debug {
+  /// Formats as, for example, `120 cases, 2 failures`, followed by a line
+  /// for each failure.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let plural = if self.failures.len() == 1 { "" } else { "s" };
+    write!(
+      f,
+      "{} cases, {} failure{}",
+      self.cases,
+      self.failures.len(),
+      plural,
+    )?;
+    for failure in self.failures.iter() {
+      write!(f, "\n  {}", failure)?;
+    }
+    Ok(())
+  }
+}
+
+/// Check `X`'s `Betweenable` implementation with searches between pairs of
+/// `points`.
+///
+/// `points` must be sorted and distinct, and should include the least and
+/// greatest values of the type, so that the widest searches are tried, and
+/// some values next to each other, so that the narrowest are. Between each
+/// pair, it checks that `between` is strictly inside them and only gives
+/// up when no other point is, and it runs searches where everything is
+/// low, everything is high, and where the transition is at each point in
+/// between, checking that each probes only inside its bracket, never twice
+/// in the same place, terminates within `MAX_PROBES`, and finishes with a
+/// bracket that can't be narrowed and agrees with the predicate. A single
+/// point is also checked to have nothing between it and itself.
+///
+/// This takes a number of searches cubic in the number of points, so a few
+/// dozen points are plenty.
+pub fn self_test<X>(points: &[X]) -> Report<X>
+  where
+    X: Betweenable + Ord + fmt::Debug {
+  let mut report = Report { cases: 0, failures: Vec::new() };
+  let mut cases = 0;
+
+  for (i, &low) in points.iter().enumerate() {
+    cases += 1;
+    if let Err(failure) = check_pair(low, low, points) {
+      report.failures.push(failure);
+    }
+    for &high in points.iter().skip(i + 1) {
+      cases += 1;
+      if let Err(failure) = check_pair(low, high, points) {
+        report.failures.push(failure);
+      }
+
+      let thresholds = points.iter().filter(|&&x| low < x && x <= high);
+      let scenarios =
+        [Scenario::AllLow, Scenario::AllHigh].iter().copied()
+          .chain(thresholds.map(|&t| Scenario::Transition(t)));
+      for scenario in scenarios {
+        cases += 1;
+        if let Err(problem) = run(scenario, low, high) {
+          report.failures.push(Failure { scenario, low, high, problem });
+        }
+      }
+    }
+  }
+  report.cases = cases;
+  report
+}
+
+/// Check the laws of `between` for every ordered pair of `samples`: it
+/// gives a value strictly between `low` and `high` when `low < high` and
+/// some value is between them, and `None` otherwise, for a test suite to
+/// call with samples of its own type.
+///
+/// Adjacency can only be judged from the samples, so they should include
+/// values next to each other, as well as the least and greatest of the
+/// type. Returns the first pair found breaking a law.
+pub fn check_betweenable_laws<X>(samples: &[X]) -> Result<(), Failure<X>>
+  where
+    X: Betweenable + Ord + fmt::Debug {
+  for &low in samples.iter() {
+    for &high in samples.iter() {
+      check_pair(low, high, samples)?;
+    }
+  }
+  Ok(())
+}
+
+fn check_pair<X>(low: X, high: X, samples: &[X]) -> Result<(), Failure<X>>
+  where
+    X: Betweenable + Ord + fmt::Debug {
+  let inside = samples.iter().find(|&&x| low < x && x < high);
+  let problem =
+    match (X::between(low, high), inside) {
+      (Some(x), _) if !(low < x && x < high) => format!("gave {:?}", x),
+      (None, Some(x)) => format!("gave nothing, but {:?} is between", x),
+      _ => return Ok(()),
+    };
+  Err(Failure { scenario: Scenario::Between, low, high, problem })
+}
+
+/// Run one search, describing what went wrong if anything did.
+fn run<X>(scenario: Scenario<X>, low: X, high: X) -> Result<(), String>
+  where
+    X: Betweenable + Ord + fmt::Debug {
+  let is_low = |x: X| {
+    match scenario {
+      Scenario::Between | Scenario::AllLow => true,
+      Scenario::AllHigh => false,
+      Scenario::Transition(t) => x < t,
+    }
+  };
+  let mut probes = Vec::new();
+  let mut problem = None;
+  let result =
+    binary_search_with_budget(
+      (low, ()),
+      (high, ()),
+      Some(MAX_PROBES),
+      || false,
+      |x| {
+        if !(low < x && x < high) {
+          problem.get_or_insert(format!("probed {:?}, outside", x));
+        } else if probes.contains(&x) {
+          problem.get_or_insert(format!("probed {:?} twice", x));
+        }
+        probes.push(x);
+        if is_low(x) { Direction::Low(()) } else { Direction::High(()) }
+      },
+    );
+  if let Some(problem) = problem {
+    return Err(problem);
+  }
+  let ((x_low, ()), (x_high, ())) =
+    match result {
+      Budgeted::Converged(bracket) => bracket,
+      _ => return Err(format!("didn't finish in {} probes", MAX_PROBES)),
+    };
+  let consistent =
+    x_low < x_high
+      && (x_low == low || is_low(x_low))
+      && (x_high == high || !is_low(x_high));
+  let expected =
+    match scenario {
+      Scenario::Transition(t) => t == x_high,
+      _ => true,
+    };
+  if !(consistent && expected) {
+    return Err(format!("finished at {:?} to {:?}", x_low, x_high));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+  struct Sloppy(u8);
+
+  impl Betweenable for Sloppy {
+    // Rounds up, so it can return `high` itself.
+    fn between(Sloppy(low): Self, Sloppy(high): Self) -> Option<Self> {
+      (low < high).then(|| Sloppy(low + (high - low).div_ceil(2)))
+    }
+  }
+
+  #[test]
+  fn passes_sound_impls_and_catches_broken_ones() {
+    let report = self_test(&[0_u8, 1, 2, 3, 100, 101, 254, 255]);
+    assert!(report.is_ok(), "{}", report);
+    let report = self_test(&[i64::MIN, -1, 0, 1, i64::MAX - 1, i64::MAX]);
+    assert!(report.is_ok(), "{}", report);
+
+    let report = self_test(&[Sloppy(0), Sloppy(1), Sloppy(2), Sloppy(9)]);
+    assert!(!report.is_ok());
+    assert!(report.failures.contains(&Failure {
+      scenario: Scenario::Between,
+      low: Sloppy(0),
+      high: Sloppy(1),
+      problem: "gave Sloppy(1)".to_string(),
+    }));
+    assert!(report.to_string().starts_with("32 cases, "), "{}", report);
+
+    assert_eq!(check_betweenable_laws(&[u8::MAX, 0, 1, 7, 8, 200]), Ok(()));
+    assert_eq!(
+      check_betweenable_laws(&[i32::MIN, -2, -1, 0, i32::MAX]),
+      Ok(()),
+    );
+    let failure = check_betweenable_laws(&[Sloppy(3), Sloppy(4)]).unwrap_err();
+    assert_eq!(
+      failure.to_string(),
+      "between(Sloppy(3), Sloppy(4)): gave Sloppy(4)",
+    );
+  }
+}
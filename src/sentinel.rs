@@ -0,0 +1,108 @@
+//! Slice searches over tables padded with sentinels.
+//!
+//! Padding a sorted table with maximum values up to a power of two lets the
+//! hot loop probe without checking bounds, and with a single comparison per
+//! step, since every probe lands inside the padded table and the padding
+//! sorts after every real element.
+//!
+//! This pays off for tables that fit in cache. For large tables, probing
+//! at power-of-two strides maps many probes to the same cache sets, and
+//! `slice::partition_point` tends to win; `cargo bench --bench lookup`
+//! compares the two.
+
+/// Types with a greatest value to pad with.
+pub trait Sentinel: Copy + Ord {
+  const SENTINEL: Self;
+}
+
+macro_rules! impl_sentinel {
+  ($($t:ty),*) => {
+    $(
+      impl Sentinel for $t {
+        const SENTINEL: Self = <$t>::MAX;
+      }
+    )*
+  };
+}
+
+impl_sentinel!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A sorted table padded with sentinels up to the next power of two past
+/// its length, so that there is always at least one sentinel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Padded<T> {
+  values: Vec<T>,
+  len: usize,
+}
+
+impl<T> Padded<T>
+  where
+    T: Sentinel {
+  /// Pad `values`, which must be sorted.
+  pub fn new(mut values: Vec<T>) -> Self {
+    let len = values.len();
+    let padded = (len + 1).next_power_of_two();
+    values.resize(padded, T::SENTINEL);
+    Padded { values, len }
+  }
+
+  /// The table without its padding.
+  pub fn as_slice(&self) -> &[T] {
+    self.values.get(..self.len).unwrap_or_default()
+  }
+
+  /// Find the index of the first element that is not less than `key`.
+  pub fn lower_bound(&self, key: T) -> usize {
+    self.search(|x| x < key)
+  }
+
+  /// Find the index of the first element that is greater than `key`.
+  pub fn upper_bound(&self, key: T) -> usize {
+    self.search(|x| x <= key)
+  }
+
+  #[inline(always)]
+  fn search<P>(&self, is_low: P) -> usize
+    where
+      P: Fn(T) -> bool {
+    let mut position = 0;
+    let mut step = self.values.len() / 2;
+    while step > 0 {
+      // SAFETY: `values.len()` is a power of two and the steps are the
+      // smaller powers of two in decreasing order, so before each step
+      // `position` is at most `values.len() - 2 * step`.
+      let x = unsafe { *self.values.get_unchecked(position + step - 1) };
+      let next = position + step;
+      position = std::hint::select_unpredictable((is_low)(x), next, position);
+      step /= 2;
+    }
+    // SAFETY: `position` is at most `values.len() - 1`.
+    let x = unsafe { *self.values.get_unchecked(position) };
+    (position + (is_low)(x) as usize).min(self.len)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_partition_point() {
+    for len in 0..40 {
+      let values: Vec<u8> =
+        (0..len).map(|i: u32| (i * 7).min(255) as u8).collect();
+      let padded = Padded::new(values.clone());
+      assert_eq!(padded.as_slice(), &values[..]);
+      for key in 0..=u8::MAX {
+        assert_eq!(
+          padded.lower_bound(key),
+          values.partition_point(|&x| x < key),
+        );
+        assert_eq!(
+          padded.upper_bound(key),
+          values.partition_point(|&x| x <= key),
+        );
+      }
+    }
+  }
+}
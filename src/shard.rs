@@ -0,0 +1,161 @@
+//! Splitting a search between workers that probe concurrently, for when a
+//! single probe is slow but there are plenty of machines to run them on.
+//!
+//! The coordinator splits the bracket with `shards`, hands each shard to a
+//! worker, which searches it with `Shard::search`, and combines the reports
+//! with `merge_shards`.
+
+use crate::{Betweenable, Bracket, Direction};
+
+/// A part of a bracket, made up of the points strictly between `low` and
+/// `high`, and `high` itself if `includes_high` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shard<X> {
+  pub low: X,
+  pub high: X,
+  pub includes_high: bool,
+}
+
+/// What a worker found in its shard.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShardReport<X, A, B> {
+  /// The shard had no points to probe.
+  Empty,
+  /// Every point was low; this is the highest.
+  Low((X, A)),
+  /// Every point was high; this is the lowest.
+  High((X, B)),
+  /// The transition lies within the shard.
+  Transition(Bracket<X, A, B>),
+}
+
+/// Split the points strictly between `low` and `high` into at most `n`
+/// shards, in order.
+///
+/// The bracket is bisected repeatedly, so shards differ in width by at
+/// most a factor of two, and there are fewer than `n` only if the bracket
+/// is too narrow.
+pub fn shards<X>(low: X, high: X, n: usize) -> Vec<Shard<X>>
+  where
+    X: Betweenable {
+  let mut cuts = vec![low, high];
+  while cuts.len() <= n {
+    let mut split = Vec::with_capacity(cuts.len() * 2);
+    let mut segments = cuts.len() - 1;
+    for pair in cuts.windows(2) {
+      if let [a, b] = *pair {
+        split.push(a);
+        if segments < n {
+          if let Some(x) = X::between(a, b) {
+            split.push(x);
+            segments += 1;
+          }
+        }
+      }
+    }
+    split.push(high);
+    if split.len() == cuts.len() {
+      break;
+    }
+    cuts = split;
+  }
+  let last = cuts.len().saturating_sub(2);
+  cuts.windows(2).enumerate().filter_map(|(i, pair)| {
+    match *pair {
+      [low, high] => Some(Shard { low, high, includes_high: i < last }),
+      _ => None,
+    }
+  }).collect()
+}
+
+impl<X> Shard<X>
+  where
+    X: Betweenable {
+  /// Search the shard, probing only its own points.
+  pub fn search<A, B, F>(&self, mut f: F) -> ShardReport<X, A, B>
+    where
+      F: FnMut(X) -> Direction<A, B> {
+    let mut low = None;
+    let mut high = None;
+    let mut bounds = (self.low, self.high);
+    if self.includes_high {
+      match (f)(self.high) {
+        Direction::Low(a) => return ShardReport::Low((self.high, a)),
+        Direction::High(b) => high = Some((self.high, b)),
+      }
+    }
+    while let Some(x) = X::between(bounds.0, bounds.1) {
+      match (f)(x) {
+        Direction::Low(a) => {
+          bounds.0 = x;
+          low = Some((x, a));
+        },
+        Direction::High(b) => {
+          bounds.1 = x;
+          high = Some((x, b));
+        },
+      }
+    }
+    match (low, high) {
+      (Some(low), Some(high)) => ShardReport::Transition((low, high)),
+      (Some(low), None) => ShardReport::Low(low),
+      (None, Some(high)) => ShardReport::High(high),
+      (None, None) => ShardReport::Empty,
+    }
+  }
+}
+
+/// Combine the reports of the shards of the bracket between `low` and
+/// `high`, in shard order, into the bracket a single search would have
+/// found.
+///
+/// The first shard that isn't entirely low decides the result, so even if
+/// the predicate isn't monotonic, the merge is deterministic.
+pub fn merge_shards<X, A, B, I>(
+    low: (X, A),
+    high: (X, B),
+    reports: I,
+  ) -> Bracket<X, A, B>
+  where
+    I: IntoIterator<Item=ShardReport<X, A, B>> {
+  let mut low = low;
+  for report in reports {
+    match report {
+      ShardReport::Empty => {},
+      ShardReport::Low(a) => low = a,
+      ShardReport::High(b) => return (low, b),
+      ShardReport::Transition(bracket) => return bracket,
+    }
+  }
+  (low, high)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::binary_search;
+
+  #[test]
+  fn sharded_search_matches_binary_search() {
+    for &n in [1, 3, 5, 8].iter() {
+      let shards = shards(0_u32, 1000, n);
+      assert_eq!(shards.len(), n);
+      let first = shards.first().map(|shard| shard.low);
+      let last = shards.last().map(|shard| (shard.high, shard.includes_high));
+      assert_eq!((first, last), (Some(0), Some((1000, false))));
+      for pair in shards.windows(2) {
+        assert_eq!(pair[0].high, pair[1].low);
+        assert!(pair[0].includes_high && pair[0].low < pair[0].high);
+      }
+
+      for &t in [1, 2, 250, 500, 501, 999, 1000].iter() {
+        let f =
+          |x: u32| if x < t { Direction::Low(x) } else { Direction::High(x) };
+        let reports = shards.iter().map(|shard| shard.search(f));
+        let sharded = merge_shards((0, 0), (1000, 1000), reports);
+        assert_eq!(sharded, binary_search((0, 0), (1000, 1000), f));
+      }
+    }
+    assert_eq!(shards(0_u32, 2, 4).len(), 2);
+  }
+}
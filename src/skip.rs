@@ -0,0 +1,122 @@
+//! Searches where some points can't be tested, such as commits that don't
+//! build, in the style of `git bisect skip`.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::Betweenable;
+
+/// The answer of a predicate that can decline to answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome<A, B> {
+  Low(A),
+  High(B),
+  /// The point can't be tested.
+  Skip,
+}
+
+/// The bracket found by `binary_search_skipping`, with the points inside it
+/// that were skipped.
+///
+/// The transition is at one of the skipped points or at `high`, and can't
+/// be narrowed down any further without testing them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Skipped<X, A, B> {
+  pub low: (X, A),
+  pub high: (X, B),
+  /// The skipped points strictly between `low` and `high`, in order.
+  pub skipped: Vec<X>,
+}
+
+/// Like `binary_search`, but `f` can skip a point, and another is probed
+/// instead.
+///
+/// When the midpoint is skipped, the search tries the midpoints of the
+/// halves either side of it, and so on out from the middle, probing the
+/// first that hasn't been skipped. Points that are skipped are never probed
+/// again. The search finishes once every point left between the ends of the
+/// bracket has been skipped.
+pub fn binary_search_skipping<X, A, B, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    mut f: F,
+  ) -> Skipped<X, A, B>
+  where
+    X: Betweenable + Ord,
+    F: FnMut(X) -> Outcome<A, B> {
+  let mut skipped = BTreeSet::new();
+  while let Some(x) = next_probe(low.0, high.0, &skipped) {
+    match (f)(x) {
+      Outcome::Low(a) => {
+        low = (x, a);
+      },
+      Outcome::High(b) => {
+        high = (x, b);
+      },
+      Outcome::Skip => {
+        skipped.insert(x);
+      },
+    }
+  }
+  let skipped =
+    skipped.into_iter().filter(|&x| low.0 < x && x < high.0).collect();
+  Skipped { low, high, skipped }
+}
+
+/// The first point between `low` and `high` that hasn't been skipped,
+/// halving each part of the bracket either side of skipped points in turn.
+pub(crate) fn next_probe<X>(low: X, high: X, skipped: &BTreeSet<X>) -> Option<X>
+  where
+    X: Betweenable + Ord {
+  let mut parts = VecDeque::from([(low, high)]);
+  while let Some((a, b)) = parts.pop_front() {
+    if let Some(x) = X::between(a, b) {
+      if !skipped.contains(&x) {
+        return Some(x);
+      }
+      parts.push_back((a, x));
+      parts.push_back((x, b));
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn probes_around_skipped_points() {
+    // Commits 40 to 49 don't build, and 45 introduced the bug.
+    let mut probes = Vec::new();
+    let result =
+      binary_search_skipping((0_u32, ()), (100, ()), |x| {
+        probes.push(x);
+        if (40..50).contains(&x) {
+          Outcome::Skip
+        } else if x < 45 {
+          Outcome::Low(())
+        } else {
+          Outcome::High(())
+        }
+      });
+    assert_eq!(
+      result,
+      Skipped {
+        low: (39, ()),
+        high: (50, ()),
+        skipped: (40..50).collect(),
+      },
+    );
+    let mut unique = probes.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), probes.len());
+
+    let clean =
+      binary_search_skipping((0_u32, ()), (100, ()), |x| {
+        if x < 45 { Outcome::Low(()) } else { Outcome::High(()) }
+      });
+    assert_eq!((clean.low.0, clean.high.0), (44, 45));
+    assert!(clean.skipped.is_empty());
+  }
+}
@@ -0,0 +1,553 @@
+//! Convenience searches over sorted slices.
+
+use std::cmp::Ordering;
+use std::ops::{Bound, Range, RangeBounds, Sub};
+
+use crate::partition_point;
+
+/// Which element to prefer when two are equally close.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tie {
+  Lower,
+  Higher,
+}
+
+/// Find the element of sorted `slice` closest to `key`, and its index.
+///
+/// `distance(a, b)` measures how far apart two values are, so for integers
+/// it might be `|a, b| a.abs_diff(*b)`. This takes one bisection to find
+/// where `key` would go, and then compares the neighbours either side.
+/// Returns `None` only for an empty slice.
+pub fn closest_by<'a, T, D, F>(
+    slice: &'a [T],
+    key: &T,
+    tie: Tie,
+    mut distance: F,
+  ) -> Option<(usize, &'a T)>
+  where
+    T: Ord,
+    D: Ord,
+    F: FnMut(&T, &T) -> D {
+  let index =
+    partition_point(slice.len(), |i| slice.get(i).is_some_and(|x| x < key));
+  let at = |i: usize| slice.get(i).map(|x| (i, x));
+  let before = index.checked_sub(1).and_then(at);
+  let after = at(index);
+  match (before, after) {
+    (Some(before), Some(after)) => {
+      let d_before = (distance)(before.1, key);
+      let d_after = (distance)(after.1, key);
+      match (d_before.cmp(&d_after), tie) {
+        (Ordering::Less, _) => Some(before),
+        (Ordering::Greater, _) => Some(after),
+        (Ordering::Equal, Tie::Lower) => Some(before),
+        (Ordering::Equal, Tie::Higher) => Some(after),
+      }
+    },
+    (before, after) => before.or(after),
+  }
+}
+
+/// Find the element of sorted `slice` closest to `key`, and its index,
+/// measuring distance by subtraction, as `closest_by` does with
+/// `|a, b| a.abs_diff(*b)` for integers.
+pub fn closest<'a, T, D>(
+    slice: &'a [T],
+    key: &T,
+    tie: Tie,
+  ) -> Option<(usize, &'a T)>
+  where
+    T: Ord + Copy + Sub<Output = D>,
+    D: Ord {
+  closest_by(slice, key, tie, |&a, &b| if a < b { b - a } else { a - b })
+}
+
+/// Count the elements of sorted `slice` that lie in `range`.
+pub fn count_in_range<T, R>(slice: &[T], range: R) -> usize
+  where
+    T: Ord,
+    R: RangeBounds<T> {
+  count_in_range_by_key(slice, range, |x| x)
+}
+
+/// Count the elements of `slice`, sorted by `key`, whose keys lie in
+/// `range`.
+pub fn count_in_range_by_key<T, K, R, F>(
+    slice: &[T],
+    range: R,
+    mut key: F,
+  ) -> usize
+  where
+    K: Ord + ?Sized,
+    R: RangeBounds<K>,
+    F: FnMut(&T) -> &K {
+  let (start, end) = range_indices(slice, &range, &mut key);
+  end.saturating_sub(start)
+}
+
+/// Count the elements of sorted `slice` in each of `ranges`.
+pub fn count_in_ranges<T, R>(slice: &[T], ranges: &[R]) -> Vec<usize>
+  where
+    T: Ord,
+    R: RangeBounds<T> {
+  ranges.iter().map(|range| {
+    let (start, end) = range_indices(slice, range, |x| x);
+    end.saturating_sub(start)
+  }).collect()
+}
+
+/// Find the partition point of `pred` in each of several sorted arrays,
+/// such as the columns of a table that share a sort key, with the elements
+/// either side of it, as `partition_point_with_witness` does.
+///
+/// The searches run in lockstep, one probe per array per round, so arrays
+/// with the same length and transition probe the same index in the same
+/// round, which keeps the memory accesses of aligned columns together.
+pub fn partition_points<'a, T, P>(
+    arrays: &[&'a [T]],
+    mut pred: P,
+  ) -> Vec<(usize, Option<&'a T>, Option<&'a T>)>
+  where
+    P: FnMut(&T) -> bool {
+  let mut brackets: Vec<(usize, usize)> =
+    arrays.iter().map(|array| (0, array.len())).collect();
+  loop {
+    let mut active = false;
+    for (array, bracket) in arrays.iter().zip(brackets.iter_mut()) {
+      let (low, high) = *bracket;
+      if low >= high {
+        continue;
+      }
+      active = true;
+      let mid = low + (high - low) / 2;
+      if array.get(mid).is_some_and(&mut pred) {
+        bracket.0 = mid + 1;
+      } else {
+        bracket.1 = mid;
+      }
+    }
+    if !active {
+      let witnesses =
+        arrays.iter().zip(brackets).map(|(array, (index, _))| {
+          let last_low = index.checked_sub(1).and_then(|i| array.get(i));
+          (index, last_low, array.get(index))
+        });
+      return witnesses.collect();
+    }
+  }
+}
+
+/// Find where each of `queries` would go in sorted `haystack`, as the index
+/// of the first element not less than it, so the query is bracketed by the
+/// elements either side of that index.
+///
+/// The queries are sorted in place, and the results are in the same order.
+/// The middle query is searched for first, and splits both the haystack
+/// and the other queries in two, so each half of the queries only searches
+/// its half of the haystack. This makes `O(m log(n / m))` comparisons for
+/// `m` queries, and probes each part of the haystack while it's in cache.
+pub fn batch_search<T>(haystack: &[T], queries: &mut [T]) -> Vec<usize>
+  where
+    T: Ord {
+  queries.sort();
+  let mut results = vec![0; queries.len()];
+  // Ranges of queries with the range of the haystack their answers are in.
+  let mut stack = vec![(0..queries.len(), 0..haystack.len())];
+  while let Some((within, range)) = stack.pop() {
+    if within.is_empty() {
+      continue;
+    }
+    let middle = within.start + within.len() / 2;
+    let index =
+      match queries.get(middle) {
+        Some(query) => {
+          let part = haystack.get(range.clone()).unwrap_or_default();
+          range.start + partition_point(part.len(), |i| {
+            part.get(i).is_some_and(|x| x < query)
+          })
+        },
+        None => continue,
+      };
+    if let Some(result) = results.get_mut(middle) {
+      *result = index;
+    }
+    stack.push((within.start..middle, range.start..index));
+    stack.push((middle + 1..within.end, index..range.end));
+  }
+  results
+}
+
+/// Find the partition point of `pred` in `slice`, which has grown by
+/// appending since `previous` was found as the partition point of its first
+/// `previous_len` elements, such as a log that's being tailed.
+///
+/// If the old partition point was inside the old elements, appending can't
+/// move it. Otherwise only the appended elements are searched.
+pub fn partition_point_after_append<T, P>(
+    slice: &[T],
+    previous_len: usize,
+    previous: usize,
+    mut pred: P,
+  ) -> usize
+  where
+    P: FnMut(&T) -> bool {
+  if previous < previous_len {
+    return previous;
+  }
+  let start = previous_len.min(slice.len());
+  let appended = slice.get(start..).unwrap_or_default();
+  start + partition_point(appended.len(), |i| {
+    appended.get(i).is_some_and(&mut pred)
+  })
+}
+
+/// Like `slice::partition_point`, but also returning the last element for
+/// which `pred` holds and the first for which it doesn't, if there are any.
+pub fn partition_point_with_witness<T, P>(
+    slice: &[T],
+    mut pred: P,
+  ) -> (usize, Option<&T>, Option<&T>)
+  where
+    P: FnMut(&T) -> bool {
+  let index =
+    partition_point(slice.len(), |i| slice.get(i).is_some_and(&mut pred));
+  let last_low = index.checked_sub(1).and_then(|i| slice.get(i));
+  (index, last_low, slice.get(index))
+}
+
+/// Find the index of the first element that is at least `key`.
+pub fn first_at_least<T>(slice: &[T], key: &T) -> Option<usize>
+  where
+    T: Ord {
+  let index = count_below(slice, key, Ordering::Less);
+  (index < slice.len()).then_some(index)
+}
+
+/// Find the index of the first element that is greater than `key`.
+pub fn first_greater_than<T>(slice: &[T], key: &T) -> Option<usize>
+  where
+    T: Ord {
+  let index = count_below(slice, key, Ordering::Equal);
+  (index < slice.len()).then_some(index)
+}
+
+/// Find the index of the last element that is less than `key`.
+pub fn last_less_than<T>(slice: &[T], key: &T) -> Option<usize>
+  where
+    T: Ord {
+  count_below(slice, key, Ordering::Less).checked_sub(1)
+}
+
+/// Find the index of the last element that is at most `key`.
+pub fn last_at_most<T>(slice: &[T], key: &T) -> Option<usize>
+  where
+    T: Ord {
+  count_below(slice, key, Ordering::Equal).checked_sub(1)
+}
+
+/// An element's index and a reference to it or its value, if there is one.
+pub type Neighbour<'a, V> = Option<(usize, &'a V)>;
+
+/// Find the entries of `entries`, sorted by key, either side of where `key`
+/// would go: the last entry whose key is less than `key`, and the first
+/// whose key is not, each with its index and a reference to its value.
+pub fn search_by_key_value<'a, K, V>(
+    entries: &'a [(K, V)],
+    key: &K,
+  ) -> (Neighbour<'a, V>, Neighbour<'a, V>)
+  where
+    K: Ord {
+  let index =
+    partition_point(entries.len(), |i| {
+      entries.get(i).is_some_and(|(k, _)| k < key)
+    });
+  let at = |i: usize| entries.get(i).map(|(_, v)| (i, v));
+  (index.checked_sub(1).and_then(at), at(index))
+}
+
+/// Find the elements of `slice`, sorted by `key`, either side of where
+/// `target` would go: the last element whose key is less than `target`,
+/// and the first whose key is not, each with its index.
+pub fn binary_search_by_key<'a, T, K, F>(
+    slice: &'a [T],
+    target: &K,
+    mut key: F,
+  ) -> (Neighbour<'a, T>, Neighbour<'a, T>)
+  where
+    K: Ord,
+    F: FnMut(&T) -> K {
+  let index =
+    partition_point(slice.len(), |i| {
+      slice.get(i).is_some_and(|x| (key)(x) < *target)
+    });
+  let at = |i: usize| slice.get(i).map(|x| (i, x));
+  (index.checked_sub(1).and_then(at), at(index))
+}
+
+/// Find the first element for which `compare` doesn't return `Less`, the
+/// way `slice::binary_search_by` compares elements with the target, and its
+/// index, which is the length of the slice if there's no such element.
+pub fn lower_bound_by<T, F>(
+    slice: &[T],
+    mut compare: F,
+  ) -> (usize, Option<&T>)
+  where
+    F: FnMut(&T) -> Ordering {
+  let index =
+    partition_point(slice.len(), |i| {
+      slice.get(i).is_some_and(|x| (compare)(x) == Ordering::Less)
+    });
+  (index, slice.get(index))
+}
+
+/// Like `lower_bound_by`, but finding the first element for which `compare`
+/// returns `Greater`.
+pub fn upper_bound_by<T, F>(
+    slice: &[T],
+    mut compare: F,
+  ) -> (usize, Option<&T>)
+  where
+    F: FnMut(&T) -> Ordering {
+  let index =
+    partition_point(slice.len(), |i| {
+      slice.get(i).is_some_and(|x| (compare)(x) != Ordering::Greater)
+    });
+  (index, slice.get(index))
+}
+
+/// Find the run of elements for which `compare` returns `Equal`, as indices
+/// and as a subslice.
+pub fn equal_range_by<T, F>(
+    slice: &[T],
+    mut compare: F,
+  ) -> (Range<usize>, &[T])
+  where
+    F: FnMut(&T) -> Ordering {
+  let (start, _) = lower_bound_by(slice, &mut compare);
+  let rest = slice.get(start..).unwrap_or_default();
+  let (len, _) = upper_bound_by(rest, compare);
+  let range = start..start + len;
+  (range.clone(), slice.get(range).unwrap_or_default())
+}
+
+/// Count the leading elements of sorted `slice` that compare to `key` as
+/// at most `limit`: with `Less`, those that are less than `key`, and with
+/// `Equal`, those that are at most `key`.
+fn count_below<T>(slice: &[T], key: &T, limit: Ordering) -> usize
+  where
+    T: Ord {
+  partition_point(slice.len(), |i| {
+    slice.get(i).is_some_and(|x| x.cmp(key) <= limit)
+  })
+}
+
+/// Find the indices where `range` starts and ends in `slice`, which is
+/// sorted by `key`.
+pub(crate) fn range_indices<T, K, R, F>(
+    slice: &[T],
+    range: &R,
+    mut key: F,
+  ) -> (usize, usize)
+  where
+    K: Ord + ?Sized,
+    R: RangeBounds<K>,
+    F: FnMut(&T) -> &K {
+  let mut bound = |bound: Bound<&K>, unbounded: usize| {
+    let len = slice.len();
+    match bound {
+      Bound::Included(k) => {
+        partition_point(len, |i| slice.get(i).is_some_and(|x| (key)(x) < k))
+      },
+      Bound::Excluded(k) => {
+        partition_point(len, |i| slice.get(i).is_some_and(|x| (key)(x) <= k))
+      },
+      Bound::Unbounded => unbounded,
+    }
+  };
+  let start = (bound)(range.start_bound(), 0);
+  let end =
+    match range.end_bound() {
+      Bound::Included(k) => (bound)(Bound::Excluded(k), slice.len()),
+      Bound::Excluded(k) => (bound)(Bound::Included(k), slice.len()),
+      Bound::Unbounded => slice.len(),
+    };
+  (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+
+  #[test]
+  fn closest_by_test() {
+    let values = [1_u32, 4, 8, 8, 20];
+    let distance = |a: &u32, b: &u32| a.abs_diff(*b);
+    let closest = |key, tie| closest_by(&values, &key, tie, distance);
+    assert_eq!(closest(0, Tie::Lower), Some((0, &1)));
+    assert_eq!(closest(5, Tie::Lower), Some((1, &4)));
+    assert_eq!(closest(6, Tie::Lower), Some((1, &4)));
+    assert_eq!(closest(6, Tie::Higher), Some((2, &8)));
+    assert_eq!(closest(8, Tie::Lower), Some((2, &8)));
+    assert_eq!(closest(14, Tie::Higher), Some((4, &20)));
+    assert_eq!(closest(99, Tie::Lower), Some((4, &20)));
+    assert_eq!(closest_by(&[], &3, Tie::Lower, distance), None);
+
+    assert_eq!(super::closest(&values, &6, Tie::Lower), Some((1, &4)));
+    assert_eq!(super::closest(&values, &6, Tie::Higher), Some((2, &8)));
+    assert_eq!(super::closest(&[-5_i8, 5], &0, Tie::Higher), Some((1, &5)));
+    let times = [Duration::from_secs(1), Duration::from_secs(10)];
+    let key = Duration::from_secs(4);
+    assert_eq!(super::closest(&times, &key, Tie::Lower), Some((0, &times[0])));
+  }
+
+  #[test]
+  fn count_in_range_test() {
+    let values = [1, 3, 3, 3, 5, 8, 13];
+    assert_eq!(count_in_range(&values, 3..=5), 4);
+    assert_eq!(count_in_range(&values, 3..5), 3);
+    assert_eq!(count_in_range(&values, ..3), 1);
+    assert_eq!(count_in_range(&values, 4..), 3);
+    assert_eq!(count_in_range(&values, ..), 7);
+    let between = (Bound::Excluded(5), Bound::Excluded(8));
+    assert_eq!(count_in_range(&values, between), 0);
+    assert_eq!(
+      count_in_ranges(&values, &[0..2, 2..4, 4..14]),
+      vec![1, 3, 3],
+    );
+
+    let people = [("ann", 23), ("bob", 31), ("cat", 35), ("dan", 40)];
+    assert_eq!(count_in_range_by_key(&people, 30..=40, |p| &p.1), 3);
+    assert_eq!(count_in_range_by_key(&people, "b"..="c", |p| &p.0), 1);
+  }
+
+  #[test]
+  fn partition_points_test() {
+    let a = [1, 2, 3, 4, 5, 6];
+    let b = [2, 4, 6, 8, 10, 12];
+    let c = [0, 0, 0];
+    let empty: [i32; 0] = [];
+    let arrays: [&[i32]; 4] = [&a, &b, &c, &empty];
+    assert_eq!(
+      partition_points(&arrays, |&x| x < 5),
+      vec![
+        (4, Some(&4), Some(&5)),
+        (2, Some(&4), Some(&6)),
+        (3, Some(&0), None),
+        (0, None, None),
+      ],
+    );
+  }
+
+  #[test]
+  fn search_by_key_value_test() {
+    let rates = [(0, "free"), (10, "basic"), (100, "premium")];
+    assert_eq!(search_by_key_value(&rates, &50), (
+      Some((1, &"basic")),
+      Some((2, &"premium")),
+    ));
+    assert_eq!(search_by_key_value(&rates, &10), (
+      Some((0, &"free")),
+      Some((1, &"basic")),
+    ));
+    assert_eq!(search_by_key_value(&rates, &0), (None, Some((0, &"free"))));
+    assert_eq!(
+      search_by_key_value(&rates, &500),
+      (Some((2, &"premium")), None),
+    );
+  }
+
+  #[test]
+  fn binary_search_by_key_test() {
+    let people = [("ann", 23), ("bob", 31), ("cat", 31), ("dan", 40)];
+    let age = |p: &(&str, u32)| p.1;
+    assert_eq!(
+      binary_search_by_key(&people, &31, age),
+      (Some((0, &people[0])), Some((1, &people[1]))),
+    );
+    assert_eq!(
+      binary_search_by_key(&people, &35, age),
+      (Some((2, &people[2])), Some((3, &people[3]))),
+    );
+    assert_eq!(binary_search_by_key(&people, &50, age).1, None);
+    assert_eq!(binary_search_by_key(&people, &0, age).0, None);
+  }
+
+  #[test]
+  fn bounds_by_comparator() {
+    let values = [1, 3, 3, 3, 5];
+    let to = |key: i32| move |x: &i32| x.cmp(&key);
+    assert_eq!(lower_bound_by(&values, to(3)), (1, Some(&3)));
+    assert_eq!(upper_bound_by(&values, to(3)), (4, Some(&5)));
+    assert_eq!(lower_bound_by(&values, to(9)), (5, None));
+    assert_eq!(upper_bound_by(&values, to(0)), (0, Some(&1)));
+    assert_eq!(equal_range_by(&values, to(3)), (1..4, &values[1..4]));
+    assert_eq!(equal_range_by(&values, to(4)), (4..4, &values[4..4]));
+    assert_eq!(equal_range_by(&[], to(4)), (0..0, &[][..]));
+  }
+
+  #[test]
+  fn batch_search_test() {
+    let haystack: Vec<u32> = (0..100).map(|x| x * 3).collect();
+    let mut queries = vec![299, 0, 5, 150, 6, 6, 1000, 151];
+    let results = batch_search(&haystack, &mut queries);
+    assert_eq!(queries, vec![0, 5, 6, 6, 150, 151, 299, 1000]);
+    let expected: Vec<usize> =
+      queries.iter().map(|q| haystack.partition_point(|x| x < q)).collect();
+    assert_eq!(results, expected);
+    assert_eq!(batch_search(&[], &mut [3, 1]), vec![0, 0]);
+  }
+
+  #[test]
+  fn partition_point_after_append_test() {
+    let mut log = vec![1, 4, 9];
+    let below = |key: u32| move |x: &u32| *x < key;
+    let mut found = log.partition_point(below(5));
+    assert_eq!(found, 2);
+    let mut all = log.partition_point(below(20));
+    assert_eq!(all, 3);
+    let len = log.len();
+    log.extend([16, 25, 36]);
+    found = partition_point_after_append(&log, len, found, below(5));
+    all = partition_point_after_append(&log, len, all, below(20));
+    assert_eq!((found, all), (2, 4));
+    assert_eq!(partition_point_after_append(&log, 9, 9, below(50)), 6);
+  }
+
+  #[test]
+  fn partition_point_with_witness_test() {
+    let values = [1, 4, 9, 16];
+    assert_eq!(
+      partition_point_with_witness(&values, |&x| x < 5),
+      (2, Some(&4), Some(&9)),
+    );
+    assert_eq!(
+      partition_point_with_witness(&values, |&x| x < 0),
+      (0, None, Some(&1)),
+    );
+    assert_eq!(
+      partition_point_with_witness(&values, |&x| x < 99),
+      (4, Some(&16), None),
+    );
+  }
+
+  #[test]
+  fn duplicate_key_bounds() {
+    let values = [1, 3, 3, 3, 5];
+    let all = |key| {
+      (
+        first_at_least(&values, &key),
+        first_greater_than(&values, &key),
+        last_less_than(&values, &key),
+        last_at_most(&values, &key),
+      )
+    };
+    assert_eq!(all(0), (Some(0), Some(0), None, None));
+    assert_eq!(all(1), (Some(0), Some(1), None, Some(0)));
+    assert_eq!(all(3), (Some(1), Some(4), Some(0), Some(3)));
+    assert_eq!(all(4), (Some(4), Some(4), Some(3), Some(3)));
+    assert_eq!(all(5), (Some(4), None, Some(3), Some(4)));
+    assert_eq!(all(6), (None, None, Some(4), Some(4)));
+  }
+}
@@ -0,0 +1,514 @@
+//! Sorted vectors, as compact, cache-friendly alternatives to `BTreeSet`
+//! and `BTreeMap` for collections that are read far more than written.
+
+use std::iter::FromIterator;
+use std::ops::{Range, RangeBounds};
+
+use crate::cursor::Cursor;
+use crate::partition_point;
+use crate::slice::range_indices;
+
+/// What to do when inserting an element equal to one already present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Duplicate {
+  /// Leave the collection alone and hand the new element back.
+  Reject,
+  /// Keep the element already there and drop the new one.
+  KeepFirst,
+  /// Replace the elements already there with the new one.
+  KeepLast,
+}
+
+/// A vector kept in sorted order. Equal elements are kept in the order they
+/// were inserted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVec<T> {
+  items: Vec<T>,
+}
+
+impl<T> Default for SortedVec<T> {
+  fn default() -> Self {
+    SortedVec { items: Vec::new() }
+  }
+}
+
+impl<T> SortedVec<T>
+  where
+    T: Ord {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sort `items` to make a sorted vector.
+  pub fn from_vec(mut items: Vec<T>) -> Self {
+    items.sort();
+    SortedVec { items }
+  }
+
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  pub fn as_slice(&self) -> &[T] {
+    &self.items
+  }
+
+  pub fn into_vec(self) -> Vec<T> {
+    self.items
+  }
+
+  /// Find the index of the first element that is not less than `key`.
+  pub fn lower_bound(&self, key: &T) -> usize {
+    let items = &self.items;
+    partition_point(items.len(), |i| items.get(i).is_some_and(|x| x < key))
+  }
+
+  /// Find the index of the first element that is greater than `key`.
+  pub fn upper_bound(&self, key: &T) -> usize {
+    let items = &self.items;
+    partition_point(items.len(), |i| items.get(i).is_some_and(|x| x <= key))
+  }
+
+  /// The indices of the elements equal to `key`.
+  pub fn equal_range(&self, key: &T) -> Range<usize> {
+    let start = self.lower_bound(key);
+    start..self.upper_bound(key).max(start)
+  }
+
+  pub fn contains(&self, key: &T) -> bool {
+    self.items.get(self.lower_bound(key)).is_some_and(|x| x == key)
+  }
+
+  /// The elements that lie in `range`.
+  pub fn range<R>(&self, range: R) -> &[T]
+    where
+      R: RangeBounds<T> {
+    let (start, end) = range_indices(&self.items, &range, |x| x);
+    self.items.get(start..end.max(start)).unwrap_or_default()
+  }
+
+  /// Insert `item` after any elements equal to it, returning its index.
+  pub fn insert(&mut self, item: T) -> usize {
+    let index = self.upper_bound(&item);
+    self.items.insert(index, item);
+    index
+  }
+
+  /// Insert `item`, handling any elements equal to it by `policy`.
+  ///
+  /// Returns the index of the element left equal to `item`, or gives
+  /// `item` back if it was rejected. Afterwards there is at most one
+  /// element equal to `item`, unless it was rejected or there were already
+  /// several and the first was kept.
+  pub fn insert_dedup(
+      &mut self,
+      item: T,
+      policy: Duplicate,
+    ) -> Result<usize, T> {
+    let range = self.equal_range(&item);
+    if range.is_empty() {
+      self.items.insert(range.start, item);
+      return Ok(range.start);
+    }
+    match policy {
+      Duplicate::Reject => Err(item),
+      Duplicate::KeepFirst => Ok(range.start),
+      Duplicate::KeepLast => {
+        let start = range.start;
+        self.items.splice(range, std::iter::once(item));
+        Ok(start)
+      },
+    }
+  }
+
+  /// Insert `item`, or if there are elements equal to it, merge it into the
+  /// first of them with `merge`, returning the index either way.
+  ///
+  /// The merged element must still compare equal to `item`.
+  pub fn upsert<F>(&mut self, item: T, merge: F) -> usize
+    where
+      F: FnOnce(&mut T, T) {
+    let index = self.lower_bound(&item);
+    match self.items.get_mut(index) {
+      Some(existing) if *existing == item => (merge)(existing, item),
+      _ => self.items.insert(index, item),
+    }
+    index
+  }
+
+  /// Insert every element of `batch`, each after any existing elements
+  /// equal to it.
+  ///
+  /// The batch is sorted and then merged in, in one pass over both, so
+  /// this takes `O(n + m log m)` rather than the `O(n m)` of inserting one
+  /// by one. The merge gallops over runs from either side, so it makes far
+  /// fewer comparisons than that when the batch is clustered.
+  pub fn merge_insert(&mut self, mut batch: Vec<T>) {
+    batch.sort();
+    let runs = merge_runs(&self.items, &batch);
+    let mut merged = Vec::with_capacity(self.items.len() + batch.len());
+    let mut old = std::mem::take(&mut self.items).into_iter();
+    let mut new = batch.into_iter();
+    for (from_old, n) in runs {
+      if from_old {
+        merged.extend(old.by_ref().take(n));
+      } else {
+        merged.extend(new.by_ref().take(n));
+      }
+    }
+    self.items = merged;
+  }
+
+  /// Insert every element of `iter`, as `merge_insert`.
+  pub fn extend_sorted<I>(&mut self, iter: I)
+    where
+      I: IntoIterator<Item = T> {
+    self.merge_insert(iter.into_iter().collect());
+  }
+
+  /// Remove the first element equal to `key`, if there is one.
+  pub fn remove(&mut self, key: &T) -> Option<T> {
+    let index = self.lower_bound(key);
+    if self.items.get(index)? == key {
+      Some(self.items.remove(index))
+    } else {
+      None
+    }
+  }
+}
+
+impl<T> FromIterator<T> for SortedVec<T>
+  where
+    T: Ord {
+  fn from_iter<I>(iter: I) -> Self
+    where
+      I: IntoIterator<Item = T> {
+    Self::from_vec(iter.into_iter().collect())
+  }
+}
+
+impl<T> Extend<T> for SortedVec<T>
+  where
+    T: Ord {
+  fn extend<I>(&mut self, iter: I)
+    where
+      I: IntoIterator<Item = T> {
+    self.extend_sorted(iter);
+  }
+}
+
+/// The runs taken alternately from `old` and `new`, as whether the run is
+/// from `old` and its length, that merge them with equal elements of `old`
+/// first.
+fn merge_runs<T>(old: &[T], new: &[T]) -> Vec<(bool, usize)>
+  where
+    T: Ord {
+  let mut runs = Vec::new();
+  let mut old_cursor = Cursor::new(old);
+  let mut new_cursor = Cursor::new(new);
+  let (mut i, mut j) = (0, 0);
+  while let Some(next) = new.get(j) {
+    let end = old_cursor.upper_bound(next);
+    if end > i {
+      runs.push((true, end - i));
+      i = end;
+    }
+    let end =
+      match old.get(i) {
+        Some(next) => new_cursor.lower_bound(next),
+        None => new.len(),
+      };
+    runs.push((false, end - j));
+    j = end;
+  }
+  runs.push((true, old.len() - i));
+  runs
+}
+
+/// A map stored as a vector of key-value pairs sorted by key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedMap<K, V> {
+  entries: Vec<(K, V)>,
+}
+
+impl<K, V> Default for SortedMap<K, V> {
+  fn default() -> Self {
+    SortedMap { entries: Vec::new() }
+  }
+}
+
+impl<K, V> SortedMap<K, V>
+  where
+    K: Ord {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sort `entries` by key to make a map. Where a key appears more than
+  /// once, the last value for it is kept, as with repeated inserts.
+  pub fn from_vec(mut entries: Vec<(K, V)>) -> Self {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.reverse();
+    entries.dedup_by(|a, b| a.0 == b.0);
+    entries.reverse();
+    SortedMap { entries }
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn as_slice(&self) -> &[(K, V)] {
+    &self.entries
+  }
+
+  pub fn into_vec(self) -> Vec<(K, V)> {
+    self.entries
+  }
+
+  /// Find the index of `key`, or where it would be inserted.
+  fn search(&self, key: &K) -> Result<usize, usize> {
+    let entries = &self.entries;
+    let index =
+      partition_point(entries.len(), |i| {
+        entries.get(i).is_some_and(|(k, _)| k < key)
+      });
+    match entries.get(index) {
+      Some((k, _)) if k == key => Ok(index),
+      _ => Err(index),
+    }
+  }
+
+  pub fn contains_key(&self, key: &K) -> bool {
+    self.search(key).is_ok()
+  }
+
+  pub fn get(&self, key: &K) -> Option<&V> {
+    let index = self.search(key).ok()?;
+    self.entries.get(index).map(|(_, v)| v)
+  }
+
+  pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+    let index = self.search(key).ok()?;
+    self.entries.get_mut(index).map(|(_, v)| v)
+  }
+
+  /// The entries whose keys lie in `range`.
+  pub fn range<R>(&self, range: R) -> &[(K, V)]
+    where
+      R: RangeBounds<K> {
+    let (start, end) = range_indices(&self.entries, &range, |(k, _)| k);
+    self.entries.get(start..end.max(start)).unwrap_or_default()
+  }
+
+  /// Insert a value for `key`, returning the value it replaces.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    match self.search(&key) {
+      Ok(index) => {
+        self.entries.get_mut(index).map(|(_, v)| std::mem::replace(v, value))
+      },
+      Err(index) => {
+        self.entries.insert(index, (key, value));
+        None
+      },
+    }
+  }
+
+  /// Insert a value for `key`, handling an existing value by `policy`.
+  ///
+  /// Gives the entry back if it was rejected. Replacing the value is the
+  /// same as `insert`, except the old value is dropped.
+  pub fn insert_dedup(
+      &mut self,
+      key: K,
+      value: V,
+      policy: Duplicate,
+    ) -> Result<(), (K, V)> {
+    match (self.search(&key), policy) {
+      (Err(index), _) => self.entries.insert(index, (key, value)),
+      (Ok(_), Duplicate::Reject) => return Err((key, value)),
+      (Ok(_), Duplicate::KeepFirst) => {},
+      (Ok(index), Duplicate::KeepLast) => {
+        if let Some(entry) = self.entries.get_mut(index) {
+          entry.1 = value;
+        }
+      },
+    }
+    Ok(())
+  }
+
+  /// Insert a value for `key`, or if it has one, merge `value` into it with
+  /// `merge`.
+  pub fn upsert<F>(&mut self, key: K, value: V, merge: F)
+    where
+      F: FnOnce(&mut V, V) {
+    match self.search(&key) {
+      Ok(index) => {
+        if let Some((_, existing)) = self.entries.get_mut(index) {
+          (merge)(existing, value);
+        }
+      },
+      Err(index) => self.entries.insert(index, (key, value)),
+    }
+  }
+
+  pub fn remove(&mut self, key: &K) -> Option<V> {
+    let index = self.search(key).ok()?;
+    Some(self.entries.remove(index).1)
+  }
+
+  /// Look up `key` for in-place insertion or modification.
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+    let index = self.search(&key);
+    Entry { map: self, key, index }
+  }
+}
+
+impl<K, V> FromIterator<(K, V)> for SortedMap<K, V>
+  where
+    K: Ord {
+  fn from_iter<I>(iter: I) -> Self
+    where
+      I: IntoIterator<Item = (K, V)> {
+    Self::from_vec(iter.into_iter().collect())
+  }
+}
+
+/// A key in a `SortedMap` that may or may not have a value yet.
+pub struct Entry<'a, K, V> {
+  map: &'a mut SortedMap<K, V>,
+  key: K,
+  index: Result<usize, usize>,
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  /// Whether the key already has a value.
+  pub fn is_occupied(&self) -> bool {
+    self.index.is_ok()
+  }
+
+  /// Modify the value, if there is one.
+  pub fn and_modify<F>(self, f: F) -> Self
+    where
+      F: FnOnce(&mut V) {
+    if let Ok(index) = self.index {
+      if let Some((_, v)) = self.map.entries.get_mut(index) {
+        (f)(v);
+      }
+    }
+    self
+  }
+
+  /// Get the value, inserting `value` if there isn't one.
+  pub fn or_insert(self, value: V) -> &'a mut V {
+    self.or_insert_with(|| value)
+  }
+
+  /// Get the value, inserting the result of `f` if there isn't one.
+  // `index` is where `search` found the key, and the map has been borrowed
+  // mutably ever since, so it's still in bounds.
+  #[allow(clippy::indexing_slicing)]
+  pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+      F: FnOnce() -> V {
+    let entries = &mut self.map.entries;
+    match self.index {
+      Ok(index) => &mut entries[index].1,
+      Err(index) => &mut entries.insert_mut(index, (self.key, (f)())).1,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sorted_vec_test() {
+    let mut values: SortedVec<i32> = vec![5, 1, 4, 1].into_iter().collect();
+    assert_eq!(values.as_slice(), &[1, 1, 4, 5]);
+    assert_eq!(values.insert(3), 2);
+    assert_eq!(values.insert(1), 2);
+    assert!(values.contains(&4));
+    assert!(!values.contains(&2));
+    assert_eq!(values.range(2..5), &[3, 4]);
+    assert_eq!(values.remove(&1), Some(1));
+    assert_eq!(values.remove(&9), None);
+    assert_eq!(values.into_vec(), vec![1, 1, 3, 4, 5]);
+  }
+
+  #[test]
+  fn merge_insert_test() {
+    let mut values = SortedVec::from_vec(vec![(1, 'a'), (3, 'a'), (5, 'a')]);
+    values.merge_insert(vec![]);
+    assert_eq!(values.len(), 3);
+    let mut batch = vec![(6, 'b'), (0, 'b'), (3, 'b'), (3, 'a'), (3, 'c')];
+    batch.extend((7..10).map(|x| (x, 'b')));
+    values.extend(batch);
+    let mut expected = vec![(1, 'a'), (3, 'a'), (5, 'a')];
+    expected.extend(vec![(6, 'b'), (0, 'b'), (3, 'b'), (3, 'a'), (3, 'c')]);
+    expected.extend((7..10).map(|x| (x, 'b')));
+    expected.sort();
+    assert_eq!(values.as_slice(), expected.as_slice());
+
+    let mut values = SortedVec::new();
+    values.extend_sorted(vec![2, 1]);
+    values.extend_sorted(vec![0, 5, 1]);
+    assert_eq!(values.into_vec(), vec![0, 1, 1, 2, 5]);
+  }
+
+  #[test]
+  fn sorted_map_test() {
+    let mut map: SortedMap<&str, u32> =
+      vec![("b", 2), ("a", 1), ("c", 3), ("a", 10)].into_iter().collect();
+    assert_eq!(map.as_slice(), &[("a", 10), ("b", 2), ("c", 3)]);
+    assert_eq!(map.get(&"a"), Some(&10));
+    assert_eq!(map.insert("b", 20), Some(2));
+    assert_eq!(map.insert("ab", 5), None);
+    assert_eq!(map.range("a".."b"), &[("a", 10), ("ab", 5)]);
+    *map.entry("d").or_insert(0) += 4;
+    *map.entry("d").and_modify(|v| *v *= 10).or_insert(0) += 1;
+    assert_eq!(map.get(&"d"), Some(&41));
+    assert!(map.entry("c").is_occupied());
+    assert_eq!(map.remove(&"c"), Some(3));
+    assert_eq!(map.len(), 4);
+  }
+
+  #[test]
+  fn duplicate_policies() {
+    let mut values = SortedVec::new();
+    assert_eq!(values.insert_dedup(3, Duplicate::Reject), Ok(0));
+    assert_eq!(values.insert_dedup(3, Duplicate::Reject), Err(3));
+    assert_eq!(values.insert_dedup(3, Duplicate::KeepFirst), Ok(0));
+    values.insert(3);
+    values.insert(5);
+    assert_eq!(values.equal_range(&3), 0..2);
+    assert_eq!(values.insert_dedup(3, Duplicate::KeepLast), Ok(0));
+    assert_eq!(values.as_slice(), &[3, 5]);
+    assert_eq!(values.upsert(1, |_, _| unreachable!()), 0);
+    assert_eq!(values.as_slice(), &[1, 3, 5]);
+
+    let mut map = SortedMap::new();
+    assert_eq!(map.insert_dedup("a", 1, Duplicate::Reject), Ok(()));
+    assert_eq!(map.insert_dedup("a", 2, Duplicate::Reject), Err(("a", 2)));
+    assert_eq!(map.insert_dedup("a", 2, Duplicate::KeepFirst), Ok(()));
+    assert_eq!(map.get(&"a"), Some(&1));
+    assert_eq!(map.insert_dedup("a", 2, Duplicate::KeepLast), Ok(()));
+    assert_eq!(map.get(&"a"), Some(&2));
+    map.upsert("a", 5, |v, new| *v += new);
+    map.upsert("b", 5, |v, new| *v += new);
+    assert_eq!(map.as_slice(), &[("a", 7), ("b", 5)]);
+  }
+}
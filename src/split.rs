@@ -0,0 +1,95 @@
+//! Splitting a sequence into contiguous parts, by bisecting the largest
+//! part allowed and checking greedily whether the split is possible.
+
+use std::ops::Add;
+
+use crate::{binary_search, Betweenable, Direction};
+
+/// Split `weights` into at most `k` contiguous parts so that the largest
+/// part sum is as small as possible.
+///
+/// Returns the largest part sum and the indices at which each part after
+/// the first starts. There are `min(k, weights.len())` parts, none of them
+/// empty. Returns `None` if `k` is zero, or if `weights` is empty.
+pub fn min_max_partition<T>(
+    weights: &[T],
+    k: usize,
+  ) -> Option<(T, Vec<usize>)>
+  where
+    T: Betweenable + Ord + Add<Output = T> + From<u8> {
+  if k == 0 {
+    return None;
+  }
+  let heaviest = *weights.iter().max()?;
+  let total = weights.iter().fold(T::from(0_u8), |sum, &w| sum + w);
+  let fits = |limit: T| greedy_cuts(weights, limit).len() < k;
+  let limit =
+    if fits(heaviest) {
+      heaviest
+    } else {
+      let (_, (limit, ())) =
+        binary_search((heaviest, ()), (total, ()), |limit| {
+          if fits(limit) {
+            Direction::High(())
+          } else {
+            Direction::Low(())
+          }
+        });
+      limit
+    };
+
+  // Cutting a part further can't make the largest one larger, so use up
+  // any parts the greedy split left over on the earliest uncut positions.
+  let mut cuts = greedy_cuts(weights, limit);
+  let parts = k.min(weights.len());
+  let mut i = 1;
+  while cuts.len() + 1 < parts {
+    if let Err(at) = cuts.binary_search(&i) {
+      cuts.insert(at, i);
+    }
+    i += 1;
+  }
+  Some((limit, cuts))
+}
+
+/// Cut `weights` into parts no heavier than `limit`, taking as much as will
+/// fit into each part in turn, and return where each new part starts. Every
+/// element must be no heavier than `limit` on its own.
+fn greedy_cuts<T>(weights: &[T], limit: T) -> Vec<usize>
+  where
+    T: Copy + Ord + Add<Output = T> {
+  let mut cuts = Vec::new();
+  let mut part: Option<T> = None;
+  for (i, &w) in weights.iter().enumerate() {
+    part =
+      match part {
+        Some(sum) if sum + w <= limit => Some(sum + w),
+        Some(_) => {
+          cuts.push(i);
+          Some(w)
+        },
+        None => Some(w),
+      };
+  }
+  cuts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn min_max_partition_test() {
+    let weights = [7_u32, 2, 5, 10, 8];
+    assert_eq!(min_max_partition(&weights, 2), Some((18, vec![3])));
+    assert_eq!(min_max_partition(&weights, 3), Some((14, vec![3, 4])));
+    assert_eq!(min_max_partition(&weights, 1), Some((32, vec![])));
+    assert_eq!(
+      min_max_partition(&weights, 9),
+      Some((10, vec![1, 2, 3, 4])),
+    );
+    assert_eq!(min_max_partition(&[1_u32, 1, 1, 1], 3), Some((2, vec![1, 2])));
+    assert_eq!(min_max_partition::<u32>(&[], 2), None);
+    assert_eq!(min_max_partition(&weights, 0), None);
+  }
+}
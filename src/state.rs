@@ -0,0 +1,280 @@
+//! Searches driven from outside, one probe at a time.
+//!
+//! `binary_search` calls the predicate itself, which doesn't suit a person
+//! answering each probe through a UI, or a scheduler that wants to run
+//! other work between probes. A `SearchState` is the same search turned
+//! inside out: ask it what to probe, work out the answer however and
+//! whenever suits, and feed the answer back.
+//!
+//! With the `serde` feature, a state can also be saved after each probe
+//! and resumed later, in another process or on another machine, which
+//! lets a bisection that takes hours survive restarts.
+
+use crate::{Betweenable, Bracket, Direction};
+
+/// The bracket of a search in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchState<X, A, B> {
+  low: (X, A),
+  high: (X, B),
+}
+
+impl<X, A, B> SearchState<X, A, B>
+  where
+    X: Betweenable {
+  /// Start a search between `low` and `high`, as with `binary_search`.
+  pub fn new(low: (X, A), high: (X, B)) -> Self {
+    SearchState { low, high }
+  }
+
+  /// The point to probe next, or `None` once the search is finished.
+  pub fn next_probe(&self) -> Option<X> {
+    X::between(self.low.0, self.high.0)
+  }
+
+  /// Narrow the bracket with the answer for `next_probe`. Does nothing if
+  /// the search is finished.
+  pub fn feed(&mut self, direction: Direction<A, B>) {
+    if let Some(x) = self.next_probe() {
+      match direction {
+        Direction::Low(a) => {
+          self.low = (x, a);
+        },
+        Direction::High(b) => {
+          self.high = (x, b);
+        },
+      }
+    }
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.next_probe().is_none()
+  }
+
+  /// The largest low found so far, with its witness.
+  pub fn low(&self) -> &(X, A) {
+    &self.low
+  }
+
+  /// The smallest high found so far, with its witness.
+  pub fn high(&self) -> &(X, B) {
+    &self.high
+  }
+
+  /// The bracket found so far, which is the result of the search once it's
+  /// finished.
+  pub fn into_bracket(self) -> Bracket<X, A, B> {
+    (self.low, self.high)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<X, A, B> SearchState<X, A, B>
+  where
+    X: Betweenable + serde::Serialize,
+    A: serde::Serialize,
+    B: serde::Serialize {
+  /// Save the search with `serializer`, in any format `serde` supports, to
+  /// carry on with `resume`.
+  pub fn checkpoint<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: serde::Serializer {
+    serde::Serialize::serialize(self, serializer)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, X, A, B> SearchState<X, A, B>
+  where
+    X: Betweenable + serde::Deserialize<'de>,
+    A: serde::Deserialize<'de>,
+    B: serde::Deserialize<'de> {
+  /// Load a search saved by `checkpoint`, which carries on from the probe
+  /// after the last one fed to it.
+  pub fn resume<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: serde::Deserializer<'de> {
+    serde::Deserialize::deserialize(deserializer)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::binary_search;
+
+  #[test]
+  fn matches_binary_search() {
+    let f = |x: i32| {
+      if x * x < 200 {
+        Direction::Low(x)
+      } else {
+        Direction::High(-x)
+      }
+    };
+    let mut state = SearchState::new((0, 0), (100, -100));
+    let mut probes = Vec::new();
+    while let Some(x) = state.next_probe() {
+      probes.push(x);
+      state.feed(f(x));
+    }
+    assert!(state.is_finished());
+    assert_eq!(state.low(), &(14, 14));
+    state.feed(Direction::Low(0));
+    assert_eq!(state.into_bracket(), binary_search((0, 0), (100, -100), f));
+    assert_eq!(probes, vec![50, 25, 12, 18, 15, 13, 14]);
+  }
+
+  /// A serializer that writes the integers in a value in order, and
+  /// rejects everything but the structs and tuples around them.
+  #[cfg(feature = "serde")]
+  mod integers {
+    use serde::de::value::Error;
+    use serde::ser::{self, Error as _, Impossible, Serialize};
+
+    pub struct Integers(pub Vec<i64>);
+
+    type Unsupported = Impossible<(), Error>;
+
+    macro_rules! unsupported {
+      ($(fn $name:ident$(<$t:ident>)?(self $(, $arg:ty)*) -> $ok:ty;)*) => {
+        $(
+          fn $name$(<$t: Serialize + ?Sized>)?(
+              self,
+              $(_: $arg),*
+            ) -> Result<$ok, Error> {
+            Err(Error::custom("unsupported"))
+          }
+        )*
+      };
+    }
+
+    impl ser::Serializer for &mut Integers {
+      type Ok = ();
+      type Error = Error;
+      type SerializeSeq = Unsupported;
+      type SerializeTuple = Self;
+      type SerializeTupleStruct = Unsupported;
+      type SerializeTupleVariant = Unsupported;
+      type SerializeMap = Unsupported;
+      type SerializeStruct = Self;
+      type SerializeStructVariant = Unsupported;
+
+      fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.0.push(v.into());
+        Ok(())
+      }
+
+      fn serialize_tuple(self, _: usize) -> Result<Self, Error> {
+        Ok(self)
+      }
+
+      fn serialize_struct(
+          self,
+          _: &'static str,
+          _: usize,
+        ) -> Result<Self, Error> {
+        Ok(self)
+      }
+
+      unsupported! {
+        fn serialize_bool(self, bool) -> ();
+        fn serialize_i8(self, i8) -> ();
+        fn serialize_i16(self, i16) -> ();
+        fn serialize_i64(self, i64) -> ();
+        fn serialize_u8(self, u8) -> ();
+        fn serialize_u16(self, u16) -> ();
+        fn serialize_u32(self, u32) -> ();
+        fn serialize_u64(self, u64) -> ();
+        fn serialize_f32(self, f32) -> ();
+        fn serialize_f64(self, f64) -> ();
+        fn serialize_char(self, char) -> ();
+        fn serialize_str(self, &str) -> ();
+        fn serialize_bytes(self, &[u8]) -> ();
+        fn serialize_none(self) -> ();
+        fn serialize_some<T>(self, &T) -> ();
+        fn serialize_unit(self) -> ();
+        fn serialize_unit_struct(self, &str) -> ();
+        fn serialize_unit_variant(self, &str, u32, &str) -> ();
+        fn serialize_newtype_struct<T>(self, &str, &T) -> ();
+        fn serialize_newtype_variant<T>(self, &str, u32, &str, &T) -> ();
+        fn serialize_seq(self, Option<usize>) -> Unsupported;
+        fn serialize_tuple_struct(self, &str, usize) -> Unsupported;
+        fn serialize_tuple_variant(self, &str, u32, &str, usize)
+          -> Unsupported;
+        fn serialize_map(self, Option<usize>) -> Unsupported;
+        fn serialize_struct_variant(self, &str, u32, &str, usize)
+          -> Unsupported;
+      }
+    }
+
+    impl ser::SerializeTuple for &mut Integers {
+      type Ok = ();
+      type Error = Error;
+
+      fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+        where
+          T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+      }
+
+      fn end(self) -> Result<(), Error> {
+        Ok(())
+      }
+    }
+
+    impl ser::SerializeStruct for &mut Integers {
+      type Ok = ();
+      type Error = Error;
+
+      fn serialize_field<T>(
+          &mut self,
+          _: &'static str,
+          value: &T,
+        ) -> Result<(), Error>
+        where
+          T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+      }
+
+      fn end(self) -> Result<(), Error> {
+        Ok(())
+      }
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn resumes_from_checkpoints() {
+    use serde::de::value::{Error, SeqDeserializer};
+
+    use integers::Integers;
+
+    let f = |x: i32| {
+      if x * x < 200 {
+        Direction::Low(x)
+      } else {
+        Direction::High(-x)
+      }
+    };
+    let mut state = SearchState::new((0, 0), (100, -100));
+    let mut saved = Integers(Vec::new());
+    state.checkpoint(&mut saved).unwrap();
+    assert_eq!(saved.0, vec![0, 0, 100, -100]);
+    let mut probes = 0;
+    while let Some(x) = state.next_probe() {
+      let mut saved = Integers(Vec::new());
+      state.checkpoint(&mut saved).unwrap();
+      // The derived `Deserialize` reads a struct from a sequence of its
+      // fields, and each `(X, A)` field from a sequence of two.
+      let fields = saved.0.chunks(2).map(|pair| pair.to_vec());
+      let deserializer = SeqDeserializer::<_, Error>::new(fields);
+      state = SearchState::resume(deserializer).unwrap();
+      state.feed(f(x));
+      probes += 1;
+    }
+    assert_eq!(state.into_bracket(), binary_search((0, 0), (100, -100), f));
+    assert_eq!(probes, 7);
+  }
+}
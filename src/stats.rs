@@ -0,0 +1,467 @@
+//! Searches that record what they did.
+
+use std::fmt::{self, Display, Write};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::{
+  binary_search as search, Betweenable, Bracket, Direction, Provenance,
+  SearchResult,
+};
+
+/// A single call to the predicate, made when the bracket was `(low, high)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Probe<X> {
+  pub low: X,
+  pub x: X,
+  pub high: X,
+  pub direction: Direction<(), ()>,
+  pub duration: Duration,
+}
+
+/// The probes made by a search, in the order they were made, and the
+/// bracket `(low, high)` it finished with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats<X> {
+  pub probes: Vec<Probe<X>>,
+  pub low: X,
+  pub high: X,
+}
+
+impl<X> fmt::Display for Probe<X>
+  where
+    X: Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let side =
+      match self.direction {
+        Direction::Low(()) => "low",
+        Direction::High(()) => "high",
+      };
+    write!(
+      f,
+      "{} in ({}, {}): {}, {:?}",
+      self.x,
+      self.low,
+      self.high,
+      side,
+      self.duration,
+    )
+  }
+}
+
+impl<X> fmt::Display for Stats<X>
+  where
+    X: Display {
+  /// Formats as, for example, `22 ≤ x < 23, 7 probes`. The alternate form,
+  /// `{:#}`, follows that with a line for each probe.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let plural = if self.probes.len() == 1 { "" } else { "s" };
+    write!(
+      f,
+      "{} ≤ x < {}, {} probe{}",
+      self.low,
+      self.high,
+      self.probes.len(),
+      plural,
+    )?;
+    if f.alternate() {
+      for probe in self.probes.iter() {
+        write!(f, "\n  {}", probe)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl<X> Stats<X> {
+  pub fn probe_count(&self) -> usize {
+    self.probes.len()
+  }
+
+  /// The time spent in the predicate.
+  pub fn total_duration(&self) -> Duration {
+    self.probes.iter().map(|probe| probe.duration).sum()
+  }
+
+  /// The probe duration at quantile `q`, between 0 and 1, by nearest rank.
+  pub fn latency_percentile(&self, q: f64) -> Option<Duration> {
+    let mut durations: Vec<Duration> =
+      self.probes.iter().map(|probe| probe.duration).collect();
+    durations.sort();
+    let rank = (q.clamp(0.0, 1.0) * durations.len() as f64).ceil() as usize;
+    durations.get(rank.saturating_sub(1)).copied()
+  }
+
+  /// Count probe durations into buckets. Bucket `i` counts durations below
+  /// `bounds[i]` and at or above the bound before it; the final bucket
+  /// counts everything at or above the last bound. `bounds` must be sorted.
+  pub fn latency_histogram(&self, bounds: &[Duration]) -> Vec<usize> {
+    let mut counts = vec![0; bounds.len() + 1];
+    for probe in self.probes.iter() {
+      let bucket = bounds.partition_point(|&bound| bound <= probe.duration);
+      if let Some(count) = counts.get_mut(bucket) {
+        *count += 1;
+      }
+    }
+    counts
+  }
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is the same in every
+/// release, so hashes can be compared across builds.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+}
+
+impl<X> Stats<X>
+  where
+    X: Hash {
+  /// A hash of each probe and its direction, in order, and of the final
+  /// bracket, leaving out timings. Integers hash as their native-endian
+  /// bytes, so hashes only match between machines with the same byte
+  /// order and, for `usize`, width.
+  pub fn trace_hash(&self) -> u64 {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    for probe in self.probes.iter() {
+      probe.x.hash(&mut hasher);
+      probe.direction.hash(&mut hasher);
+    }
+    self.low.hash(&mut hasher);
+    self.high.hash(&mut hasher);
+    hasher.finish()
+  }
+}
+
+impl<X> Stats<X>
+  where
+    X: Copy + Display {
+  /// Render the probes as a Graphviz DOT graph, one node per probe showing
+  /// the bracket it narrowed, ending with the final bracket.
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph search {\n  node [shape=box];\n");
+    for (i, probe) in self.probes.iter().enumerate() {
+      let _ =
+        writeln!(
+          dot,
+          "  p{} [label=\"probe {}\\n({}, {})\"];",
+          i,
+          probe.x,
+          probe.low,
+          probe.high,
+        );
+      let label =
+        match probe.direction {
+          Direction::Low(()) => "low",
+          Direction::High(()) => "high",
+        };
+      let next =
+        if i + 1 < self.probes.len() {
+          format!("p{}", i + 1)
+        } else {
+          "result".to_string()
+        };
+      let _ = writeln!(dot, "  p{} -> {} [label=\"{}\"];", i, next, label);
+    }
+    let _ =
+      writeln!(
+        dot,
+        "  result [label=\"{} | {}\", shape=doubleoctagon];",
+        self.low,
+        self.high,
+      );
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+/// Something told about each probe of a search as it's made, and about the
+/// bracket it finishes with.
+pub trait Observer<X> {
+  /// Called after each probe.
+  fn probe(&mut self, probe: &Probe<X>);
+
+  /// Called once, when the search finishes at `(low, high)` after `probes`
+  /// probes.
+  fn finish(&mut self, low: X, high: X, probes: usize) {
+    let _ = (low, high, probes);
+  }
+}
+
+impl<X> Observer<X> for Stats<X>
+  where
+    X: Copy {
+  fn probe(&mut self, probe: &Probe<X>) {
+    self.probes.push(*probe);
+  }
+
+  fn finish(&mut self, low: X, high: X, _: usize) {
+    self.low = low;
+    self.high = high;
+  }
+}
+
+impl<X, F> Observer<X> for F
+  where
+    F: FnMut(&Probe<X>) {
+  fn probe(&mut self, probe: &Probe<X>) {
+    (self)(probe)
+  }
+}
+
+/// Like `binary_search`, but also returns a record of every probe,
+/// including how long the predicate took.
+pub fn binary_search<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    f: F,
+  ) -> (Bracket<X, A, B>, Stats<X>)
+  where
+    X: Betweenable,
+    F: FnMut(X) -> Direction<A, B> {
+  let mut stats = Stats { probes: Vec::new(), low: low.0, high: high.0 };
+  let result = binary_search_observed(low, high, &mut stats, f);
+  (result, stats)
+}
+
+/// Like `binary_search`, but returning the result with its provenance, for
+/// when how a threshold was found has to be auditable.
+pub fn binary_search_with_provenance<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    f: F,
+  ) -> SearchResult<X, A, B>
+  where
+    X: Betweenable + Hash,
+    F: FnMut(X) -> Direction<A, B> {
+  let (result, stats) = binary_search(low, high, f);
+  let provenance =
+    Provenance::new(
+      "binary_search",
+      None,
+      stats.probe_count(),
+      stats.trace_hash(),
+    );
+  SearchResult::from(result).with_provenance(provenance)
+}
+
+/// Like `binary_search`, but telling `observer` about each probe as it's
+/// made instead of keeping a record of them, for logging a long search as
+/// it goes.
+pub fn binary_search_observed<X, A, B, O, F>(
+    low: (X, A),
+    high: (X, B),
+    observer: &mut O,
+    mut f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable,
+    O: Observer<X> + ?Sized,
+    F: FnMut(X) -> Direction<A, B> {
+  let mut probes = 0;
+  let mut bracket = (low.0, high.0);
+  let result =
+    search(low, high, |x| {
+      let start = Instant::now();
+      let direction = (f)(x);
+      let duration = start.elapsed();
+      let side =
+        match direction {
+          Direction::Low(_) => Direction::Low(()),
+          Direction::High(_) => Direction::High(()),
+        };
+      let (low, high) = bracket;
+      bracket =
+        match direction {
+          Direction::Low(_) => (x, high),
+          Direction::High(_) => (low, x),
+        };
+      probes += 1;
+      observer.probe(&Probe { low, x, high, direction: side, duration });
+      direction
+    });
+  let (low, high) = bracket;
+  observer.finish(low, high, probes);
+  result
+}
+
+/// Emits a `tracing` event for each probe, with the bracket it left, and
+/// one when the search finishes.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tracer;
+
+#[cfg(feature = "tracing")]
+impl<X> Observer<X> for Tracer
+  where
+    X: Copy + fmt::Debug {
+  fn probe(&mut self, probe: &Probe<X>) {
+    let (direction, low, high) =
+      match probe.direction {
+        Direction::Low(()) => ("low", probe.x, probe.high),
+        Direction::High(()) => ("high", probe.low, probe.x),
+      };
+    tracing::trace!(
+      x = ?probe.x,
+      direction,
+      low = ?low,
+      high = ?high,
+      duration = ?probe.duration,
+      "probe",
+    );
+  }
+
+  fn finish(&mut self, low: X, high: X, probes: usize) {
+    tracing::debug!(low = ?low, high = ?high, probes, "search finished");
+  }
+}
+
+/// Like `binary_search`, but in a `tracing` span of its own, with an event
+/// for each probe, so a search inside a larger service shows up in its
+/// traces.
+#[cfg(feature = "tracing")]
+pub fn binary_search_traced<X, A, B, F>(
+    low: (X, A),
+    high: (X, B),
+    f: F,
+  ) -> Bracket<X, A, B>
+  where
+    X: Betweenable + fmt::Debug,
+    F: FnMut(X) -> Direction<A, B> {
+  let span =
+    tracing::debug_span!("binary_search", low = ?low.0, high = ?high.0);
+  let _entered = span.enter();
+  binary_search_observed(low, high, &mut Tracer, f)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn records_probes() {
+    let (result, mut stats) =
+      binary_search((0_u32, ()), (100, ()), |x| {
+        if x < 95 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(result, ((94, ()), (95, ())));
+    let xs: Vec<u32> = stats.probes.iter().map(|probe| probe.x).collect();
+    assert_eq!(xs, vec![50, 75, 87, 93, 96, 94, 95]);
+    assert!(matches!(stats.probes[0].direction, Direction::Low(())));
+    assert!(matches!(stats.probes[4].direction, Direction::High(())));
+
+    // Timings depend on the machine, so give the probes at 90 and above
+    // fixed ones.
+    let slow = Duration::from_millis(2);
+    for probe in stats.probes.iter_mut() {
+      probe.duration = if probe.x >= 90 { slow } else { Duration::ZERO };
+    }
+    assert_eq!(stats.latency_histogram(&[slow]), vec![3, 4]);
+    assert_eq!(stats.latency_percentile(1.0), Some(slow));
+    assert_eq!(stats.latency_percentile(0.0), Some(Duration::ZERO));
+    assert_eq!(stats.total_duration(), slow * 4);
+  }
+
+  #[test]
+  fn reports_probes_to_observers() {
+    let threshold = |x: u32| {
+      if x < 23 { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let mut seen = Vec::new();
+    let result =
+      binary_search_observed((0, ()), (100, ()), &mut |probe: &Probe<u32>| {
+        seen.push((probe.x, probe.direction));
+      }, threshold);
+    assert_eq!(result, ((22, ()), (23, ())));
+    assert_eq!(seen.len(), 7);
+    assert_eq!(seen[0], (50, Direction::High(())));
+    assert_eq!(seen[6], (22, Direction::Low(())));
+
+    struct Summary(Option<(u32, u32, usize)>);
+    impl Observer<u32> for Summary {
+      fn probe(&mut self, _: &Probe<u32>) {}
+
+      fn finish(&mut self, low: u32, high: u32, probes: usize) {
+        self.0 = Some((low, high, probes));
+      }
+    }
+    let mut summary = Summary(None);
+    binary_search_observed((0, ()), (100, ()), &mut summary, threshold);
+    assert_eq!(summary.0, Some((22, 23, 7)));
+  }
+
+  #[cfg(feature = "tracing")]
+  #[test]
+  fn traced_search_finds_the_transition() {
+    let result =
+      binary_search_traced((0_u32, ()), (100, ()), |x| {
+        if x < 23 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!(result, ((22, ()), (23, ())));
+  }
+
+  #[test]
+  fn records_provenance() {
+    let threshold = |t: u32| {
+      move |x| if x < t { Direction::Low(()) } else { Direction::High(()) }
+    };
+    let search = |t| {
+      binary_search_with_provenance((0, ()), (100, ()), threshold(t))
+    };
+    let result = search(23);
+    assert_eq!(result.boundary(), 23);
+    let provenance = result.provenance.unwrap();
+    assert_eq!(provenance.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(provenance.strategy, "binary_search");
+    assert_eq!(provenance.probes, 7);
+    let hash = |t| search(t).provenance.map(|p| p.trace_hash);
+    assert_eq!(hash(23), Some(provenance.trace_hash));
+    assert_ne!(hash(24), Some(provenance.trace_hash));
+  }
+
+  #[test]
+  fn renders_dot_and_text() {
+    let (_, stats) =
+      binary_search((0_u32, ()), (4, ()), |x| {
+        if x < 3 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(
+      stats.to_dot(),
+      "digraph search {\n\
+      \x20 node [shape=box];\n\
+      \x20 p0 [label=\"probe 2\\n(0, 4)\"];\n\
+      \x20 p0 -> p1 [label=\"low\"];\n\
+      \x20 p1 [label=\"probe 3\\n(2, 4)\"];\n\
+      \x20 p1 -> result [label=\"high\"];\n\
+      \x20 result [label=\"2 | 3\", shape=doubleoctagon];\n\
+      }\n",
+    );
+
+    let text = format!("{:#}", stats);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "2 ≤ x < 3, 2 probes");
+    assert!(lines[1].starts_with("  2 in (0, 4): low, "));
+    assert!(lines[2].starts_with("  3 in (2, 4): high, "));
+    let (_, stats) = binary_search((7_u32, ()), (8, ()), |_| unreachable!());
+    assert_eq!(stats.to_string(), "7 ≤ x < 8, 0 probes");
+  }
+}
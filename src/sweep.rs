@@ -0,0 +1,81 @@
+//! Finding where a monotonic function crosses each of several levels.
+
+use std::cmp::Ordering;
+
+use crate::{binary_search, Betweenable, Direction};
+
+/// For each of `targets`, find the bracket where non-decreasing `f` crosses
+/// it: the largest value at which `f` is below the target, and the
+/// smallest at which it isn't. `f` is taken to be below every target at
+/// `low` and at or above every target at `high`, so it isn't probed there.
+///
+/// The targets are searched in sorted order, median first, and each search
+/// narrows the brackets of the targets either side of it, so `k` targets
+/// over a range of `n` take about `k log(n / k)` probes rather than the
+/// `k log n` of searching for each separately. The brackets are returned in
+/// the order of `targets`.
+pub fn sweep_thresholds<X, Y, F>(
+    low: X,
+    high: X,
+    targets: &[Y],
+    mut f: F,
+  ) -> Vec<(X, X)>
+  where
+    X: Betweenable,
+    Y: PartialOrd,
+    F: FnMut(X) -> Y {
+  let mut order: Vec<usize> = (0..targets.len()).collect();
+  order.sort_by(|&a, &b| {
+    targets.get(a).partial_cmp(&targets.get(b)).unwrap_or(Ordering::Equal)
+  });
+  let mut brackets = vec![(low, high); targets.len()];
+  let mut pending = vec![(0, order.len(), low, high)];
+  while let Some((start, end, low, high)) = pending.pop() {
+    if start >= end {
+      continue;
+    }
+    let mid = start + (end - start) / 2;
+    let next = order.get(mid).and_then(|&i| Some((i, targets.get(i)?)));
+    let (index, target) =
+      match next {
+        Some(next) => next,
+        None => continue,
+      };
+    let ((l, ()), (h, ())) =
+      binary_search((low, ()), (high, ()), |x| {
+        if (f)(x) < *target {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    if let Some(bracket) = brackets.get_mut(index) {
+      *bracket = (l, h);
+    }
+    pending.push((start, mid, low, h));
+    pending.push((mid + 1, end, l, high));
+  }
+  brackets
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sweeps_many_targets() {
+    let targets: Vec<u64> = (1..=100).rev().map(|t| t * t * 97).collect();
+    let mut probes = 0;
+    let brackets =
+      sweep_thresholds(0_u64, 1_000_000, &targets, |x| {
+        probes += 1;
+        x * x
+      });
+    for (&target, &(low, high)) in targets.iter().zip(brackets.iter()) {
+      assert!(low * low < target && high * high >= target);
+      assert_eq!(high, low + 1);
+    }
+    assert!(probes < 100 * 20 / 2, "{} probes", probes);
+    assert_eq!(sweep_thresholds(0_u32, 10, &[] as &[u32], |x| x), vec![]);
+  }
+}
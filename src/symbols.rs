@@ -0,0 +1,80 @@
+//! Resolving addresses to symbols, as profilers and debuggers do.
+
+use crate::partition_point;
+
+/// The symbol an address resolved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Resolved<'a, S> {
+  /// The index of the symbol's entry in the table.
+  pub index: usize,
+  /// The symbol itself, as stored in the table.
+  pub symbol: &'a S,
+  /// How far the address is past the start of the symbol.
+  pub offset: u64,
+}
+
+/// Find the symbol containing `address` in `table`, a list of
+/// `(start, size, symbol)` entries sorted by start, whose symbols don't
+/// overlap.
+///
+/// An address is in a symbol if it's within `size` bytes of its start. A
+/// symbol with size zero, such as a label, instead extends up to the next
+/// symbol, but only if no sized symbol starts at the same address. An
+/// address in the gap after a sized symbol resolves to nothing.
+pub fn resolve<S>(
+    table: &[(u64, u64, S)],
+    address: u64,
+  ) -> Option<Resolved<'_, S>> {
+  let end =
+    partition_point(table.len(), |i| {
+      table.get(i).is_some_and(|&(start, _, _)| start <= address)
+    });
+  let start = table.get(end.checked_sub(1)?)?.0;
+  let begin =
+    partition_point(end, |i| {
+      table.get(i).is_some_and(|&(s, _, _)| s < start)
+    });
+  let offset = address - start;
+  let entries = table.get(begin..end)?.iter().enumerate().rev();
+  let mut sized = false;
+  let mut label = None;
+  for (i, (_, size, symbol)) in entries {
+    let index = begin + i;
+    if *size == 0 {
+      label = label.or(Some(Resolved { index, symbol, offset }));
+    } else if offset < *size {
+      return Some(Resolved { index, symbol, offset });
+    } else {
+      sized = true;
+    }
+  }
+  if sized { None } else { label }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resolves_addresses() {
+    let table = [
+      (0x1000, 0x20, "main"),
+      (0x1040, 0, "_loop"),
+      (0x1080, 0, "alias"),
+      (0x1080, 0x10, "helper"),
+      (0x2000, 0, "_end"),
+    ];
+    let name = |address| {
+      resolve(&table, address).map(|r| (r.symbol.to_string(), r.offset))
+    };
+    assert_eq!(name(0xfff), None);
+    assert_eq!(name(0x1000), Some(("main".to_string(), 0)));
+    assert_eq!(name(0x101f), Some(("main".to_string(), 0x1f)));
+    assert_eq!(name(0x1020), None);
+    assert_eq!(name(0x1050), Some(("_loop".to_string(), 0x10)));
+    assert_eq!(name(0x1084), Some(("helper".to_string(), 4)));
+    assert_eq!(name(0x1090), None);
+    assert_eq!(name(0x3000), Some(("_end".to_string(), 0x1000)));
+    assert_eq!(resolve(&table, 0x1080).map(|r| r.index), Some(3));
+  }
+}
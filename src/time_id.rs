@@ -0,0 +1,110 @@
+//! Searching by creation time over time-ordered IDs, such as UUIDv7s and
+//! ULIDs.
+//!
+//! Both formats put a 48-bit Unix timestamp in milliseconds in their most
+//! significant bits, so their 128-bit integer representation (as given by
+//! `Uuid::as_u128`, or `u128::from_be_bytes` on the raw bytes) sorts by
+//! creation time and can be bisected directly as a `u128`. With the `uuid`
+//! feature, `Uuid` is also `Betweenable`, by the same representation.
+
+use crate::partition_point;
+
+const TIMESTAMP_SHIFT: u32 = 80;
+const RANDOM_MASK: u128 = (1 << TIMESTAMP_SHIFT) - 1;
+const UUID_V7_VERSION: u128 = 0x7 << 76;
+const UUID_V7_VARIANT: u128 = 0b10 << 62;
+const UUID_V7_RANDOM_MASK: u128 = RANDOM_MASK & !(0xf << 76) & !(0b11 << 62);
+
+/// The creation time of a UUIDv7 or ULID, in milliseconds since the epoch.
+pub fn timestamp_millis(id: u128) -> u64 {
+  (id >> TIMESTAMP_SHIFT) as u64
+}
+
+/// The smallest and largest UUIDv7s that can be created in `millis`.
+/// Timestamps wider than 48 bits are truncated.
+pub fn uuid_v7_bounds(millis: u64) -> (u128, u128) {
+  let base = timestamp_bits(millis) | UUID_V7_VERSION | UUID_V7_VARIANT;
+  (base, base | UUID_V7_RANDOM_MASK)
+}
+
+/// The smallest and largest ULIDs that can be created in `millis`.
+/// Timestamps wider than 48 bits are truncated.
+pub fn ulid_bounds(millis: u64) -> (u128, u128) {
+  let base = timestamp_bits(millis);
+  (base, base | RANDOM_MASK)
+}
+
+/// Find the index of the first ID created at or after `millis`, where
+/// `id_at(i)` gives the `i`th of `len` IDs in increasing order.
+pub fn lower_bound_by_time<F>(len: usize, mut id_at: F, millis: u64) -> usize
+  where
+    F: FnMut(usize) -> u128 {
+  partition_point(len, |i| timestamp_millis((id_at)(i)) < millis)
+}
+
+fn timestamp_bits(millis: u64) -> u128 {
+  u128::from(millis & 0xffff_ffff_ffff) << TIMESTAMP_SHIFT
+}
+
+/// UUIDs are bisected as their 128-bit integers, so the midpoint of two
+/// UUIDv7s sorts between them but needn't have the version and variant bits
+/// of a UUIDv7 itself.
+#[cfg(feature = "uuid")]
+impl crate::Betweenable for uuid::Uuid {
+  fn between(low: Self, high: Self) -> Option<Self> {
+    u128::between(low.as_u128(), high.as_u128()).map(uuid::Uuid::from_u128)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn uuid_v7_bounds_are_valid() {
+    let (low, high) = uuid_v7_bounds(1_700_000_000_000);
+    assert_eq!(format!("{:032x}", low), "018bcfe5680070008000000000000000");
+    assert_eq!(format!("{:032x}", high), "018bcfe568007fffbfffffffffffffff");
+    assert_eq!(timestamp_millis(low), 1_700_000_000_000);
+    assert_eq!(timestamp_millis(high), 1_700_000_000_000);
+    assert_eq!(ulid_bounds(1).1 + 1, ulid_bounds(2).0);
+  }
+
+  #[test]
+  fn lower_bound_by_time_test() {
+    let ids: Vec<u128> =
+      [10, 10, 20, 30, 30, 30, 40]
+        .iter()
+        .enumerate()
+        .map(|(i, &millis)| ulid_bounds(millis).0 + i as u128)
+        .collect();
+    let find = |millis| lower_bound_by_time(ids.len(), |i| ids[i], millis);
+    assert_eq!(find(0), 0);
+    assert_eq!(find(10), 0);
+    assert_eq!(find(11), 2);
+    assert_eq!(find(30), 3);
+    assert_eq!(find(41), 7);
+  }
+
+  #[cfg(feature = "uuid")]
+  #[test]
+  fn searches_uuids_by_time() {
+    use crate::{binary_search, Betweenable, Direction};
+    use uuid::Uuid;
+
+    // The first ID created at or after 1_700_000_000_123.
+    let cutoff = 1_700_000_000_123;
+    let first = Uuid::from_u128(uuid_v7_bounds(0).0);
+    let last = Uuid::from_u128(uuid_v7_bounds(u64::MAX).1);
+    let (_, (found, ())) =
+      binary_search((first, ()), (last, ()), |id: Uuid| {
+        if timestamp_millis(id.as_u128()) < cutoff {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(found.as_u128(), ulid_bounds(cutoff).0);
+    assert_eq!(Uuid::between(Uuid::nil(), Uuid::from_u128(1)), None);
+  }
+}
@@ -0,0 +1,134 @@
+//! Bisection to a tolerance, as in root finding, rather than to adjacent
+//! points.
+
+use crate::{Bracket, Direction};
+
+/// Types that can be halved, giving a midpoint between any two values.
+pub trait Halve: Copy + PartialOrd {
+  /// A point between `low` and `high`, which may round to either of them
+  /// when they're close.
+  fn midpoint(low: Self, high: Self) -> Self;
+
+  /// `high - low`, or `None` if that isn't representable.
+  fn width(low: Self, high: Self) -> Option<Self>;
+}
+
+macro_rules! impl_halve_int {
+  ($($t:ty),*) => {
+    $(
+      impl Halve for $t {
+        fn midpoint(low: Self, high: Self) -> Self {
+          (low >> 1) + (high >> 1) + (low & high & 1)
+        }
+
+        fn width(low: Self, high: Self) -> Option<Self> {
+          high.checked_sub(low)
+        }
+      }
+    )*
+  };
+}
+
+macro_rules! impl_halve_float {
+  ($($t:ty),*) => {
+    $(
+      impl Halve for $t {
+        fn midpoint(low: Self, high: Self) -> Self {
+          low / 2.0 + high / 2.0
+        }
+
+        fn width(low: Self, high: Self) -> Option<Self> {
+          Some(high - low).filter(|width| width.is_finite())
+        }
+      }
+    )*
+  };
+}
+
+impl_halve_int!(u8, u16, u32, u64, u128, usize);
+impl_halve_int!(i8, i16, i32, i64, i128, isize);
+impl_halve_float!(f32, f64);
+
+/// Like `binary_search`, but stopping as soon as `high - low <= eps`.
+///
+/// Also stops if the midpoint rounds to one of the bounds, so an `eps` too
+/// small for the precision of `X` can't make it loop forever. A bracket too
+/// wide for its width to be representable in `X` is always wider than
+/// `eps`. Returns the bracket and its width, or `None` for the width if
+/// it's still too wide to represent. For fixed-point numbers, search their
+/// underlying integers with `eps` in the same units.
+pub fn binary_search_with_tolerance<X, A, B, F>(
+    mut low: (X, A),
+    mut high: (X, B),
+    eps: X,
+    mut f: F,
+  ) -> (Bracket<X, A, B>, Option<X>)
+  where
+    X: Halve,
+    F: FnMut(X) -> Direction<A, B> {
+  while X::width(low.0, high.0).is_none_or(|width| width > eps) {
+    let x = X::midpoint(low.0, high.0);
+    if x <= low.0 || high.0 <= x {
+      break;
+    }
+    match (f)(x) {
+      Direction::Low(a) => {
+        low = (x, a);
+      },
+      Direction::High(b) => {
+        high = (x, b);
+      },
+    }
+  }
+  let width = X::width(low.0, high.0);
+  ((low, high), width)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stops_within_tolerance() {
+    let cube_root_of_10 = |x: f64| {
+      if x * x * x < 10.0 {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    };
+    let search = |eps| {
+      binary_search_with_tolerance((0.0, ()), (10.0, ()), eps, cube_root_of_10)
+    };
+    let (((low, ()), (high, ())), width) = search(1e-6);
+    let width = width.unwrap();
+    assert!(width <= 1e-6 && width > 1e-7, "width {}", width);
+    assert!(low < 10f64.cbrt() && 10f64.cbrt() <= high);
+
+    let (_, width) = search(0.0);
+    assert!(width.is_some_and(|width| width > 0.0 && width < 1e-14));
+
+    // Hundredths, as fixed-point integers.
+    let ((_, (high, ())), width) =
+      binary_search_with_tolerance((-12800_i16, ()), (12700, ()), 5, |x| {
+        if x < -333 {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert!(width <= Some(5) && (-333..-328).contains(&high));
+
+    // Wider than `i32` can represent.
+    let ((low, high), width) =
+      binary_search_with_tolerance((i32::MIN, ()), (i32::MAX, ()), 0, |x| {
+        if x < 1000 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert_eq!((low.0, high.0, width), (999, 1000, Some(1)));
+    let (_, width) =
+      binary_search_with_tolerance((f64::MIN, ()), (f64::MAX, ()), 1.0, |x| {
+        if x < 0.5 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    assert!(width.is_some_and(|width| width <= 1.0));
+  }
+}
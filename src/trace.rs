@@ -0,0 +1,262 @@
+//! A stable binary format for the traces of searches, for saving a long
+//! search, such as a bisection spread over many machines, and resuming it
+//! with a later build.
+//!
+//! A trace is a `stats::Stats`: the bracket a search has reached and the
+//! probes it made on the way. It's encoded as:
+//!
+//! ```text
+//! magic       4 bytes, "BSTR"
+//! version     varint, currently 1
+//! low, high   varints, the bracket
+//! count       varint, the number of probes
+//! probes      for each probe, in order:
+//!   low, x, high  varints, the probe and the bracket it was made in
+//!   direction     1 byte, 0 for low and 1 for high
+//!   duration      varints, whole seconds and then nanoseconds
+//! ```
+//!
+//! Varints are LEB128, with signed types zigzag encoded, so the format
+//! doesn't depend on the endianness or word size of the machine that wrote
+//! it. Unlike `checkpoint`, which follows `postcard` in writing 8-bit
+//! values as single bytes, a trace writes values of every width as
+//! varints, so it can be read as any integer type with the same signedness
+//! that its values fit in.
+//!
+//! Later releases of this crate will keep reading every earlier version,
+//! and brackets saved by `checkpoint::encode`, which has no header, can be
+//! read with `from_checkpoint`.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::checkpoint::{self, read_varint, write_varint, Varint};
+use crate::stats::{Probe, Stats};
+use crate::Direction;
+
+/// The bytes every trace starts with.
+pub const MAGIC: [u8; 4] = *b"BSTR";
+
+/// The version of the format that `encode` writes.
+pub const VERSION: u32 = 1;
+
+/// Why a trace couldn't be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraceError {
+  /// The bytes don't start with `MAGIC`.
+  NotATrace,
+  /// The trace was written by a later version of the format.
+  UnsupportedVersion(u128),
+  /// The trace ends part of the way through.
+  Truncated,
+  /// A value doesn't fit the type it's read as, or a field isn't valid.
+  Invalid,
+}
+
+impl fmt::Display for TraceError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TraceError::NotATrace => write!(f, "not a search trace"),
+      TraceError::UnsupportedVersion(version) => {
+        write!(f, "unsupported trace version {}", version)
+      },
+      TraceError::Truncated => write!(f, "trace is truncated"),
+      TraceError::Invalid => write!(f, "trace is invalid"),
+    }
+  }
+}
+
+impl std::error::Error for TraceError {}
+
+fn push_varint(out: &mut Vec<u8>, x: u128) {
+  let mut buffer = [0; 19];
+  let n = write_varint(x, &mut buffer).unwrap_or_default();
+  out.extend_from_slice(buffer.get(..n).unwrap_or_default());
+}
+
+/// Write `stats` in the current version of the format.
+pub fn encode<X>(stats: &Stats<X>) -> Vec<u8>
+  where
+    X: Varint {
+  let mut out = MAGIC.to_vec();
+  push_varint(&mut out, u128::from(VERSION));
+  push_varint(&mut out, stats.low.to_varint());
+  push_varint(&mut out, stats.high.to_varint());
+  push_varint(&mut out, stats.probes.len() as u128);
+  for probe in stats.probes.iter() {
+    push_varint(&mut out, probe.low.to_varint());
+    push_varint(&mut out, probe.x.to_varint());
+    push_varint(&mut out, probe.high.to_varint());
+    out.push(
+      match probe.direction {
+        Direction::Low(()) => 0,
+        Direction::High(()) => 1,
+      }
+    );
+    push_varint(&mut out, u128::from(probe.duration.as_secs()));
+    push_varint(&mut out, u128::from(probe.duration.subsec_nanos()));
+  }
+  out
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn byte(&mut self) -> Result<u8, TraceError> {
+    let (&byte, rest) =
+      self.bytes.split_first().ok_or(TraceError::Truncated)?;
+    self.bytes = rest;
+    Ok(byte)
+  }
+
+  fn varint(&mut self) -> Result<u128, TraceError> {
+    // A varint that runs off the end is truncated; one that is too long for
+    // a `u128` is invalid.
+    let (x, n) =
+      read_varint(self.bytes).ok_or_else(|| {
+        if self.bytes.iter().all(|&byte| byte & 0x80 != 0) {
+          TraceError::Truncated
+        } else {
+          TraceError::Invalid
+        }
+      })?;
+    self.bytes = self.bytes.get(n..).unwrap_or_default();
+    Ok(x)
+  }
+
+  fn value<T>(&mut self) -> Result<T, TraceError>
+    where
+      T: Varint {
+    T::from_varint(self.varint()?).ok_or(TraceError::Invalid)
+  }
+}
+
+/// Read a trace written by `encode`, in this or any earlier version of the
+/// format.
+pub fn decode<X>(bytes: &[u8]) -> Result<Stats<X>, TraceError>
+  where
+    X: Varint {
+  let body = bytes.strip_prefix(&MAGIC[..]).ok_or(TraceError::NotATrace)?;
+  let mut reader = Reader { bytes: body };
+  match reader.varint()? {
+    1 => {
+      let low = reader.value()?;
+      let high = reader.value()?;
+      let count: usize = reader.value()?;
+      // Each probe takes at least six bytes, which bounds the allocation
+      // for a corrupt count.
+      let mut probes = Vec::with_capacity(count.min(reader.bytes.len() / 6));
+      for _ in 0..count {
+        let low = reader.value()?;
+        let x = reader.value()?;
+        let high = reader.value()?;
+        let direction =
+          match reader.byte()? {
+            0 => Direction::Low(()),
+            1 => Direction::High(()),
+            _ => return Err(TraceError::Invalid),
+          };
+        let seconds = reader.value()?;
+        let nanos: u32 = reader.value()?;
+        if nanos >= 1_000_000_000 {
+          return Err(TraceError::Invalid);
+        }
+        let duration = Duration::new(seconds, nanos);
+        probes.push(Probe { low, x, high, direction, duration });
+      }
+      Ok(Stats { probes, low, high })
+    },
+    version => Err(TraceError::UnsupportedVersion(version)),
+  }
+}
+
+/// Read a bracket written by `checkpoint::encode` as a trace with no
+/// probes.
+pub fn from_checkpoint<X>(bytes: &[u8]) -> Result<Stats<X>, TraceError>
+  where
+    X: Varint {
+  let (low, high) = checkpoint::decode(bytes).ok_or(TraceError::Invalid)?;
+  Ok(Stats { probes: Vec::new(), low, high })
+}
+
+/// A trace with its version, for storing with `serde` instead of `encode`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Versioned<X> {
+  pub version: u32,
+  pub stats: Stats<X>,
+}
+
+#[cfg(feature = "serde")]
+impl<X> Versioned<X> {
+  /// `stats`, marked with the current version.
+  pub fn new(stats: Stats<X>) -> Self {
+    Versioned { version: VERSION, stats }
+  }
+
+  /// The trace, if its version can be read by this release.
+  pub fn into_stats(self) -> Result<Stats<X>, TraceError> {
+    match self.version {
+      1 => Ok(self.stats),
+      version => Err(TraceError::UnsupportedVersion(u128::from(version))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_every_version() {
+    let (_, stats) =
+      crate::stats::binary_search((-1000_i32, ()), (1000, ()), |x| {
+        if x < 17 { Direction::Low(()) } else { Direction::High(()) }
+      });
+    let bytes = encode(&stats);
+    assert_eq!(decode::<i32>(&bytes), Ok(stats.clone()));
+    assert_eq!(decode::<i64>(&bytes).map(|s| (s.low, s.high)), Ok((16, 17)));
+    assert_eq!(decode::<i8>(&bytes), Err(TraceError::Invalid));
+
+    // Version 1, as written by the first release with traces.
+    let probe = |low, x, high, direction, millis| {
+      let duration = Duration::from_millis(millis);
+      Probe { low, x, high, direction, duration }
+    };
+    let version_1 = Stats {
+      probes: vec![
+        probe(0_u16, 500, 1000, Direction::High(()), 1500),
+        probe(0, 250, 500, Direction::Low(()), 2),
+      ],
+      low: 250,
+      high: 500,
+    };
+    let bytes = [
+      b'B', b'S', b'T', b'R', 1, 0xfa, 0x01, 0xf4, 0x03, 2,
+      0, 0xf4, 0x03, 0xe8, 0x07, 1, 1, 0x80, 0xca, 0xb5, 0xee, 0x01,
+      0, 0xfa, 0x01, 0xf4, 0x03, 0, 0, 0x80, 0x89, 0x7a,
+    ];
+    assert_eq!(encode(&version_1), bytes);
+    assert_eq!(decode(&bytes), Ok(version_1));
+
+    // Brackets saved by `checkpoint`, which has no header.
+    let mut saved = [0; checkpoint::MAX_LEN];
+    let n = checkpoint::encode(3_u64, 300, &mut saved).unwrap();
+    let migrated = from_checkpoint::<u64>(&saved[..n]).unwrap();
+    assert_eq!((migrated.low, migrated.high), (3, 300));
+    assert!(migrated.probes.is_empty());
+    let mut bytes_saved = [0; checkpoint::MAX_LEN];
+    let m = checkpoint::encode(-3_i8, 100, &mut bytes_saved).unwrap();
+    let migrated = from_checkpoint::<i8>(&bytes_saved[..m]).unwrap();
+    assert_eq!((migrated.low, migrated.high), (-3, 100));
+
+    let mut later = bytes;
+    later[4] = 2;
+    assert_eq!(decode::<u16>(&later), Err(TraceError::UnsupportedVersion(2)));
+    assert_eq!(decode::<u16>(&bytes[..20]), Err(TraceError::Truncated));
+    assert_eq!(decode::<u16>(&saved[..n]), Err(TraceError::NotATrace));
+  }
+}
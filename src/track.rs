@@ -0,0 +1,155 @@
+//! Remembering the results of searches that are run again and again, such
+//! as a nightly job finding the load a service can take, so that each run
+//! can start from where the last one finished.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::ops::Bound;
+use std::str::FromStr;
+
+use crate::gallop::{gallop_down, gallop_up, Steppable};
+use crate::{binary_search, Direction};
+
+/// The last bracket found by each labelled search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Thresholds<X> {
+  brackets: BTreeMap<String, (X, X)>,
+}
+
+impl<X> Default for Thresholds<X> {
+  fn default() -> Self {
+    Thresholds { brackets: BTreeMap::new() }
+  }
+}
+
+impl<X> Thresholds<X>
+  where
+    X: Steppable {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The bracket the last search called `label` finished with.
+  pub fn get(&self, label: &str) -> Option<(X, X)> {
+    self.brackets.get(label).copied()
+  }
+
+  /// Search between `low` and `high`, as with `binary_search`, starting
+  /// from the last result for `label` if there is one, and record the
+  /// result.
+  ///
+  /// The old bracket is probed first. If the transition hasn't moved, that
+  /// is all the search takes; otherwise it gallops away from the old
+  /// bracket towards the transition, taking `O(log d)` probes where `d` is
+  /// how far the transition moved.
+  pub fn search<F>(&mut self, label: &str, low: X, high: X, mut f: F) -> (X, X)
+    where
+      F: FnMut(X) -> Direction<(), ()> {
+    let mut low = low;
+    let mut high = high;
+    let mut hinted = None;
+    if let Some((old_low, old_high)) = self.get(label) {
+      for &x in [old_low, old_high].iter() {
+        if low < x && x < high {
+          match (f)(x) {
+            Direction::Low(()) => {
+              low = x;
+              hinted = Some(true);
+            },
+            Direction::High(()) => {
+              high = x;
+              hinted = Some(false);
+              break;
+            },
+          }
+        }
+      }
+    }
+    let (low, high) =
+      match hinted {
+        Some(true) => {
+          gallop_up((low, ()), Bound::Excluded(high), &mut f)
+            .unwrap_or_else(|low| (low, (high, ())))
+        },
+        Some(false) => {
+          gallop_down((high, ()), Bound::Excluded(low), &mut f)
+            .unwrap_or_else(|high| ((low, ()), high))
+        },
+        None => ((low, ()), (high, ())),
+      };
+    let ((low, ()), (high, ())) = binary_search(low, high, f);
+    self.brackets.insert(label.to_string(), (low, high));
+    (low, high)
+  }
+}
+
+impl<X> Thresholds<X>
+  where
+    X: Steppable + Display + FromStr {
+  /// Write the brackets out as text, one `label low high` line each, to be
+  /// read back with `from_text`. Labels must not contain whitespace.
+  pub fn to_text(&self) -> String {
+    let mut text = String::new();
+    for (label, (low, high)) in self.brackets.iter() {
+      let _ = writeln!(text, "{} {} {}", label, low, high);
+    }
+    text
+  }
+
+  /// Read brackets written by `to_text`, or `None` if a line is malformed.
+  pub fn from_text(text: &str) -> Option<Self> {
+    let mut brackets = BTreeMap::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+      let mut words = line.split_whitespace();
+      let label = words.next()?.to_string();
+      let low = words.next()?.parse().ok()?;
+      let high = words.next()?.parse().ok()?;
+      if words.next().is_some() {
+        return None;
+      }
+      brackets.insert(label, (low, high));
+    }
+    Some(Thresholds { brackets })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn threshold(
+      t: u32,
+      probes: &mut u32,
+    ) -> impl FnMut(u32) -> Direction<(), ()> + '_ {
+    move |x| {
+      *probes += 1;
+      if x < t {
+        Direction::Low(())
+      } else {
+        Direction::High(())
+      }
+    }
+  }
+
+  #[test]
+  fn reuses_previous_brackets() {
+    let mut thresholds = Thresholds::new();
+    let mut probes = 0;
+    let result =
+      thresholds.search("qps", 0, 1_000_000, threshold(4321, &mut probes));
+    assert_eq!(result, (4320, 4321));
+    assert_eq!(probes, 20);
+
+    let mut thresholds = Thresholds::from_text(&thresholds.to_text()).unwrap();
+    assert_eq!(thresholds.get("qps"), Some((4320, 4321)));
+    let reruns = [(4321, 2), (4330, 10), (4000, 22), (4321, 20)];
+    for &(t, max_probes) in reruns.iter() {
+      let mut probes = 0;
+      let result =
+        thresholds.search("qps", 0, 1_000_000, threshold(t, &mut probes));
+      assert_eq!(result, (t - 1, t));
+      assert!(probes <= max_probes, "{} probes for {}", probes, t);
+    }
+    assert_eq!(Thresholds::<u32>::from_text("qps 1"), None);
+  }
+}
@@ -0,0 +1,139 @@
+//! Uniform binary search over a fixed table.
+//!
+//! This is Shar's variant of uniform binary search: the power-of-two steps
+//! are computed once for the length of the table, and each probe then only
+//! adds a step to the current position, with no midpoint arithmetic.
+
+/// A sorted slice with its precomputed step table.
+#[derive(Debug, Clone)]
+pub struct Uniform<'a, T> {
+  slice: &'a [T],
+  deltas: Vec<usize>,
+}
+
+impl<'a, T> Uniform<'a, T>
+  where
+    T: Ord {
+  /// Precompute the steps for `slice`, which must be sorted.
+  pub fn new(slice: &'a [T]) -> Self {
+    let mut deltas = Vec::new();
+    if let Some(top) = slice.len().checked_next_power_of_two() {
+      let mut step = if top > slice.len() { top >> 1 } else { top };
+      while step > 0 {
+        deltas.push(step);
+        step >>= 1;
+      }
+    }
+    Uniform { slice, deltas }
+  }
+
+  pub fn as_slice(&self) -> &'a [T] {
+    self.slice
+  }
+
+  /// Find the index of the first element that is not less than `key`.
+  pub fn lower_bound(&self, key: &T) -> usize {
+    self.search(|x| x < key)
+  }
+
+  /// Find the index of the first element that is greater than `key`.
+  pub fn upper_bound(&self, key: &T) -> usize {
+    self.search(|x| x <= key)
+  }
+
+  fn search<P>(&self, is_low: P) -> usize
+    where
+      P: Fn(&T) -> bool {
+    let mut deltas = self.deltas.iter();
+    let first =
+      match deltas.next() {
+        None => return 0,
+        Some(&first) => first,
+      };
+    let below = |i: usize| self.slice.get(i).is_some_and(&is_low);
+
+    // The first probe picks whichever of the windows aligned to the start
+    // and the end of the slice contains the answer; from then on every
+    // probe is in range.
+    let mut position =
+      if below(first - 1) {
+        self.slice.len() - first
+      } else {
+        0
+      };
+    for &delta in deltas {
+      if below(position + delta - 1) {
+        position += delta;
+      }
+    }
+    if below(position) {
+      position + 1
+    } else {
+      position
+    }
+  }
+}
+
+/// Find the index of the first element of `slice` that is not less than
+/// `key`, without a precomputed table.
+///
+/// The window is a power of two wide, so each step just halves a size
+/// register and conditionally adds it to the base, without branching.
+/// Lengths that aren't a power of two are handled by a first probe that
+/// picks between the windows aligned to either end of the slice.
+pub fn lower_bound<T>(slice: &[T], key: &T) -> usize
+  where
+    T: Ord {
+  let len = slice.len();
+  if len == 0 {
+    return 0;
+  }
+  let below = |i: usize| slice.get(i).is_some_and(|x| x < key);
+  let mut size =
+    if len.is_power_of_two() {
+      len
+    } else {
+      len.next_power_of_two() >> 1
+    };
+  let mut base =
+    if size < len && below(size - 1) {
+      len - size
+    } else {
+      0
+    };
+  while size > 1 {
+    size /= 2;
+    let next = base + size;
+    // SAFETY: the window `base..base + 2 * size` is within the slice.
+    let x = unsafe { slice.get_unchecked(next - 1) };
+    base = std::hint::select_unpredictable(x < key, next, base);
+  }
+  base + below(base) as usize
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_partition_point() {
+    for len in 0..70 {
+      let values: Vec<u32> = (0..len).map(|i| i / 3 * 2).collect();
+      let uniform = Uniform::new(&values);
+      for key in 0..=len {
+        assert_eq!(
+          uniform.lower_bound(&key),
+          values.partition_point(|&x| x < key),
+        );
+        assert_eq!(
+          lower_bound(&values, &key),
+          values.partition_point(|&x| x < key),
+        );
+        assert_eq!(
+          uniform.upper_bound(&key),
+          values.partition_point(|&x| x <= key),
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,132 @@
+//! Finding the minimum of a unimodal function, one that decreases and then
+//! increases, such as the cost of a configuration as one parameter is
+//! tuned.
+
+use crate::Betweenable;
+
+/// The minimum found by `ternary_search`, with the values either side of it
+/// that show it's a minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Minimum<X, Y> {
+  pub x: X,
+  pub value: Y,
+  /// The point just below `x`, and its value, unless `x` is the lower
+  /// bound of the search.
+  pub below: Option<(X, Y)>,
+  /// The point just above `x`, and its value, unless `x` is the upper
+  /// bound of the search.
+  pub above: Option<(X, Y)>,
+}
+
+/// Find the point in `low..=high` where `f` is smallest.
+///
+/// `f` must be unimodal: strictly decreasing up to its minimum, and
+/// strictly increasing after it. To find a maximum instead, wrap the values
+/// in `std::cmp::Reverse`. Each probe is placed in the larger of the two
+/// parts of the bracket either side of the best point so far, as in a
+/// golden-section search, so the bracket shrinks geometrically.
+pub fn ternary_search<X, Y, F>(low: X, high: X, mut f: F) -> Minimum<X, Y>
+  where
+    X: Betweenable + PartialOrd,
+    Y: PartialOrd,
+    F: FnMut(X) -> Y {
+  let mut a = (low, (f)(low));
+  let mut c = (high, (f)(high));
+  // First find a point below both ends, homing in on an end if there
+  // isn't one.
+  let mut b =
+    loop {
+      let x =
+        match X::between(a.0, c.0) {
+          Some(x) => x,
+          None => {
+            // If an end has moved, the other is smaller, so a minimum here
+            // is at one of the bounds of the search.
+            return
+              if a.1 <= c.1 {
+                Minimum { x: a.0, value: a.1, below: None, above: Some(c) }
+              } else {
+                Minimum { x: c.0, value: c.1, below: Some(a), above: None }
+              };
+          },
+        };
+      let y = (f)(x);
+      if a.1 < y {
+        c = (x, y);
+      } else if c.1 < y {
+        a = (x, y);
+      } else {
+        break (x, y);
+      }
+    };
+  // Then narrow the bracket around it.
+  loop {
+    let below = X::between(a.0, b.0).map(|x| (x, true));
+    let above = X::between(b.0, c.0).map(|x| (x, false));
+    let probe =
+      if X::between(a.0, c.0).is_some_and(|middle| middle <= b.0) {
+        below.or(above)
+      } else {
+        above.or(below)
+      };
+    let (x, is_below) =
+      match probe {
+        Some(probe) => probe,
+        None => {
+          return
+            Minimum { x: b.0, value: b.1, below: Some(a), above: Some(c) };
+        },
+      };
+    let y = (f)(x);
+    match (is_below, y < b.1) {
+      (true, true) => {
+        c = b;
+        b = (x, y);
+      },
+      (true, false) => {
+        a = (x, y);
+      },
+      (false, true) => {
+        a = b;
+        b = (x, y);
+      },
+      (false, false) => {
+        c = (x, y);
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cmp::Reverse;
+
+  #[test]
+  fn finds_minimum_with_neighbours() {
+    let mut probes = 0;
+    let minimum =
+      ternary_search(0_i64, 1000, |x| {
+        probes += 1;
+        (x - 37) * (x - 37)
+      });
+    assert_eq!(
+      minimum,
+      Minimum { x: 37, value: 0, below: Some((36, 1)), above: Some((38, 1)) },
+    );
+    assert!(probes <= 30, "{} probes", probes);
+
+    for &peak in [0, 1, 2, 50, 98, 99, 100].iter() {
+      let maximum =
+        ternary_search(0_u32, 100, |x| Reverse(1000 - x.abs_diff(peak)));
+      assert_eq!(maximum.x, peak);
+      let minimum = ternary_search(0_u32, 100, |x| x.abs_diff(peak));
+      assert_eq!(minimum.x, peak);
+      assert_eq!(minimum.below.map(|(x, _)| x), peak.checked_sub(1));
+      assert_eq!(
+        minimum.above.map(|(x, _)| x),
+        Some(peak + 1).filter(|&x| x <= 100),
+      );
+    }
+  }
+}
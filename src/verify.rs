@@ -0,0 +1,100 @@
+//! Proof harnesses for the core invariants.
+//!
+//! The harnesses are checked with [Kani](https://github.com/model-checking/kani):
+//!
+//! ```text
+//! cargo kani --features verify
+//! ```
+//!
+//! They establish that
+//!
+//! * `between` only ever returns a value strictly inside `(low, high)`, and
+//!   returns `None` exactly when there is no such value,
+//! * `binary_search` terminates, and
+//! * the bracket it returns is adjacent and consistent with the predicate.
+//!
+//! The same properties are checked exhaustively over `u8` by the unit tests
+//! in this module, so `cargo test --features verify` exercises them without
+//! Kani installed.
+
+#[cfg(kani)]
+mod proofs {
+  use crate::{binary_search, Betweenable, Direction};
+
+  #[kani::proof]
+  fn between_is_strictly_inside() {
+    let low: u64 = kani::any();
+    let high: u64 = kani::any();
+    match u64::between(low, high) {
+      Some(mid) => assert!(low < mid && mid < high),
+      None => assert!(high <= low || high - low == 1),
+    }
+  }
+
+  #[kani::proof]
+  fn signed_between_is_strictly_inside() {
+    let low: i64 = kani::any();
+    let high: i64 = kani::any();
+    match i64::between(low, high) {
+      Some(mid) => assert!(low < mid && mid < high),
+      None => assert!(high <= low || high - low == 1),
+    }
+  }
+
+  #[kani::proof]
+  #[kani::unwind(10)]
+  fn binary_search_brackets_the_transition() {
+    let low: u8 = kani::any();
+    let high: u8 = kani::any();
+    let threshold: u8 = kani::any();
+    kani::assume(low < threshold && threshold <= high);
+    let ((largest_low, ()), (smallest_high, ())) =
+      binary_search((low, ()), (high, ()), |x| {
+        assert!(low < x && x < high);
+        if x < threshold {
+          Direction::Low(())
+        } else {
+          Direction::High(())
+        }
+      });
+    assert_eq!(largest_low + 1, smallest_high);
+    assert_eq!(smallest_high, threshold);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{binary_search, Betweenable, Direction};
+
+  #[test]
+  fn between_is_strictly_inside() {
+    for low in 0..=u8::MAX {
+      for high in 0..=u8::MAX {
+        match u8::between(low, high) {
+          Some(mid) => assert!(low < mid && mid < high),
+          None => assert!(high <= low || high - low == 1),
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn binary_search_brackets_the_transition() {
+    for low in 0..u8::MAX {
+      for threshold in low + 1..=u8::MAX {
+        let high = threshold.saturating_add(low % 7);
+        let ((largest_low, ()), (smallest_high, ())) =
+          binary_search((low, ()), (high, ()), |x| {
+            assert!(low < x && x < high);
+            if x < threshold {
+              Direction::Low(())
+            } else {
+              Direction::High(())
+            }
+          });
+        assert_eq!(largest_low + 1, smallest_high);
+        assert_eq!(smallest_high, threshold);
+      }
+    }
+  }
+}